@@ -0,0 +1,227 @@
+//! A small ssh-agent-like credential holder.
+//!
+//! `nsg agent start` keeps credentials in memory and answers requests on a
+//! Unix socket (`~/.nsg/agent.sock`, or `$NSG_AGENT_SOCK`) until its lifetime
+//! expires. Other `nsg` invocations ask the agent before reading the
+//! credentials file, so the password never has to be stored on disk.
+
+use crate::config::{config_dir, Credentials};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const AGENT_SOCKET: &str = "agent.sock";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentStatus {
+    pub username: String,
+    /// Profile the credentials were loaded from; `None` for the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Path of the agent socket, honouring `NSG_AGENT_SOCK`
+pub fn socket_path() -> Result<PathBuf> {
+    match std::env::var_os("NSG_AGENT_SOCK") {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(config_dir()?.join(AGENT_SOCKET)),
+    }
+}
+
+/// Credentials of `profile` held by a running agent, if any
+pub fn fetch_credentials(profile: Option<&str>) -> Option<Credentials> {
+    let reply = match profile {
+        Some(profile) => request(&format!("GET {}", profile)),
+        None => request("GET"),
+    }
+    .ok()?;
+    serde_json::from_str(&reply).ok()
+}
+
+pub fn status() -> Result<AgentStatus> {
+    let reply = request("STATUS")?;
+    serde_json::from_str(&reply).context("Invalid reply from agent")
+}
+
+pub fn stop() -> Result<()> {
+    request("STOP").map(|_| ())
+}
+
+#[cfg(unix)]
+fn request(command: &str) -> Result<String> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let path = socket_path()?;
+    let mut stream = UnixStream::connect(&path)
+        .with_context(|| format!("No agent listening on {}", path.display()))?;
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(2)))?;
+
+    writeln!(stream, "{}", command)?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+
+    match reply.trim_end().strip_prefix("ERR ") {
+        Some(message) => anyhow::bail!("Agent error: {}", message),
+        None => Ok(reply.trim_end().to_string()),
+    }
+}
+
+#[cfg(not(unix))]
+fn request(_command: &str) -> Result<String> {
+    anyhow::bail!("The nsg agent is only supported on Unix systems")
+}
+
+/// Serve `credentials` of `profile` on the agent socket until `expires_at` or `STOP`
+#[cfg(unix)]
+pub fn serve(
+    credentials: Credentials,
+    profile: Option<String>,
+    expires_at: DateTime<Utc>,
+) -> Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::UnixListener;
+    use std::sync::Arc;
+
+    let path = socket_path()?;
+
+    if path.exists() {
+        if request("STATUS").is_ok() {
+            anyhow::bail!("An agent is already running on {}", path.display());
+        }
+        // Left behind by an agent that didn't shut down cleanly
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+    }
+
+    if let Some(dir) = path.parent().filter(|dir| !dir.exists()) {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    // Create the socket owner-only from the start rather than chmod'ing it
+    // after other users could already have connected
+    let umask = unsafe { libc::umask(0o177) };
+    let listener = UnixListener::bind(&path);
+    unsafe { libc::umask(umask) };
+    let listener = listener.with_context(|| format!("Failed to listen on {}", path.display()))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .context("Failed to set agent socket permissions")?;
+
+    let lifetime = (expires_at - Utc::now()).to_std().unwrap_or_default();
+    let expiry_path = path.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(lifetime);
+        let _ = std::fs::remove_file(&expiry_path);
+        std::process::exit(0);
+    });
+
+    let agent = Arc::new(Agent {
+        status: serde_json::to_string(&AgentStatus {
+            username: credentials.username.clone(),
+            profile: profile.clone(),
+            expires_at,
+        })?,
+        secret: serde_json::to_string(&credentials)?,
+        profile,
+        path,
+    });
+    let uid = unsafe { libc::geteuid() };
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        // Root can connect despite the socket's mode; nobody else but us
+        if peer_uid(&stream) != Some(uid) {
+            continue;
+        }
+
+        // One connection per thread, so a client that never sends its
+        // command can't hold up everyone else
+        let agent = Arc::clone(&agent);
+        std::thread::spawn(move || agent.answer(stream));
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+struct Agent {
+    status: String,
+    secret: String,
+    profile: Option<String>,
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl Agent {
+    fn answer(&self, mut stream: std::os::unix::net::UnixStream) {
+        use std::io::{BufRead, BufReader, Write};
+
+        let _ = stream.set_read_timeout(Some(std::time::Duration::from_secs(5)));
+        let mut line = String::new();
+        if BufReader::new(&stream).read_line(&mut line).is_err() {
+            return;
+        }
+
+        let mut words = line.split_whitespace();
+        let reply = match (words.next(), words.next()) {
+            (Some("GET"), profile) if profile == self.profile.as_deref() => self.secret.as_str(),
+            (Some("GET"), _) => "ERR agent holds a different profile",
+            (Some("STATUS"), None) => self.status.as_str(),
+            (Some("STOP"), None) => {
+                let _ = std::fs::remove_file(&self.path);
+                let _ = writeln!(stream, "OK");
+                std::process::exit(0);
+            }
+            _ => "ERR unknown command",
+        };
+        let _ = writeln!(stream, "{}", reply);
+    }
+}
+
+/// User id of the process on the other end of `stream`
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &std::os::unix::net::UnixStream) -> Option<u32> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut cred: libc::ucred = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let rc = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    (rc == 0).then_some(cred.uid)
+}
+
+/// User id of the process on the other end of `stream`
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+fn peer_uid(stream: &std::os::unix::net::UnixStream) -> Option<u32> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut uid: libc::uid_t = 0;
+    let mut gid: libc::gid_t = 0;
+    let rc = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+    (rc == 0).then_some(uid)
+}
+
+#[cfg(not(unix))]
+pub fn serve(
+    _credentials: Credentials,
+    _profile: Option<String>,
+    _expires_at: DateTime<Utc>,
+) -> Result<()> {
+    anyhow::bail!("The nsg agent is only supported on Unix systems")
+}
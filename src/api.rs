@@ -0,0 +1,211 @@
+//! The NSG operations the commands need, as a trait, so that something other
+//! than a live gateway can stand in for [`NsgClient`]: a mock in tests, or a
+//! wrapper adding caching or logging.
+
+use crate::client::{DownloadEvent, DownloadOptions, NsgClient};
+use crate::error::{Result, ResultExt};
+use crate::filenames::FilenameMapper;
+use crate::models::{DownloadedFile, JobStatus, JobSummary, OutputFile, ToolInfo};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// What can be asked of NSG. Implemented by [`NsgClient`]; the methods have
+/// the same meaning as the client's methods of the same name.
+///
+/// The trait is object safe, so code taking a `&dyn NsgApi` accepts an
+/// `&NsgClient` as it is.
+pub trait NsgApi {
+    /// Check that the credentials are accepted
+    fn test_connection(&self) -> Result<()>;
+
+    /// All of the user's jobs
+    fn list_jobs(&self) -> Result<Vec<JobSummary>>;
+
+    /// Status of a job given its URL, `/job/...` path or bare ID
+    fn get_job_status(&self, job_url_or_id: &str) -> Result<JobStatus>;
+
+    /// Status of each of `jobs`, in the order given. Fetched one at a time
+    /// unless the implementation knows better.
+    fn get_job_statuses(&self, jobs: &[&str]) -> Vec<Result<JobStatus>> {
+        jobs.iter().map(|job| self.get_job_status(job)).collect()
+    }
+
+    /// Requests [`get_job_statuses`](Self::get_job_statuses) makes at once
+    fn max_concurrency(&self) -> usize {
+        1
+    }
+
+    /// Remove a job and its results from the server
+    fn delete_job(&self, job_url_or_id: &str) -> Result<()>;
+
+    /// Cancel a queued or running job; for NSG the same as deleting it
+    fn cancel_job(&self, job_url_or_id: &str) -> Result<()> {
+        self.delete_job(job_url_or_id)
+    }
+
+    /// Submit a job with its `input.<name>` files and tool parameters
+    fn submit_job_with_inputs(
+        &self,
+        tool: &str,
+        inputs: &[(String, PathBuf)],
+        params: &[(String, String)],
+    ) -> Result<JobStatus>;
+
+    /// Every tool NSG offers
+    fn list_tools(&self) -> Result<Vec<ToolInfo>>;
+
+    /// A tool's PISE XML parameter description
+    fn get_tool_xml(&self, tool: &str) -> Result<String>;
+
+    /// Files currently in a running job's working directory
+    fn list_working_dir(&self, job_status: &JobStatus) -> Result<Vec<OutputFile>>;
+
+    /// Output files of a finished job
+    fn get_output_files(&self, job_status: &JobStatus) -> Result<Vec<OutputFile>>;
+
+    /// Download one listed file to `output_path`, returning its size
+    fn download_file(&self, file: &OutputFile, output_path: &Path) -> Result<u64>;
+
+    /// Read one listed file as it arrives, without saving it anywhere
+    fn open_output_file<'a>(&'a self, file: &OutputFile) -> Result<Box<dyn Read + Send + 'a>>;
+
+    /// Download a job's outputs into `output_dir`, passing each
+    /// [`DownloadEvent`] to `on_event`.
+    ///
+    /// By default the files are read one after another through
+    /// [`open_output_file`](Self::open_output_file), honouring `options`'
+    /// filter and `working_dir` but neither `jobs` nor `resume`;
+    /// [`NsgClient`] downloads in parallel and resumes `.part` files.
+    fn download_results(
+        &self,
+        job_url_or_id: &str,
+        output_dir: &Path,
+        options: &DownloadOptions,
+        on_event: &mut (dyn FnMut(DownloadEvent) + Send),
+    ) -> Result<Vec<DownloadedFile>> {
+        let job_status = self.get_job_status(job_url_or_id)?;
+        let mut output_files = if options.working_dir {
+            self.list_working_dir(&job_status)?
+        } else {
+            self.get_output_files(&job_status)?
+        };
+        output_files.retain(|f| options.filter.matches(&f.filename));
+
+        std::fs::create_dir_all(output_dir).context("Failed to create output directory")?;
+
+        let mut names = FilenameMapper::new();
+        let mut downloaded = Vec::with_capacity(output_files.len());
+        for file in output_files {
+            let local_name = names.local_name(&file.filename);
+            let path = output_dir.join(&local_name);
+
+            on_event(DownloadEvent::FileStarted {
+                filename: file.filename.clone(),
+                size: file.size,
+            });
+            let result = self.open_output_file(&file).and_then(|mut reader| {
+                let mut out = std::fs::File::create(&path)
+                    .with_context(|| format!("Failed to create {}", path.display()))?;
+                std::io::copy(&mut reader, &mut out)
+                    .with_context(|| format!("Failed to download {}", file.filename))
+            });
+            let size = match result {
+                Ok(size) => size,
+                Err(e) => {
+                    on_event(DownloadEvent::FileFailed {
+                        filename: file.filename.clone(),
+                        error: e.to_string(),
+                    });
+                    return Err(e);
+                }
+            };
+
+            let done = DownloadedFile {
+                filename: local_name,
+                remote_filename: file.filename,
+                path,
+                size,
+                up_to_date: false,
+            };
+            on_event(DownloadEvent::FileFinished { file: done.clone() });
+            downloaded.push(done);
+        }
+
+        names.write_record(output_dir)?;
+
+        on_event(DownloadEvent::AllDone {
+            files: downloaded.len(),
+            bytes: downloaded.iter().map(|file| file.size).sum(),
+        });
+        Ok(downloaded)
+    }
+}
+
+impl NsgApi for NsgClient {
+    fn test_connection(&self) -> Result<()> {
+        NsgClient::test_connection(self)
+    }
+
+    fn list_jobs(&self) -> Result<Vec<JobSummary>> {
+        NsgClient::list_jobs(self)
+    }
+
+    fn get_job_status(&self, job_url_or_id: &str) -> Result<JobStatus> {
+        NsgClient::get_job_status(self, job_url_or_id)
+    }
+
+    fn get_job_statuses(&self, jobs: &[&str]) -> Vec<Result<JobStatus>> {
+        NsgClient::get_job_statuses(self, jobs)
+    }
+
+    fn max_concurrency(&self) -> usize {
+        NsgClient::max_concurrency(self)
+    }
+
+    fn delete_job(&self, job_url_or_id: &str) -> Result<()> {
+        NsgClient::delete_job(self, job_url_or_id)
+    }
+
+    fn submit_job_with_inputs(
+        &self,
+        tool: &str,
+        inputs: &[(String, PathBuf)],
+        params: &[(String, String)],
+    ) -> Result<JobStatus> {
+        NsgClient::submit_job_with_inputs(self, tool, inputs, params)
+    }
+
+    fn list_tools(&self) -> Result<Vec<ToolInfo>> {
+        NsgClient::list_tools(self)
+    }
+
+    fn get_tool_xml(&self, tool: &str) -> Result<String> {
+        NsgClient::get_tool_xml(self, tool)
+    }
+
+    fn list_working_dir(&self, job_status: &JobStatus) -> Result<Vec<OutputFile>> {
+        NsgClient::list_working_dir(self, job_status)
+    }
+
+    fn get_output_files(&self, job_status: &JobStatus) -> Result<Vec<OutputFile>> {
+        NsgClient::get_output_files(self, job_status)
+    }
+
+    fn download_file(&self, file: &OutputFile, output_path: &Path) -> Result<u64> {
+        NsgClient::download_file(self, file, output_path)
+    }
+
+    fn open_output_file<'a>(&'a self, file: &OutputFile) -> Result<Box<dyn Read + Send + 'a>> {
+        Ok(Box::new(NsgClient::open_output_file(self, file)?))
+    }
+
+    fn download_results(
+        &self,
+        job_url_or_id: &str,
+        output_dir: &Path,
+        options: &DownloadOptions,
+        on_event: &mut (dyn FnMut(DownloadEvent) + Send),
+    ) -> Result<Vec<DownloadedFile>> {
+        self.download_results_with(job_url_or_id, output_dir, options, on_event)
+    }
+}
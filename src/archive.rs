@@ -0,0 +1,293 @@
+//! Building and checking job input archives
+
+use anyhow::{Context, Result};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Largest input archive NSG accepts for a job
+pub const MAX_INPUT_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Script that NSG's Python tools (`PY_*`) run from the top-level folder
+pub const PYTHON_ENTRY_SCRIPT: &str = "input.py";
+
+/// A way an input archive breaks NSG's conventions, found before uploading
+#[derive(Debug, Clone)]
+pub enum ArchiveIssue {
+    Empty,
+    FilesAtTopLevel { files: Vec<String> },
+    SeveralTopLevelFolders { folders: Vec<String> },
+    UnsafePath { name: String },
+    MissingEntryScript { tool: String, expected: String },
+    TooLarge { size: u64 },
+}
+
+impl fmt::Display for ArchiveIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveIssue::Empty => write!(f, "The archive is empty"),
+            ArchiveIssue::FilesAtTopLevel { files } => write!(
+                f,
+                "Files at the top level of the archive ({}); NSG expects everything inside one \
+                 folder - zip the folder itself, not its contents",
+                preview(files)
+            ),
+            ArchiveIssue::SeveralTopLevelFolders { folders } => write!(
+                f,
+                "{} folders at the top level of the archive ({}); NSG expects exactly one",
+                folders.len(),
+                preview(folders)
+            ),
+            ArchiveIssue::UnsafePath { name } => write!(
+                f,
+                "Entry '{}' has an absolute path or '..'; NSG would refuse to unpack it",
+                name
+            ),
+            ArchiveIssue::MissingEntryScript { tool, expected } => write!(
+                f,
+                "No {} in the archive; {} runs {}",
+                expected.rsplit('/').next().unwrap_or(expected),
+                tool,
+                expected
+            ),
+            ArchiveIssue::TooLarge { size } => write!(
+                f,
+                "The archive is {:.2} GB; NSG accepts at most {} GB per input",
+                *size as f64 / (1024 * 1024 * 1024) as f64,
+                MAX_INPUT_SIZE / (1024 * 1024 * 1024)
+            ),
+        }
+    }
+}
+
+/// Check that the job's main input archive follows NSG's layout: one
+/// top-level folder, safe relative paths, the entry script a Python tool
+/// runs, and a size NSG accepts. Fails only if `path` can't be read as a ZIP.
+pub fn check_input_zip(path: &Path, tool: &str) -> Result<Vec<ArchiveIssue>> {
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?
+        .len();
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let archive = ZipArchive::new(file)
+        .with_context(|| format!("{} is not a valid ZIP archive", path.display()))?;
+
+    let mut issues = Vec::new();
+    if size > MAX_INPUT_SIZE {
+        issues.push(ArchiveIssue::TooLarge { size });
+    }
+
+    let mut folders = BTreeSet::new();
+    let mut top_files = Vec::new();
+    let mut names = BTreeSet::new();
+    for name in archive.file_names() {
+        // Finder adds these to every archive it makes; NSG ignores them
+        if name.starts_with("__MACOSX/") || name.ends_with(".DS_Store") {
+            continue;
+        }
+        if name.starts_with('/')
+            || name.starts_with('\\')
+            || name.split(['/', '\\']).any(|part| part == "..")
+            || name.get(1..2) == Some(":")
+        {
+            issues.push(ArchiveIssue::UnsafePath {
+                name: name.to_string(),
+            });
+            continue;
+        }
+
+        match name.split_once('/') {
+            Some((folder, _)) => {
+                folders.insert(folder.to_string());
+            }
+            None => top_files.push(name.to_string()),
+        }
+        names.insert(name.to_string());
+    }
+
+    if names.is_empty() && issues.is_empty() {
+        issues.push(ArchiveIssue::Empty);
+        return Ok(issues);
+    }
+    if !top_files.is_empty() {
+        issues.push(ArchiveIssue::FilesAtTopLevel { files: top_files });
+    }
+    if folders.len() > 1 {
+        issues.push(ArchiveIssue::SeveralTopLevelFolders {
+            folders: folders.iter().cloned().collect(),
+        });
+    }
+
+    if tool.starts_with("PY_") && folders.len() == 1 {
+        let folder = folders.first().map(String::as_str).unwrap_or_default();
+        let expected = format!("{}/{}", folder, PYTHON_ENTRY_SCRIPT);
+        if !names.contains(&expected) {
+            issues.push(ArchiveIssue::MissingEntryScript {
+                tool: tool.to_string(),
+                expected,
+            });
+        }
+    }
+
+    Ok(issues)
+}
+
+/// The first few of `names`, for messages
+fn preview(names: &[String]) -> String {
+    const SHOWN: usize = 3;
+    let mut text = names
+        .iter()
+        .take(SHOWN)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if names.len() > SHOWN {
+        text.push_str(&format!(", ... {} more", names.len() - SHOWN));
+    }
+    text
+}
+
+/// What an input archive holds, read from its central directory without
+/// extracting anything
+#[derive(Debug, Clone)]
+pub struct ZipSummary {
+    pub entries: usize,
+    pub uncompressed_size: u64,
+}
+
+/// Open `path` as a ZIP archive and summarize it; fails if it isn't one
+pub fn inspect_zip(path: &Path) -> Result<ZipSummary> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let archive = ZipArchive::new(file)
+        .with_context(|| format!("{} is not a valid ZIP archive", path.display()))?;
+
+    Ok(ZipSummary {
+        entries: archive.len(),
+        uncompressed_size: archive.decompressed_size().unwrap_or_default() as u64,
+    })
+}
+
+/// Zip `dir` into `dest` with the directory itself as the single top-level
+/// folder, which is the layout NSG expects (`modeldir/input.py`, ...).
+///
+/// Entries are sorted and carry a fixed timestamp, so zipping the same tree
+/// twice gives identical bytes and duplicate detection still works.
+pub fn zip_directory(dir: &Path, dest: &Path) -> Result<()> {
+    let root = dir
+        .canonicalize()
+        .with_context(|| format!("Failed to read {}", dir.display()))?;
+    let top = root
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("Cannot zip a directory without a name")?
+        .to_string();
+
+    let file =
+        File::create(dest).with_context(|| format!("Failed to create {}", dest.display()))?;
+    let mut zip = ZipWriter::new(file);
+    add_tree(&mut zip, &root, &top)?;
+
+    zip.finish()
+        .with_context(|| format!("Failed to finish {}", dest.display()))?;
+
+    Ok(())
+}
+
+/// Copy the ZIP `archive` to `dest` with each `(folder, dir)` of `extra`
+/// added as `<top-level folder>/<folder>/...`, so the job finds the files
+/// next to its own. The archive must have a single top-level folder.
+pub fn zip_with_directories(
+    archive: &Path,
+    extra: &[(String, PathBuf)],
+    dest: &Path,
+) -> Result<()> {
+    let file =
+        File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let mut source = ZipArchive::new(file)
+        .with_context(|| format!("{} is not a valid ZIP archive", archive.display()))?;
+
+    let folders: BTreeSet<&str> = source
+        .file_names()
+        .filter(|name| !name.starts_with("__MACOSX/"))
+        .filter_map(|name| name.split_once('/').map(|(folder, _)| folder))
+        .collect();
+    let top = match folders.len() {
+        1 => folders
+            .first()
+            .map(|folder| folder.to_string())
+            .unwrap_or_default(),
+        _ => anyhow::bail!(
+            "{} has no single top-level folder to add files to",
+            archive.display()
+        ),
+    };
+
+    let file =
+        File::create(dest).with_context(|| format!("Failed to create {}", dest.display()))?;
+    let mut zip = ZipWriter::new(file);
+    for index in 0..source.len() {
+        let entry = source.by_index_raw(index)?;
+        let name = entry.name().to_string();
+        zip.raw_copy_file(entry)
+            .with_context(|| format!("Failed to copy {} from {}", name, archive.display()))?;
+    }
+    for (folder, dir) in extra {
+        add_tree(&mut zip, dir, &format!("{}/{}", top, folder))?;
+    }
+
+    zip.finish()
+        .with_context(|| format!("Failed to finish {}", dest.display()))?;
+
+    Ok(())
+}
+
+/// Add every file below `root` to `zip` as `<prefix>/<relative path>`, in
+/// sorted order and with a fixed timestamp
+fn add_tree(zip: &mut ZipWriter<File>, root: &Path, prefix: &str) -> Result<()> {
+    let mut files = Vec::new();
+    collect_files(root, &mut files)?;
+    files.sort();
+
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default());
+
+    for path in files {
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let name = format!(
+            "{}/{}",
+            prefix,
+            relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/")
+        );
+
+        let size = std::fs::metadata(&path)?.len();
+        zip.start_file(name.as_str(), options.large_file(size >= u32::MAX as u64))
+            .with_context(|| format!("Failed to add {} to the archive", name))?;
+        let mut input =
+            File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+        std::io::copy(&mut input, zip)
+            .with_context(|| format!("Failed to add {} to the archive", name))?;
+    }
+
+    Ok(())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+    {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,609 @@
+//! Tokio-based counterpart to [`NsgClient`](crate::NsgClient), for use inside
+//! async applications. Enabled with the `async` cargo feature.
+
+use crate::client::{
+    is_unchanged, link_path, load_ca_bundle, next_link, range_start, submit_fields, ClientOptions,
+    DownloadEvent, DownloadOptions, DEFAULT_MAX_CONCURRENCY, DEFAULT_RATE_LIMIT,
+};
+use crate::config::Credentials;
+use crate::error::{api_error, response_error, NsgError, Result, ResultExt};
+use crate::filenames::{long_path, partial_path, FilenameMapper};
+use crate::limiter::RateLimiter;
+use crate::models::*;
+use crate::retry::{is_transient_error, is_transient_status, RetryPolicy};
+use crate::timeouts::Timeouts;
+use futures_util::{stream, StreamExt, TryStreamExt};
+use reqwest::{multipart, Client, StatusCode};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Semaphore;
+
+pub struct NsgAsyncClient {
+    client: Client,
+    /// For the contents of output files, limited only on going quiet
+    downloads: Client,
+    credentials: Credentials,
+    base_url: String,
+    retry: RetryPolicy,
+    timeouts: Timeouts,
+    limiter: Semaphore,
+    rate: RateLimiter,
+}
+
+impl NsgAsyncClient {
+    pub fn new(credentials: Credentials) -> Result<Self> {
+        Self::with_options(credentials, ClientOptions::default())
+    }
+
+    pub fn with_options(credentials: Credentials, options: ClientOptions) -> Result<Self> {
+        let certificates = match &options.ca_cert {
+            Some(path) => load_ca_bundle(path)?,
+            None => Vec::new(),
+        };
+        let builder = || {
+            let mut builder = Client::builder()
+                .user_agent(options.user_agent())
+                .connect_timeout(options.timeouts.connect)
+                .danger_accept_invalid_certs(options.insecure);
+            for certificate in &certificates {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+            builder
+        };
+
+        let mut client = builder();
+        if let Some(timeout) = options.timeouts.request {
+            client = client.timeout(timeout);
+        }
+        let client = client.build().context("Failed to create HTTP client")?;
+
+        // A read timeout rather than a total one, so downloads may take as
+        // long as they need while data keeps coming
+        let mut downloads = builder();
+        if let Some(timeout) = options.timeouts.read {
+            downloads = downloads.read_timeout(timeout);
+        }
+        let downloads = downloads.build().context("Failed to create HTTP client")?;
+
+        let limit = options
+            .max_concurrency
+            .unwrap_or(DEFAULT_MAX_CONCURRENCY)
+            .max(1);
+
+        Ok(Self {
+            client,
+            downloads,
+            credentials,
+            base_url: options.base_url(),
+            retry: options.retry,
+            timeouts: options.timeouts,
+            limiter: Semaphore::new(limit),
+            rate: RateLimiter::new(Some(options.rate_limit.unwrap_or(DEFAULT_RATE_LIMIT))),
+        })
+    }
+
+    pub fn new_with_url(credentials: Credentials, base_url: String) -> Result<Self> {
+        let mut client = Self::new(credentials)?;
+        client.base_url = base_url;
+        Ok(client)
+    }
+
+    fn build_request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.request_on(&self.client, method, path)
+    }
+
+    /// A GET of a file's contents, with no deadline on the download as a
+    /// whole
+    fn build_download_request(&self, path: &str) -> reqwest::RequestBuilder {
+        self.request_on(&self.downloads, reqwest::Method::GET, path)
+    }
+
+    fn request_on(
+        &self,
+        client: &Client,
+        method: reqwest::Method,
+        path: &str,
+    ) -> reqwest::RequestBuilder {
+        let url = format!("{}{}", self.base_url, path);
+
+        client
+            .request(method, &url)
+            .basic_auth(&self.credentials.username, Some(&self.credentials.password))
+            .header("cipres-appkey", &self.credentials.app_key)
+    }
+
+    /// Send `request`, retrying transient failures and keeping to the rate
+    /// limit like the blocking client
+    async fn send(&self, request: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+        let mut retry = 0;
+
+        loop {
+            let replay = if retry < self.retry.retries {
+                request.try_clone()
+            } else {
+                None
+            };
+            tokio::time::sleep(self.rate.reserve()).await;
+            let Some(next) = replay else {
+                return request.send().await;
+            };
+
+            match next.send().await {
+                Ok(response) if is_transient_status(response.status()) => {}
+                Err(e) if is_transient_error(&e) => {}
+                result => return result,
+            }
+
+            retry += 1;
+            tokio::time::sleep(self.retry.delay(retry)).await;
+        }
+    }
+
+    /// A slot under the concurrency cap; the semaphore is never closed
+    async fn permit(&self) -> tokio::sync::SemaphorePermit<'_> {
+        self.limiter
+            .acquire()
+            .await
+            .expect("request limiter is never closed")
+    }
+
+    async fn get_text(&self, path: &str, what: &str) -> Result<String> {
+        let _permit = self.permit().await;
+
+        let response = self
+            .send(self.build_request(reqwest::Method::GET, path))
+            .await
+            .with_context(|| format!("Failed to fetch {}", what))?;
+
+        if !response.status().is_success() {
+            return Err(api_error(
+                response.status(),
+                format!("Failed to fetch {}: HTTP {}", what, response.status()),
+            ));
+        }
+
+        Ok(response.text().await?)
+    }
+
+    /// All of the user's jobs, following the job list across pages
+    pub async fn list_jobs(&self) -> Result<Vec<JobSummary>> {
+        let mut jobs = Vec::new();
+        let mut next = Some(format!("/job/{}", self.credentials.username));
+        let mut seen = HashSet::new();
+
+        while let Some(path) = next.take() {
+            if !seen.insert(path.clone()) {
+                break;
+            }
+
+            let _permit = self.permit().await;
+            let response = self
+                .send(self.build_request(reqwest::Method::GET, &path))
+                .await
+                .context("Failed to fetch job list")?;
+            if !response.status().is_success() {
+                return Err(api_error(
+                    response.status(),
+                    format!("Failed to list jobs: HTTP {}", response.status()),
+                ));
+            }
+
+            let link = next_link(response.headers());
+            let page = parse_job_page(&response.text().await?)?;
+            jobs.extend(page.jobs);
+
+            if let Some(url) = page.next.or(link) {
+                next = Some(link_path(&self.base_url, &path, &url)?);
+            }
+        }
+
+        Ok(jobs)
+    }
+
+    pub async fn get_job_status(&self, job_url_or_id: &str) -> Result<JobStatus> {
+        let path = if job_url_or_id.starts_with("http") {
+            job_url_or_id
+                .strip_prefix(&self.base_url)
+                .context("Invalid job URL")?
+                .to_string()
+        } else if job_url_or_id.starts_with("/job/") {
+            job_url_or_id.to_string()
+        } else {
+            format!("/job/{}/{}", self.credentials.username, job_url_or_id)
+        };
+
+        let body = self
+            .get_text(&path, &format!("status of job {}", job_url_or_id))
+            .await?;
+        parse_job_status(&body)
+    }
+
+    pub async fn submit_job(&self, zip_path: &Path, tool: &str) -> Result<JobStatus> {
+        self.submit_job_with_params(zip_path, tool, &[]).await
+    }
+
+    /// Submit a job; `params` are sent like
+    /// [`NsgClient::submit_job_with_params`](crate::NsgClient::submit_job_with_params)
+    pub async fn submit_job_with_params(
+        &self,
+        zip_path: &Path,
+        tool: &str,
+        params: &[(String, String)],
+    ) -> Result<JobStatus> {
+        self.submit_job_with_inputs(
+            tool,
+            &[("infile_".to_string(), zip_path.to_path_buf())],
+            params,
+        )
+        .await
+    }
+
+    /// Submit a job with several input files, each sent as `input.<name>`
+    pub async fn submit_job_with_inputs(
+        &self,
+        tool: &str,
+        inputs: &[(String, PathBuf)],
+        params: &[(String, String)],
+    ) -> Result<JobStatus> {
+        let path = format!("/job/{}", self.credentials.username);
+
+        let mut form = multipart::Form::new().text("tool", tool.to_string());
+        let mut upload_size = 0;
+
+        for (name, input_path) in inputs {
+            let contents = tokio::fs::read(input_path)
+                .await
+                .with_context(|| format!("Failed to read {}", input_path.display()))?;
+            upload_size += contents.len() as u64;
+            let file_part = multipart::Part::bytes(contents).file_name(
+                input_path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("job.zip")
+                    .to_string(),
+            );
+            form = form.part(format!("input.{}", name), file_part);
+        }
+
+        for (field, value) in submit_fields(params) {
+            form = form.text(field, value);
+        }
+
+        let _permit = self.permit().await;
+
+        let mut request = self
+            .build_request(reqwest::Method::POST, &path)
+            .multipart(form);
+        if let Some(timeout) = self.timeouts.upload(upload_size) {
+            request = request.timeout(timeout);
+        }
+        let response = self.send(request).await.context("Failed to submit job")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(response_error(status, "Failed to submit job", &body));
+        }
+
+        let body = response.text().await?;
+        parse_job_status(&body)
+    }
+
+    /// List the output files of a finished job, to fetch with
+    /// [`download_file`](Self::download_file)
+    pub async fn get_output_files(&self, job_status: &JobStatus) -> Result<Vec<OutputFile>> {
+        let results_url = job_status
+            .results_uri
+            .as_deref()
+            .context("Job has no results URL - may not be completed yet")?;
+
+        let results_path = results_url
+            .strip_prefix(&self.base_url)
+            .context("Invalid results URL")?;
+
+        let body = self.get_text(results_path, "results list").await?;
+        parse_output_files(&body)
+    }
+
+    /// Files in a running job's working directory
+    pub async fn list_working_dir(&self, job_status: &JobStatus) -> Result<Vec<OutputFile>> {
+        let working_dir_url = job_status
+            .working_dir_uri
+            .as_deref()
+            .context("Job has no working directory URL - it may not have started yet")?;
+
+        let working_dir_path = working_dir_url
+            .strip_prefix(&self.base_url)
+            .context("Invalid working directory URL")?;
+
+        let body = self
+            .get_text(working_dir_path, "working directory listing")
+            .await?;
+        parse_output_files(&body)
+    }
+
+    /// Download all output files into `output_dir`, with the same filename
+    /// sanitization as the blocking client
+    pub async fn download_results<F>(
+        &self,
+        job_url_or_id: &str,
+        output_dir: &Path,
+        on_event: F,
+    ) -> Result<Vec<DownloadedFile>>
+    where
+        F: FnMut(DownloadEvent),
+    {
+        self.download_results_with(
+            job_url_or_id,
+            output_dir,
+            &DownloadOptions::default(),
+            on_event,
+        )
+        .await
+    }
+
+    /// Like [`download_results`](Self::download_results), with a file filter
+    /// and up to `options.jobs` files transferred at once. Files still in
+    /// flight when one fails are abandoned.
+    pub async fn download_results_with<F>(
+        &self,
+        job_url_or_id: &str,
+        output_dir: &Path,
+        options: &DownloadOptions,
+        on_event: F,
+    ) -> Result<Vec<DownloadedFile>>
+    where
+        F: FnMut(DownloadEvent),
+    {
+        let job_status = self.get_job_status(job_url_or_id).await?;
+        let mut output_files = if options.working_dir {
+            self.list_working_dir(&job_status).await?
+        } else {
+            self.get_output_files(&job_status).await?
+        };
+        output_files.retain(|f| options.filter.matches(&f.filename));
+
+        tokio::fs::create_dir_all(long_path(output_dir))
+            .await
+            .context("Failed to create output directory")?;
+
+        let mut names = FilenameMapper::new();
+        let planned: Vec<(OutputFile, String)> = output_files
+            .into_iter()
+            .map(|file| {
+                let local_name = names.local_name(&file.filename);
+                (file, local_name)
+            })
+            .collect();
+
+        // Only ever locked between awaits, so a std mutex is enough
+        let on_event = Mutex::new(on_event);
+        let emit = |event| (on_event.lock().unwrap_or_else(|e| e.into_inner()))(event);
+
+        let downloaded: Vec<DownloadedFile> = stream::iter(planned)
+            .map(|(file, local_name)| {
+                let emit = &emit;
+                async move {
+                    let output_path = output_dir.join(&local_name);
+                    let up_to_date = options.skip_unchanged
+                        && file
+                            .size
+                            .is_some_and(|size| is_unchanged(&output_path, size));
+                    let size = if up_to_date {
+                        file.size.unwrap_or_default()
+                    } else {
+                        emit(DownloadEvent::FileStarted {
+                            filename: file.filename.clone(),
+                            size: file.size,
+                        });
+                        let result = self
+                            .download_file_with(&file, &output_path, options, |bytes| {
+                                emit(DownloadEvent::Progress {
+                                    filename: file.filename.clone(),
+                                    downloaded: bytes,
+                                    total: file.size,
+                                })
+                            })
+                            .await;
+                        match result {
+                            Ok(size) => size,
+                            Err(e) => {
+                                emit(DownloadEvent::FileFailed {
+                                    filename: file.filename.clone(),
+                                    error: e.to_string(),
+                                });
+                                return Err(e);
+                            }
+                        }
+                    };
+
+                    let downloaded = DownloadedFile {
+                        filename: local_name,
+                        remote_filename: file.filename,
+                        path: output_path,
+                        size,
+                        up_to_date,
+                    };
+                    emit(DownloadEvent::FileFinished {
+                        file: downloaded.clone(),
+                    });
+                    Ok::<_, NsgError>(downloaded)
+                }
+            })
+            .buffered(options.jobs.max(1))
+            .try_collect()
+            .await?;
+
+        names.write_record(output_dir)?;
+
+        emit(DownloadEvent::AllDone {
+            files: downloaded.len(),
+            bytes: downloaded.iter().map(|file| file.size).sum(),
+        });
+        Ok(downloaded)
+    }
+
+    /// Download one listed file to `output_path`, which is used as given;
+    /// see [`NsgClient::download_file`](crate::NsgClient::download_file)
+    pub async fn download_file(&self, file: &OutputFile, output_path: &Path) -> Result<u64> {
+        self.download_file_with(file, output_path, &DownloadOptions::default(), |_| {})
+            .await
+    }
+
+    /// Like [`download_file`](Self::download_file), with the same `.part`,
+    /// resume and size check handling as the blocking client
+    pub async fn download_file_with(
+        &self,
+        file: &OutputFile,
+        output_path: &Path,
+        options: &DownloadOptions,
+        mut progress: impl FnMut(u64),
+    ) -> Result<u64> {
+        let mut retry = 0;
+
+        loop {
+            match self
+                .fetch_file(file, output_path, options, &mut progress)
+                .await
+            {
+                Err(NsgError::Transfer(_)) if retry < self.retry.retries => {}
+                result => return result,
+            }
+
+            retry += 1;
+            tokio::time::sleep(self.retry.delay(retry)).await;
+        }
+    }
+
+    /// One attempt of [`download_file_with`](Self::download_file_with)
+    async fn fetch_file(
+        &self,
+        file: &OutputFile,
+        output_path: &Path,
+        options: &DownloadOptions,
+        progress: &mut impl FnMut(u64),
+    ) -> Result<u64> {
+        let download_path = file
+            .download_uri
+            .strip_prefix(&self.base_url)
+            .context("Invalid download URL")?;
+
+        if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(long_path(parent))
+                .await
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let part_path = partial_path(output_path);
+        // Working directory files may be rewritten between snapshots, so
+        // only appending to a finished file's `.part` is safe
+        let existing = match file.size {
+            Some(size) if options.resume && !options.working_dir => {
+                let existing = tokio::fs::metadata(long_path(&part_path))
+                    .await
+                    .map(|m| m.len())
+                    .unwrap_or(0);
+                if existing > size {
+                    0
+                } else {
+                    existing
+                }
+            }
+            _ => 0,
+        };
+
+        let _permit = self.permit().await;
+
+        let mut request = self.build_download_request(download_path);
+        if existing > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+        }
+
+        let mut response = self
+            .send(request)
+            .await
+            .with_context(|| format!("Failed to download {}", file.filename))?;
+
+        let offset = match response.status() {
+            StatusCode::PARTIAL_CONTENT if existing > 0 => {
+                if range_start(response.headers()) != Some(existing) {
+                    return Err(NsgError::Transfer(format!(
+                        "Failed to resume {}: server sent an unexpected range",
+                        file.filename
+                    )));
+                }
+                existing
+            }
+            StatusCode::RANGE_NOT_SATISFIABLE if existing > 0 && Some(existing) == file.size => {
+                progress(existing);
+                return finish_part(file, &part_path, output_path, options.working_dir).await;
+            }
+            status if status.is_success() => 0,
+            status => {
+                return Err(api_error(
+                    status,
+                    format!("Failed to download {}: HTTP {}", file.filename, status),
+                ))
+            }
+        };
+
+        let mut dest = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(offset > 0)
+            .truncate(offset == 0)
+            .open(long_path(&part_path))
+            .await
+            .with_context(|| format!("Failed to create {}", part_path.display()))?;
+
+        let mut downloaded_bytes = offset;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .with_context(|| format!("Failed to read from {}", file.filename))?
+        {
+            dest.write_all(&chunk)
+                .await
+                .with_context(|| format!("Failed to write to {}", file.filename))?;
+
+            downloaded_bytes += chunk.len() as u64;
+            progress(downloaded_bytes);
+        }
+        dest.flush()
+            .await
+            .with_context(|| format!("Failed to write to {}", file.filename))?;
+        drop(dest);
+
+        finish_part(file, &part_path, output_path, options.working_dir).await
+    }
+}
+
+async fn finish_part(
+    file: &OutputFile,
+    part_path: &Path,
+    output_path: &Path,
+    may_grow: bool,
+) -> Result<u64> {
+    let size = tokio::fs::metadata(long_path(part_path))
+        .await
+        .with_context(|| format!("Failed to read {}", part_path.display()))?
+        .len();
+
+    // Files in a running job's working directory can grow after listing
+    if let Some(expected) = file.size {
+        if size < expected || (size != expected && !may_grow) {
+            return Err(NsgError::Transfer(format!(
+                "Download of {} is incomplete: got {} of {} bytes (run the download again to resume)",
+                file.filename, size, expected
+            )));
+        }
+    }
+
+    tokio::fs::rename(long_path(part_path), long_path(output_path))
+        .await
+        .with_context(|| format!("Failed to move {} into place", output_path.display()))?;
+
+    Ok(size)
+}
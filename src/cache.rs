@@ -0,0 +1,641 @@
+//! Local database of every job this machine has seen or submitted
+//! (`~/.nsg/jobs.db`, SQLite), so tools, parameters, tags and results stay
+//! known after NSG forgets a job.
+
+use crate::config::config_dir;
+use crate::models::{tool_from_job_id, JobStage, JobStatus};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const DATABASE_FILE: &str = "jobs.db";
+
+/// Bumped with every schema change; see [`migrate`]
+const SCHEMA_VERSION: i32 = 4;
+
+/// Profile chosen with `--profile`, whose jobs the cache holds
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Load and record only the jobs of the named profile from now on, instead
+/// of those of the default credentials. Only the first call has an effect.
+pub fn set_profile(name: String) {
+    let _ = PROFILE.set(name);
+}
+
+fn current_profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+/// Last-known state of a job, as seen by any `nsg` command on this machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedJob {
+    pub job_id: String,
+    /// Profile the job was seen under; `None` for the default credentials
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub tool: Option<String>,
+    pub stage: String,
+    pub failed: bool,
+    pub terminal: bool,
+    pub date_submitted: Option<String>,
+    pub url: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub notes: Vec<JobNote>,
+    /// Name of the input archive, if the job was submitted from this machine
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_file: Option<String>,
+    /// Local path of each input (by form name, e.g. `infile_`) the job was
+    /// submitted with from this machine, so it can be resubmitted
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inputs: BTreeMap<String, PathBuf>,
+    /// `vparam.` values the job was submitted with from this machine
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    /// SHA-256 of the input archive, used to spot accidental resubmissions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submitted_at: Option<DateTime<Utc>>,
+    /// Directory the results were last downloaded to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_dir: Option<PathBuf>,
+    /// When NSG handed the job to the cluster, from its job messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the job stopped running on the cluster, from its job messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobNote {
+    pub text: String,
+    pub added_at: DateTime<Utc>,
+}
+
+impl CachedJob {
+    pub fn new(job_id: &str) -> Self {
+        Self {
+            job_id: job_id.to_string(),
+            profile: current_profile().map(str::to_string),
+            tool: tool_from_job_id(job_id).map(str::to_string),
+            stage: String::new(),
+            failed: false,
+            terminal: false,
+            date_submitted: None,
+            url: None,
+            updated_at: Utc::now(),
+            tags: Vec::new(),
+            notes: Vec::new(),
+            input_file: None,
+            inputs: BTreeMap::new(),
+            params: BTreeMap::new(),
+            input_sha256: None,
+            submitted_at: None,
+            download_dir: None,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    pub fn is_queued(&self) -> bool {
+        !self.terminal && JobStage::from(self.stage.as_str()).is_queued()
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.stage.is_empty() && !self.terminal && !self.failed && !self.is_queued()
+    }
+
+    /// When the job was submitted: recorded at submission from this machine,
+    /// otherwise as reported by NSG
+    pub fn submitted(&self) -> Option<DateTime<Utc>> {
+        self.submitted_at.or_else(|| {
+            self.date_submitted
+                .as_deref()
+                .and_then(|date| date.parse::<DateTime<Utc>>().ok())
+        })
+    }
+
+    /// How long the job ran on the cluster, if its job messages said when it
+    /// started and finished. Includes time spent in the cluster's own queue.
+    pub fn run_time(&self) -> Option<chrono::TimeDelta> {
+        match (self.started_at, self.finished_at) {
+            (Some(started), Some(finished)) if finished >= started => Some(finished - started),
+            _ => None,
+        }
+    }
+}
+
+/// Local cache of job states, backed by `~/.nsg/jobs.db`.
+///
+/// Holds the jobs of the current profile only (see [`set_profile`]), so
+/// offline listings don't mix up accounts.
+///
+/// Loaded whole; [`save`](Self::save) writes back only the jobs changed
+/// through [`entry`](Self::entry), so concurrent `nsg` processes don't undo
+/// each other's updates to other jobs.
+#[derive(Debug, Default)]
+pub struct JobCache {
+    pub jobs: BTreeMap<String, CachedJob>,
+    changed: BTreeSet<String>,
+}
+
+impl JobCache {
+    pub fn load() -> Result<Self> {
+        let connection = open()?;
+
+        Ok(Self {
+            jobs: read_jobs(&connection)?,
+            changed: BTreeSet::new(),
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if self.changed.is_empty() {
+            return Ok(());
+        }
+
+        let mut connection = open()?;
+        let tx = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        for job_id in &self.changed {
+            if let Some(job) = self.jobs.get(job_id) {
+                write_job(&tx, job)?;
+            }
+        }
+        tx.commit().context("Failed to save the job database")
+    }
+
+    pub fn database_path() -> Result<PathBuf> {
+        Ok(config_dir()?.join(DATABASE_FILE))
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<&CachedJob> {
+        self.jobs.get(job_id)
+    }
+
+    /// The tool a job was submitted to, from the cache or else its job handle
+    pub fn tool_for<'a>(&'a self, job_id: &'a str) -> Option<&'a str> {
+        self.get(job_id)
+            .and_then(|job| job.tool.as_deref())
+            .or_else(|| tool_from_job_id(job_id))
+    }
+
+    /// The cache entry for `job_id`, created if this job hasn't been seen
+    /// yet, and written back by the next [`save`](Self::save)
+    pub fn entry(&mut self, job_id: &str) -> &mut CachedJob {
+        self.changed.insert(job_id.to_string());
+        self.jobs
+            .entry(job_id.to_string())
+            .or_insert_with(|| CachedJob::new(job_id))
+    }
+
+    /// Jobs submitted from this machine since `since` with the same tool,
+    /// input archive and parameters
+    pub fn find_duplicates(
+        &self,
+        tool: &str,
+        input_sha256: &str,
+        params: &BTreeMap<String, String>,
+        since: DateTime<Utc>,
+    ) -> Vec<&CachedJob> {
+        self.jobs
+            .values()
+            .filter(|job| job.submitted_at.is_some_and(|at| at >= since))
+            .filter(|job| job.input_sha256.as_deref() == Some(input_sha256))
+            .filter(|job| job.tool.as_deref() == Some(tool))
+            .filter(|job| &job.params == params)
+            .collect()
+    }
+
+    /// Mark a job as gone from the server, keeping its tags and notes
+    pub fn record_removed(&mut self, job_id: &str, stage: &str) {
+        let entry = self.entry(job_id);
+
+        entry.stage = stage.to_string();
+        entry.terminal = true;
+        entry.updated_at = Utc::now();
+    }
+
+    pub fn record_status(&mut self, status: &JobStatus) {
+        let entry = self.entry(&status.job_id);
+
+        entry.stage = status.job_stage.to_string();
+        entry.failed = status.failed;
+        entry.terminal = status.terminal_stage || status.job_stage.is_terminal();
+        if status.date_submitted.is_some() {
+            entry.date_submitted = status.date_submitted.clone();
+        }
+        if !status.self_uri.is_empty() {
+            entry.url = Some(status.self_uri.clone());
+        }
+        let started = first_message_at(status, |stage| *stage == JobStage::Running)
+            .or_else(|| first_message_at(status, |stage| *stage == JobStage::Submitted));
+        if started.is_some() {
+            entry.started_at = started;
+        }
+        if entry.terminal {
+            let finished = first_message_at(status, |stage| {
+                matches!(
+                    stage,
+                    JobStage::LoadResults | JobStage::Completed | JobStage::Failed
+                )
+            });
+            if finished.is_some() {
+                entry.finished_at = finished;
+            }
+        }
+        entry.updated_at = Utc::now();
+    }
+}
+
+/// Time of the first of `status`'s messages at a stage `reached` accepts
+fn first_message_at(
+    status: &JobStatus,
+    reached: impl Fn(&JobStage) -> bool,
+) -> Option<DateTime<Utc>> {
+    status
+        .messages
+        .iter()
+        .filter(|message| reached(&JobStage::from(message.stage.as_str())))
+        .find_map(|message| message.timestamp.as_deref()?.parse().ok())
+}
+
+/// Record freshly fetched statuses in the cache.
+///
+/// Best-effort: a missing or unwritable cache must never fail the command
+/// that happened to fetch the status.
+pub fn record_statuses<'a>(statuses: impl IntoIterator<Item = &'a JobStatus>) {
+    let Ok(mut cache) = JobCache::load() else {
+        return;
+    };
+
+    for status in statuses {
+        cache.record_status(status);
+    }
+
+    let _ = cache.save();
+}
+
+/// Record that a job was cancelled or deleted. Best-effort.
+pub fn record_removed(job_id: &str, stage: &str) {
+    let Ok(mut cache) = JobCache::load() else {
+        return;
+    };
+
+    cache.record_removed(job_id, stage);
+
+    let _ = cache.save();
+}
+
+/// Remember what a job was submitted with, since NSG doesn't report it back.
+///
+/// Best-effort, like [`record_statuses`].
+pub fn record_submission(
+    status: &JobStatus,
+    tool: &str,
+    input_file: Option<&str>,
+    inputs: &[(String, PathBuf)],
+    input_sha256: Option<&str>,
+    params: &[(String, String)],
+) {
+    let Ok(mut cache) = JobCache::load() else {
+        return;
+    };
+
+    cache.record_status(status);
+    let entry = cache.entry(&status.job_id);
+    entry.tool = Some(tool.to_string());
+    entry.input_file = input_file.map(str::to_string);
+    entry.inputs = inputs
+        .iter()
+        .map(|(name, path)| {
+            let path = path.canonicalize().unwrap_or_else(|_| path.clone());
+            (name.clone(), path)
+        })
+        .collect();
+    entry.params = params.iter().cloned().collect();
+    entry.input_sha256 = input_sha256.map(str::to_string);
+    entry.submitted_at = Some(Utc::now());
+
+    let _ = cache.save();
+}
+
+/// Attach a note to a job. Best-effort.
+pub fn add_note(job_id: &str, text: &str) {
+    let Ok(mut cache) = JobCache::load() else {
+        return;
+    };
+
+    cache.entry(job_id).notes.push(JobNote {
+        text: text.to_string(),
+        added_at: Utc::now(),
+    });
+
+    let _ = cache.save();
+}
+
+/// Record where a job's results were downloaded to. Best-effort.
+pub fn record_download(job_id: &str, dir: &Path) {
+    let Ok(mut cache) = JobCache::load() else {
+        return;
+    };
+
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    let entry = cache.entry(job_id);
+    entry.download_dir = Some(dir);
+    entry.updated_at = Utc::now();
+
+    let _ = cache.save();
+}
+
+/// Reduce a job URL (or `/job/<user>/<id>` path) to its bare job ID
+pub fn job_id_from(job_url_or_id: &str) -> &str {
+    job_url_or_id
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(job_url_or_id)
+}
+
+/// Open the database, creating or upgrading its schema as needed
+fn open() -> Result<Connection> {
+    let path = JobCache::database_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    let mut connection = Connection::open(&path)
+        .with_context(|| format!("Failed to open job database {}", path.display()))?;
+    // Several nsg processes (e.g. watch and download) may write at once
+    connection.busy_timeout(Duration::from_secs(10))?;
+    connection.pragma_update(None, "foreign_keys", true)?;
+    migrate(&mut connection)
+        .with_context(|| format!("Failed to set up job database {}", path.display()))?;
+
+    Ok(connection)
+}
+
+/// Bring the schema up to [`SCHEMA_VERSION`]
+fn migrate(connection: &mut Connection) -> Result<()> {
+    let version: i32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version >= SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    let tx = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    // Another process may have migrated while we waited for the lock
+    let version: i32 = tx.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    if version < 1 {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                job_id TEXT PRIMARY KEY,
+                tool TEXT,
+                stage TEXT NOT NULL,
+                failed INTEGER NOT NULL,
+                terminal INTEGER NOT NULL,
+                date_submitted TEXT,
+                url TEXT,
+                updated_at TEXT NOT NULL,
+                input_file TEXT,
+                input_sha256 TEXT,
+                submitted_at TEXT,
+                download_dir TEXT
+            );
+            CREATE INDEX IF NOT EXISTS jobs_submitted_at ON jobs (submitted_at);
+            CREATE TABLE IF NOT EXISTS job_params (
+                job_id TEXT NOT NULL REFERENCES jobs (job_id) ON DELETE CASCADE,
+                name TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (job_id, name)
+            );
+            CREATE TABLE IF NOT EXISTS job_tags (
+                job_id TEXT NOT NULL REFERENCES jobs (job_id) ON DELETE CASCADE,
+                position INTEGER NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (job_id, tag)
+            );
+            CREATE TABLE IF NOT EXISTS job_notes (
+                job_id TEXT NOT NULL REFERENCES jobs (job_id) ON DELETE CASCADE,
+                added_at TEXT NOT NULL,
+                text TEXT NOT NULL
+            );",
+        )?;
+    }
+
+    if version < 2 {
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS job_inputs (
+                job_id TEXT NOT NULL REFERENCES jobs (job_id) ON DELETE CASCADE,
+                name TEXT NOT NULL,
+                path TEXT NOT NULL,
+                PRIMARY KEY (job_id, name)
+            );",
+        )?;
+    }
+
+    if version < 3 {
+        tx.execute_batch(
+            "ALTER TABLE jobs ADD COLUMN started_at TEXT;
+            ALTER TABLE jobs ADD COLUMN finished_at TEXT;",
+        )?;
+    }
+
+    if version < 4 {
+        tx.execute_batch(
+            "ALTER TABLE jobs ADD COLUMN profile TEXT;
+            CREATE INDEX IF NOT EXISTS jobs_profile ON jobs (profile);",
+        )?;
+    }
+
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn read_jobs(connection: &Connection) -> Result<BTreeMap<String, CachedJob>> {
+    let mut jobs = BTreeMap::new();
+
+    let mut statement = connection.prepare(
+        "SELECT job_id, tool, stage, failed, terminal, date_submitted, url, updated_at,
+                input_file, input_sha256, submitted_at, download_dir, started_at, finished_at,
+                profile
+         FROM jobs
+         WHERE profile IS ?1",
+    )?;
+    let rows = statement.query_map([current_profile()], |row| {
+        Ok(CachedJob {
+            job_id: row.get(0)?,
+            profile: row.get(14)?,
+            tool: row.get(1)?,
+            stage: row.get(2)?,
+            failed: row.get(3)?,
+            terminal: row.get(4)?,
+            date_submitted: row.get(5)?,
+            url: row.get(6)?,
+            updated_at: row.get(7)?,
+            tags: Vec::new(),
+            notes: Vec::new(),
+            input_file: row.get(8)?,
+            inputs: BTreeMap::new(),
+            params: BTreeMap::new(),
+            input_sha256: row.get(9)?,
+            submitted_at: row.get(10)?,
+            download_dir: row.get::<_, Option<String>>(11)?.map(PathBuf::from),
+            started_at: row.get(12)?,
+            finished_at: row.get(13)?,
+        })
+    })?;
+    for job in rows {
+        let job = job?;
+        jobs.insert(job.job_id.clone(), job);
+    }
+
+    let mut statement = connection.prepare("SELECT job_id, name, value FROM job_params")?;
+    let rows = statement.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?))
+    })?;
+    for row in rows {
+        let (job_id, name, value) = row?;
+        if let Some(job) = jobs.get_mut(&job_id) {
+            job.params.insert(name, value);
+        }
+    }
+
+    let mut statement = connection.prepare("SELECT job_id, name, path FROM job_inputs")?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get(1)?,
+            row.get::<_, String>(2)?,
+        ))
+    })?;
+    for row in rows {
+        let (job_id, name, path) = row?;
+        if let Some(job) = jobs.get_mut(&job_id) {
+            job.inputs.insert(name, PathBuf::from(path));
+        }
+    }
+
+    let mut statement =
+        connection.prepare("SELECT job_id, tag FROM job_tags ORDER BY job_id, position")?;
+    let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+    for row in rows {
+        let (job_id, tag) = row?;
+        if let Some(job) = jobs.get_mut(&job_id) {
+            job.tags.push(tag);
+        }
+    }
+
+    let mut statement =
+        connection.prepare("SELECT job_id, added_at, text FROM job_notes ORDER BY rowid")?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            JobNote {
+                added_at: row.get(1)?,
+                text: row.get(2)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (job_id, note) = row?;
+        if let Some(job) = jobs.get_mut(&job_id) {
+            job.notes.push(note);
+        }
+    }
+
+    Ok(jobs)
+}
+
+/// Insert or replace everything stored about `job`
+fn write_job(tx: &Transaction, job: &CachedJob) -> Result<()> {
+    tx.execute(
+        "INSERT INTO jobs (job_id, tool, stage, failed, terminal, date_submitted, url,
+                           updated_at, input_file, input_sha256, submitted_at, download_dir,
+                           started_at, finished_at, profile)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+         ON CONFLICT (job_id) DO UPDATE SET
+             tool = excluded.tool,
+             stage = excluded.stage,
+             failed = excluded.failed,
+             terminal = excluded.terminal,
+             date_submitted = excluded.date_submitted,
+             url = excluded.url,
+             updated_at = excluded.updated_at,
+             input_file = excluded.input_file,
+             input_sha256 = excluded.input_sha256,
+             submitted_at = excluded.submitted_at,
+             download_dir = excluded.download_dir,
+             started_at = excluded.started_at,
+             finished_at = excluded.finished_at,
+             profile = excluded.profile",
+        params![
+            job.job_id,
+            job.tool,
+            job.stage,
+            job.failed,
+            job.terminal,
+            job.date_submitted,
+            job.url,
+            job.updated_at,
+            job.input_file,
+            job.input_sha256,
+            job.submitted_at,
+            job.download_dir
+                .as_ref()
+                .map(|dir| dir.to_string_lossy().into_owned()),
+            job.started_at,
+            job.finished_at,
+            job.profile,
+        ],
+    )?;
+
+    tx.execute("DELETE FROM job_params WHERE job_id = ?1", [&job.job_id])?;
+    for (name, value) in &job.params {
+        tx.execute(
+            "INSERT INTO job_params (job_id, name, value) VALUES (?1, ?2, ?3)",
+            params![job.job_id, name, value],
+        )?;
+    }
+
+    tx.execute("DELETE FROM job_inputs WHERE job_id = ?1", [&job.job_id])?;
+    for (name, path) in &job.inputs {
+        tx.execute(
+            "INSERT INTO job_inputs (job_id, name, path) VALUES (?1, ?2, ?3)",
+            params![job.job_id, name, path.to_string_lossy()],
+        )?;
+    }
+
+    tx.execute("DELETE FROM job_tags WHERE job_id = ?1", [&job.job_id])?;
+    for (position, tag) in job.tags.iter().enumerate() {
+        tx.execute(
+            "INSERT OR IGNORE INTO job_tags (job_id, position, tag) VALUES (?1, ?2, ?3)",
+            params![job.job_id, position as i64, tag],
+        )?;
+    }
+
+    tx.execute("DELETE FROM job_notes WHERE job_id = ?1", [&job.job_id])?;
+    for note in &job.notes {
+        tx.execute(
+            "INSERT INTO job_notes (job_id, added_at, text) VALUES (?1, ?2, ?3)",
+            params![job.job_id, note.added_at, note.text],
+        )?;
+    }
+
+    Ok(())
+}
@@ -1,91 +1,570 @@
 use crate::config::Credentials;
+use crate::error::{api_error, response_error, NsgError, Result, ResultExt};
+use crate::filenames::{long_path, partial_path, FileFilter, FilenameMapper};
+use crate::limiter::{ConcurrencyLimiter, Permit, RateLimiter};
 use crate::models::*;
-use anyhow::{Context, Result};
-use reqwest::blocking::{multipart, Client};
+use crate::retry::{is_transient_error, is_transient_status, RetryPolicy};
+use crate::timeouts::Timeouts;
+use reqwest::blocking::{multipart, Client, RequestBuilder, Response};
+use reqwest::StatusCode;
+use std::collections::HashSet;
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-const NSG_BASE_URL: &str = "https://nsgr.sdsc.edu:8443/cipresrest/v1";
+pub(crate) const NSG_BASE_URL: &str = "https://nsgr.sdsc.edu:8443/cipresrest/v1";
+
+/// Check that `url` can be used as the API base: an absolute http(s) URL.
+/// Returns it without a trailing slash, since paths are appended to it.
+pub fn parse_base_url(url: &str) -> Result<String, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid URL '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("'{}' is not an http:// or https:// URL", url));
+    }
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+/// In-flight request cap used when `--max-concurrency` is not given
+pub const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
+/// Requests per second used when `--rate-limit` is not given
+pub const DEFAULT_RATE_LIMIT: f64 = 5.0;
+
+/// Longest response body `debug` logging prints; the response itself is
+/// passed on whole
+const MAX_LOGGED_BODY: usize = 64 * 1024;
+
+/// Only responses up to this size are buffered to be logged
+const MAX_BUFFERED_BODY: u64 = 1024 * 1024;
+
+/// Output files downloaded in parallel unless told otherwise
+pub const DEFAULT_DOWNLOAD_JOBS: usize = 4;
+
+/// `nsg-cli/<version> (+<repository>)`, sent unless overridden
+pub fn default_user_agent() -> String {
+    format!(
+        "{}/{} (+{})",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        env!("CARGO_PKG_REPOSITORY")
+    )
+}
+
+/// Optional behaviour for [`NsgClient`] beyond credentials and base URL
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    /// Print an equivalent curl command to stderr before every API call
+    pub print_curl: bool,
+    /// Replace the User-Agent header entirely
+    pub user_agent: Option<String>,
+    /// Contact or workflow tag appended to the default User-Agent, so NSG
+    /// admins can tell automated pipelines apart (e.g. `sweep-A; me@lab.org`)
+    pub user_agent_tag: Option<String>,
+    /// Cap on simultaneous requests/transfers across all threads sharing
+    /// the client (defaults to [`DEFAULT_MAX_CONCURRENCY`])
+    pub max_concurrency: Option<usize>,
+    /// Average requests per second across all threads sharing the client
+    /// (defaults to [`DEFAULT_RATE_LIMIT`]; zero or less disables the limit)
+    pub rate_limit: Option<f64>,
+    /// Log every request to stderr (1), plus its status and timing (2+)
+    pub verbose: u8,
+    /// Log every request and response in full to stderr: headers, and the
+    /// bodies of API responses, with credentials redacted
+    pub debug: bool,
+    /// NSG REST endpoint to talk to instead of the production server
+    pub base_url: Option<String>,
+    /// Re-sending of requests that hit timeouts, dropped connections or 5xx
+    /// responses. Uploads are never retried.
+    pub retry: RetryPolicy,
+    /// Limits on connecting, waiting for responses and uploading
+    pub timeouts: Timeouts,
+    /// PEM file with extra root certificates to trust, e.g. those of a
+    /// TLS-intercepting proxy
+    pub ca_cert: Option<PathBuf>,
+    /// Skip TLS certificate verification altogether
+    pub insecure: bool,
+}
+
+/// How [`NsgClient::download_results_with`] fetches a job's outputs
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Which output files to fetch (default: all)
+    pub filter: FileFilter,
+    /// Files transferred at once; still capped by the client's
+    /// `max_concurrency`
+    pub jobs: usize,
+    /// Continue from a `.part` file left by an interrupted download instead
+    /// of starting over
+    pub resume: bool,
+    /// Leave files alone whose local copy already has the remote size
+    pub skip_unchanged: bool,
+    /// Fetch a running job's working directory (intermediate results)
+    /// instead of its final outputs
+    pub working_dir: bool,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            filter: FileFilter::default(),
+            jobs: DEFAULT_DOWNLOAD_JOBS,
+            resume: true,
+            skip_unchanged: false,
+            working_dir: false,
+        }
+    }
+}
+
+/// What [`NsgClient::download_results_with`] reports as it goes, with files
+/// named as NSG lists them. Owned, so events can be sent on to a channel.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum DownloadEvent {
+    /// A file's transfer is starting. `size` is `None` if NSG didn't say
+    /// how large the file is, so it can't be checked for completeness.
+    FileStarted { filename: String, size: Option<u64> },
+    /// `downloaded` of the file's `total` bytes are on disk, counting any
+    /// resumed from an earlier attempt
+    Progress {
+        filename: String,
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    /// A file is complete, or already was (`file.up_to_date`, without a
+    /// `FileStarted`)
+    FileFinished { file: DownloadedFile },
+    /// A file couldn't be downloaded. No more files are started, and the
+    /// download returns this error.
+    FileFailed { filename: String, error: String },
+    /// Every file is done; the last event of a successful download
+    AllDone { files: usize, bytes: u64 },
+}
+
+/// What [`NsgClient::submit_job_with_progress`] reports while it sends the
+/// input files, named as they are on disk
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum UploadEvent {
+    /// A file's contents are starting to go out
+    FileStarted { filename: String, size: u64 },
+    /// `uploaded` of the file's `size` bytes have been sent
+    Progress {
+        filename: String,
+        uploaded: u64,
+        size: u64,
+    },
+    /// All of a file has been sent. A submission that fails partway through
+    /// a file ends without this.
+    FileFinished { filename: String, size: u64 },
+}
+
+/// An input file as the multipart body reads it, reporting how far it got
+struct UploadReader {
+    file: std::fs::File,
+    filename: String,
+    size: u64,
+    uploaded: u64,
+    started: bool,
+    finished: bool,
+    on_event: Arc<dyn Fn(UploadEvent) + Send + Sync>,
+}
+
+impl Read for UploadReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if !self.started {
+            self.started = true;
+            (self.on_event)(UploadEvent::FileStarted {
+                filename: self.filename.clone(),
+                size: self.size,
+            });
+        }
+
+        let n = self.file.read(buf)?;
+        self.uploaded += n as u64;
+        if n > 0 {
+            (self.on_event)(UploadEvent::Progress {
+                filename: self.filename.clone(),
+                uploaded: self.uploaded,
+                size: self.size,
+            });
+        }
+        if (n == 0 || self.uploaded >= self.size) && !self.finished {
+            self.finished = true;
+            (self.on_event)(UploadEvent::FileFinished {
+                filename: self.filename.clone(),
+                size: self.uploaded,
+            });
+        }
+        Ok(n)
+    }
+}
+
+impl ClientOptions {
+    pub fn user_agent(&self) -> String {
+        match (&self.user_agent, &self.user_agent_tag) {
+            (Some(ua), _) => ua.clone(),
+            (None, Some(tag)) => format!("{} {}", default_user_agent(), tag),
+            (None, None) => default_user_agent(),
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        self.base_url
+            .as_deref()
+            .unwrap_or(NSG_BASE_URL)
+            .trim_end_matches('/')
+            .to_string()
+    }
+}
 
 pub struct NsgClient {
     client: Client,
+    /// For the contents of output files, limited only on going quiet
+    downloads: Client,
     credentials: Credentials,
     base_url: String,
+    options: ClientOptions,
+    limiter: ConcurrencyLimiter,
+    rate: RateLimiter,
 }
 
 impl NsgClient {
     pub fn new(credentials: Credentials) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .context("Failed to create HTTP client")?;
+        Self::with_options(credentials, ClientOptions::default())
+    }
+
+    pub fn with_options(credentials: Credentials, options: ClientOptions) -> Result<Self> {
+        let certificates = match &options.ca_cert {
+            Some(path) => load_ca_bundle(path)?,
+            None => Vec::new(),
+        };
+        // The blocking client's timeout applies to each wait on its own: for
+        // the response, then for every read of the body. File contents get a
+        // client of their own whose timeout is the read-idle one, so a large
+        // download may take as long as it needs while data keeps coming.
+        let build = |timeout: Option<Duration>| {
+            let mut builder = Client::builder()
+                .user_agent(options.user_agent())
+                .connect_timeout(options.timeouts.connect)
+                .timeout(timeout)
+                .danger_accept_invalid_certs(options.insecure);
+            for certificate in &certificates {
+                builder = builder.add_root_certificate(certificate.clone());
+            }
+            builder.build().context("Failed to create HTTP client")
+        };
+        let client = build(options.timeouts.request)?;
+        let downloads = build(options.timeouts.read)?;
+
+        let limiter =
+            ConcurrencyLimiter::new(options.max_concurrency.unwrap_or(DEFAULT_MAX_CONCURRENCY));
+        let rate = RateLimiter::new(Some(options.rate_limit.unwrap_or(DEFAULT_RATE_LIMIT)));
 
         Ok(Self {
             client,
+            downloads,
             credentials,
-            base_url: NSG_BASE_URL.to_string(),
+            base_url: options.base_url(),
+            options,
+            limiter,
+            rate,
         })
     }
 
     pub fn new_with_url(credentials: Credentials, base_url: String) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .context("Failed to create HTTP client")?;
+        let mut client = Self::new(credentials)?;
+        client.base_url = base_url;
+        Ok(client)
+    }
 
-        Ok(Self {
-            client,
-            credentials,
-            base_url,
-        })
+    /// The effective cap on in-flight requests
+    pub fn max_concurrency(&self) -> usize {
+        self.limiter.limit()
     }
 
     fn build_request(
         &self,
         method: reqwest::Method,
         path: &str,
+    ) -> reqwest::blocking::RequestBuilder {
+        self.build_form_request(method, path, &[])
+    }
+
+    /// A GET of a file's contents, with no deadline on the download as a
+    /// whole
+    fn build_download_request(&self, path: &str) -> reqwest::blocking::RequestBuilder {
+        self.request_on(&self.downloads, reqwest::Method::GET, path, &[])
+    }
+
+    /// Like `build_request`, but `form` lists the multipart fields the caller
+    /// is about to attach so they show up in `--curl` output. File fields are
+    /// given as `@path`, matching curl's own syntax.
+    fn build_form_request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        form: &[(String, String)],
+    ) -> reqwest::blocking::RequestBuilder {
+        self.request_on(&self.client, method, path, form)
+    }
+
+    fn request_on(
+        &self,
+        client: &Client,
+        method: reqwest::Method,
+        path: &str,
+        form: &[(String, String)],
     ) -> reqwest::blocking::RequestBuilder {
         let url = format!("{}{}", self.base_url, path);
-        self.client
+
+        if self.options.print_curl {
+            eprintln!(
+                "{}",
+                curl_command(
+                    &method,
+                    &url,
+                    &self.credentials,
+                    &self.options.user_agent(),
+                    form
+                )
+            );
+        }
+
+        client
             .request(method, &url)
             .basic_auth(&self.credentials.username, Some(&self.credentials.password))
             .header("cipres-appkey", &self.credentials.app_key)
     }
 
+    /// Send `request`, retrying transient failures as allowed by the retry
+    /// policy. Requests whose body can't be replayed (file uploads) are only
+    /// sent once.
+    fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let policy = self.options.retry;
+        let mut retry = 0;
+
+        loop {
+            let replay = if retry < policy.retries {
+                request.try_clone()
+            } else {
+                None
+            };
+            let Some(next) = replay else {
+                return self.send_once(request);
+            };
+
+            let reason = match self.send_once(next) {
+                Ok(response) if is_transient_status(response.status()) => {
+                    format!("HTTP {}", response.status())
+                }
+                Err(e) if is_transient_error(&e) => e.to_string(),
+                result => return result,
+            };
+
+            retry += 1;
+            let delay = policy.delay(retry);
+            if self.options.verbose >= 1 {
+                eprintln!(
+                    "! {}; retry {}/{} in {:.1}s",
+                    reason,
+                    retry,
+                    policy.retries,
+                    delay.as_secs_f64()
+                );
+            }
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// Send `request` once, as soon as the rate limit allows, logging it to
+    /// stderr at `-v`, its outcome at `-vv`, and both in full with `debug`.
+    /// It goes out on the client that built it: the blocking client applies
+    /// the timeout of the one executing it, and downloads need theirs.
+    fn send_once(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let debug = self.options.debug;
+        let verbose = if debug { 2 } else { self.options.verbose };
+
+        let (client, request) = request.build_split();
+        let request = request?;
+        let waited = self.rate.acquire();
+        if verbose >= 1 {
+            if verbose >= 2 && !waited.is_zero() {
+                eprintln!("  (rate limit: waited {} ms)", waited.as_millis());
+            }
+            if debug {
+                eprintln!(
+                    "> [{}] {} {}",
+                    chrono::Local::now().format("%H:%M:%S%.3f"),
+                    request.method(),
+                    request.url()
+                );
+                for (name, value) in request.headers() {
+                    eprintln!(">   {}: {}", name, self.redact_header(name, value));
+                }
+            } else {
+                eprintln!("> {} {}", request.method(), request.url());
+            }
+        }
+
+        let started = Instant::now();
+        let result = client.execute(request);
+
+        if verbose >= 2 {
+            let elapsed = started.elapsed().as_millis();
+            match &result {
+                Ok(response) => eprintln!(
+                    "< {} ({} ms, {} bytes)",
+                    response.status(),
+                    elapsed,
+                    response
+                        .content_length()
+                        .map(|len| len.to_string())
+                        .unwrap_or_else(|| "?".to_string())
+                ),
+                Err(e) => eprintln!("< {} ({} ms)", e, elapsed),
+            }
+        }
+
+        match result {
+            Ok(response) if debug => self.log_response(response),
+            result => result,
+        }
+    }
+
+    /// Log the headers and, if it's a reasonably small API response, the
+    /// body of `response`, and return an equivalent response to read from
+    fn log_response(&self, response: Response) -> reqwest::Result<Response> {
+        for (name, value) in response.headers() {
+            eprintln!("<   {}: {}", name, self.redact_header(name, value));
+        }
+
+        let textual = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|kind| {
+                ["xml", "json", "text/html"]
+                    .iter()
+                    .any(|t| kind.contains(t))
+            });
+        let small = response
+            .content_length()
+            .is_none_or(|len| len <= MAX_BUFFERED_BODY);
+        if !textual || !small {
+            eprintln!("<   (body not logged)");
+            return Ok(response);
+        }
+
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes()?;
+
+        let text = self.redact(&String::from_utf8_lossy(&body));
+        if text.len() > MAX_LOGGED_BODY {
+            let end = (0..=MAX_LOGGED_BODY)
+                .rev()
+                .find(|&i| text.is_char_boundary(i))
+                .unwrap_or(0);
+            eprintln!("{}", &text[..end]);
+            eprintln!("<   ({} more bytes not logged)", text.len() - end);
+        } else {
+            eprintln!("{}", text.trim_end());
+        }
+
+        let mut rebuilt = http::Response::new(body);
+        *rebuilt.status_mut() = status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = headers;
+        Ok(Response::from(rebuilt))
+    }
+
+    /// A header value as it may be logged: credentials are replaced
+    fn redact_header(
+        &self,
+        name: &reqwest::header::HeaderName,
+        value: &reqwest::header::HeaderValue,
+    ) -> String {
+        if name == reqwest::header::AUTHORIZATION || name.as_str() == "cipres-appkey" {
+            return "<redacted>".to_string();
+        }
+        self.redact(value.to_str().unwrap_or("<binary>"))
+    }
+
+    /// `text` with the password and application key replaced
+    fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for secret in [&self.credentials.password, &self.credentials.app_key] {
+            if !secret.is_empty() {
+                text = text.replace(secret.as_str(), "<redacted>");
+            }
+        }
+        text
+    }
+
     pub fn test_connection(&self) -> Result<()> {
         let path = format!("/job/{}", self.credentials.username);
+        let _permit = self.limiter.acquire();
         let response = self
-            .build_request(reqwest::Method::GET, &path)
-            .send()
+            .send(self.build_request(reqwest::Method::GET, &path))
             .context("Failed to connect to NSG API")?;
 
         if !response.status().is_success() {
-            anyhow::bail!(
-                "Authentication failed: HTTP {} - Check your credentials",
-                response.status()
-            );
+            return Err(api_error(
+                response.status(),
+                format!(
+                    "Authentication failed: HTTP {} - Check your credentials",
+                    response.status()
+                ),
+            ));
         }
 
         Ok(())
     }
 
+    /// All of the user's jobs, following the job list across pages
     pub fn list_jobs(&self) -> Result<Vec<JobSummary>> {
-        let path = format!("/job/{}", self.credentials.username);
+        let mut jobs = Vec::new();
+        for page in self.jobs_paged() {
+            jobs.extend(page?);
+        }
+        Ok(jobs)
+    }
+
+    /// The job list one page at a time; each page is only fetched when the
+    /// iterator gets to it, so callers can stop early. Ends after an error.
+    pub fn jobs_paged(&self) -> JobPages<'_> {
+        JobPages {
+            client: self,
+            next: Some(format!("/job/{}", self.credentials.username)),
+            seen: HashSet::new(),
+        }
+    }
+
+    fn fetch_job_page(&self, path: &str) -> Result<JobPage> {
+        let _permit = self.limiter.acquire();
         let response = self
-            .build_request(reqwest::Method::GET, &path)
-            .send()
+            .send(self.build_request(reqwest::Method::GET, path))
             .context("Failed to fetch job list")?;
 
         if !response.status().is_success() {
-            anyhow::bail!("Failed to list jobs: HTTP {}", response.status());
+            return Err(api_error(
+                response.status(),
+                format!("Failed to list jobs: HTTP {}", response.status()),
+            ));
         }
 
+        let link = next_link(response.headers());
         let body = response.text()?;
-        parse_job_list(&body)
+        let mut page = parse_job_page(&body)?;
+        page.next = page.next.or(link);
+        Ok(page)
     }
 
-    pub fn get_job_status(&self, job_url_or_id: &str) -> Result<JobStatus> {
-        let path = if job_url_or_id.starts_with("http") {
+    /// API path of a job given its URL, `/job/...` path or bare ID
+    fn job_path(&self, job_url_or_id: &str) -> Result<String> {
+        Ok(if job_url_or_id.starts_with("http") {
             job_url_or_id
                 .strip_prefix(&self.base_url)
                 .context("Invalid job URL")?
@@ -94,147 +573,957 @@ impl NsgClient {
             job_url_or_id.to_string()
         } else {
             format!("/job/{}/{}", self.credentials.username, job_url_or_id)
-        };
+        })
+    }
+
+    pub fn get_job_status(&self, job_url_or_id: &str) -> Result<JobStatus> {
+        let path = self.job_path(job_url_or_id)?;
+
+        let _permit = self.limiter.acquire();
 
         let response = self
-            .build_request(reqwest::Method::GET, &path)
-            .send()
+            .send(self.build_request(reqwest::Method::GET, &path))
             .context("Failed to fetch job status")?;
 
         if !response.status().is_success() {
-            anyhow::bail!(
-                "Failed to get job status: HTTP {}\nJob: {}",
+            return Err(api_error(
                 response.status(),
-                job_url_or_id
-            );
+                format!(
+                    "Failed to get job status: HTTP {}\nJob: {}",
+                    response.status(),
+                    job_url_or_id
+                ),
+            ));
         }
 
         let body = response.text()?;
         parse_job_status(&body)
     }
 
+    /// Fetch the status of each of `jobs` in parallel, up to
+    /// `max_concurrency` at once, returned in the order given
+    pub fn get_job_statuses<S: AsRef<str> + Sync>(&self, jobs: &[S]) -> Vec<Result<JobStatus>> {
+        let queue = Mutex::new(jobs.iter().enumerate());
+        let results = Mutex::new(Vec::with_capacity(jobs.len()));
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.max_concurrency().min(jobs.len()) {
+                scope.spawn(|| loop {
+                    let next = queue.lock().unwrap_or_else(|e| e.into_inner()).next();
+                    let Some((index, job)) = next else {
+                        break;
+                    };
+                    let status = self.get_job_status(job.as_ref());
+                    results
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push((index, status));
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, status)| status).collect()
+    }
+
+    /// Cancel a queued or running job.
+    ///
+    /// NSG has a single DELETE operation for jobs, so this also removes the
+    /// job from the job list, exactly like [`delete_job`](Self::delete_job).
+    pub fn cancel_job(&self, job_url_or_id: &str) -> Result<()> {
+        self.delete_job(job_url_or_id)
+    }
+
+    /// Remove a job and its results from the server (DELETE on the job's URL)
+    pub fn delete_job(&self, job_url_or_id: &str) -> Result<()> {
+        let path = self.job_path(job_url_or_id)?;
+
+        let _permit = self.limiter.acquire();
+
+        let response = self
+            .send(self.build_request(reqwest::Method::DELETE, &path))
+            .context("Failed to delete job")?;
+
+        if !response.status().is_success() {
+            return Err(api_error(
+                response.status(),
+                format!(
+                    "Failed to delete job: HTTP {}\nJob: {}",
+                    response.status(),
+                    job_url_or_id
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
     pub fn submit_job(&self, zip_path: &Path, tool: &str) -> Result<JobStatus> {
+        self.submit_job_with_params(zip_path, tool, &[])
+    }
+
+    /// Submit a job, passing each `(name, value)` in `params` as a form field.
+    ///
+    /// Plain names are tool parameters and sent as `vparam.<name>`; names
+    /// starting with `metadata.` are sent as-is. See [`submit_fields`].
+    pub fn submit_job_with_params(
+        &self,
+        zip_path: &Path,
+        tool: &str,
+        params: &[(String, String)],
+    ) -> Result<JobStatus> {
+        self.submit_job_with_inputs(
+            tool,
+            &[("infile_".to_string(), zip_path.to_path_buf())],
+            params,
+        )
+    }
+
+    /// Submit a job with several input files, each sent as `input.<name>`.
+    ///
+    /// Most tools take a single `infile_` archive; some take additional
+    /// parts such as a separate parameter file.
+    pub fn submit_job_with_inputs(
+        &self,
+        tool: &str,
+        inputs: &[(String, PathBuf)],
+        params: &[(String, String)],
+    ) -> Result<JobStatus> {
+        self.submit_job_with_progress(tool, inputs, params, |_| {})
+    }
+
+    /// Like [`submit_job_with_inputs`](Self::submit_job_with_inputs),
+    /// passing each [`UploadEvent`] to `on_event` as the files are sent.
+    /// Events come from the calling thread.
+    pub fn submit_job_with_progress<F>(
+        &self,
+        tool: &str,
+        inputs: &[(String, PathBuf)],
+        params: &[(String, String)],
+        on_event: F,
+    ) -> Result<JobStatus>
+    where
+        F: Fn(UploadEvent) + Send + Sync + 'static,
+    {
         let path = format!("/job/{}", self.credentials.username);
+        let on_event: Arc<dyn Fn(UploadEvent) + Send + Sync> = Arc::new(on_event);
 
-        let file_part = multipart::Part::file(zip_path)
-            .context("Failed to read ZIP file")?
-            .file_name(
-                zip_path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("job.zip")
-                    .to_string(),
-            );
+        let mut form = multipart::Form::new().text("tool", tool.to_string());
+        let mut curl_form = vec![("tool".to_string(), tool.to_string())];
+        let mut upload_size = 0;
 
-        let form = multipart::Form::new()
-            .text("tool", tool.to_string())
-            .part("input.infile_", file_part)
-            .text("metadata.statusEmail", "true");
+        for (name, input_path) in inputs {
+            let file = std::fs::File::open(input_path)
+                .with_context(|| format!("Failed to read {}", input_path.display()))?;
+            let size = file
+                .metadata()
+                .with_context(|| format!("Failed to read {}", input_path.display()))?
+                .len();
+            upload_size += size;
 
-        let response = self
-            .build_request(reqwest::Method::POST, &path)
-            .multipart(form)
-            .timeout(std::time::Duration::from_secs(60))
-            .send()
-            .context("Failed to submit job")?;
+            let filename = input_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("job.zip")
+                .to_string();
+            let reader = UploadReader {
+                file,
+                filename: filename.clone(),
+                size,
+                uploaded: 0,
+                started: false,
+                finished: false,
+                on_event: Arc::clone(&on_event),
+            };
+            // The same name and type `multipart::Part::file` would give it
+            let mime = mime_guess::from_path(input_path).first_or_octet_stream();
+            let file_part = multipart::Part::reader_with_length(reader, size)
+                .file_name(filename)
+                .mime_str(mime.as_ref())?;
+
+            let field = format!("input.{}", name);
+            form = form.part(field.clone(), file_part);
+            curl_form.push((field, format!("@{}", input_path.display())));
+        }
+
+        for (field, value) in submit_fields(params) {
+            form = form.text(field.clone(), value.clone());
+            curl_form.push((field, value));
+        }
+
+        let _permit = self.limiter.acquire();
+
+        let mut request = self
+            .build_form_request(reqwest::Method::POST, &path, &curl_form)
+            .multipart(form);
+        if let Some(timeout) = self.options.timeouts.upload(upload_size) {
+            request = request.timeout(timeout);
+        }
+        let response = self.send(request).context("Failed to submit job")?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().unwrap_or_default();
-            anyhow::bail!("Failed to submit job: HTTP {}\nResponse: {}", status, body);
+            return Err(response_error(status, "Failed to submit job", &body));
         }
 
         let body = response.text()?;
         parse_job_status(&body)
     }
 
-    pub fn download_results<F>(
-        &self,
-        job_url_or_id: &str,
-        output_dir: &Path,
-        mut progress_callback: F,
-    ) -> Result<Vec<DownloadedFile>>
-    where
-        F: FnMut(&str, u64, u64), // (filename, bytes_downloaded, total_bytes)
-    {
-        let job_status = self.get_job_status(job_url_or_id)?;
+    pub fn list_tools(&self) -> Result<Vec<ToolInfo>> {
+        let _permit = self.limiter.acquire();
+        let response = self
+            .send(self.build_request(reqwest::Method::GET, "/tool"))
+            .context("Failed to fetch tool list")?;
+
+        if !response.status().is_success() {
+            return Err(api_error(
+                response.status(),
+                format!("Failed to fetch tool list: HTTP {}", response.status()),
+            ));
+        }
+
+        let body = response.text()?;
+        parse_tool_list(&body)
+    }
+
+    /// Fetch a tool's PISE XML parameter description
+    pub fn get_tool_xml(&self, tool: &str) -> Result<String> {
+        let path = format!("/tool/{}/doc/pise", tool);
+        let _permit = self.limiter.acquire();
+        let response = self
+            .send(self.build_request(reqwest::Method::GET, &path))
+            .with_context(|| format!("Failed to fetch description of tool {}", tool))?;
+
+        if !response.status().is_success() {
+            return Err(api_error(
+                response.status(),
+                format!(
+                    "Failed to fetch description of tool {}: HTTP {}",
+                    tool,
+                    response.status()
+                ),
+            ));
+        }
 
+        Ok(response.text()?)
+    }
+
+    /// List the output files of a finished job, to fetch with
+    /// [`download_file`](Self::download_file)
+    pub fn get_output_files(&self, job_status: &JobStatus) -> Result<Vec<OutputFile>> {
         let results_url = job_status
             .results_uri
+            .as_deref()
             .context("Job has no results URL - may not be completed yet")?;
 
         let results_path = results_url
             .strip_prefix(&self.base_url)
             .context("Invalid results URL")?;
 
+        let _permit = self.limiter.acquire();
+
         let response = self
-            .build_request(reqwest::Method::GET, results_path)
-            .send()
+            .send(self.build_request(reqwest::Method::GET, results_path))
             .context("Failed to fetch results list")?;
 
         if !response.status().is_success() {
-            anyhow::bail!("Failed to get results: HTTP {}", response.status());
+            return Err(api_error(
+                response.status(),
+                format!("Failed to get results: HTTP {}", response.status()),
+            ));
         }
 
         let body = response.text()?;
-        let output_files = parse_output_files(&body)?;
+        parse_output_files(&body)
+    }
+
+    /// List the files currently in a running job's working directory
+    pub fn list_working_dir(&self, job_status: &JobStatus) -> Result<Vec<OutputFile>> {
+        let working_dir_url = job_status
+            .working_dir_uri
+            .as_deref()
+            .context("Job has no working directory URL - it may not have started yet")?;
 
-        std::fs::create_dir_all(output_dir).context("Failed to create output directory")?;
+        let working_dir_path = working_dir_url
+            .strip_prefix(&self.base_url)
+            .context("Invalid working directory URL")?;
 
-        let mut downloaded = Vec::new();
+        let _permit = self.limiter.acquire();
 
-        for file in output_files {
-            let download_path = file
-                .download_uri
-                .strip_prefix(&self.base_url)
-                .context("Invalid download URL")?;
+        let response = self
+            .send(self.build_request(reqwest::Method::GET, working_dir_path))
+            .context("Failed to fetch working directory listing")?;
+
+        if !response.status().is_success() {
+            return Err(api_error(
+                response.status(),
+                format!(
+                    "Failed to list working directory: HTTP {}",
+                    response.status()
+                ),
+            ));
+        }
+
+        let body = response.text()?;
+        parse_output_files(&body)
+    }
 
-            let output_path = output_dir.join(&file.filename);
+    /// Fetch the bytes of `file` from `offset` onwards using an HTTP Range
+    /// request. Returns an empty buffer if the file hasn't grown past `offset`.
+    #[cfg(feature = "cli")]
+    pub(crate) fn read_file_from(&self, file: &OutputFile, offset: u64) -> Result<Vec<u8>> {
+        let download_path = file
+            .download_uri
+            .strip_prefix(&self.base_url)
+            .context("Invalid download URL")?;
 
-            let mut response = self
-                .build_request(reqwest::Method::GET, download_path)
-                .send()
-                .with_context(|| format!("Failed to download {}", file.filename))?;
+        let _permit = self.limiter.acquire();
 
-            if !response.status().is_success() {
-                anyhow::bail!(
+        let mut response = self
+            .send(
+                self.build_download_request(download_path)
+                    .header(reqwest::header::RANGE, format!("bytes={}-", offset)),
+            )
+            .with_context(|| format!("Failed to download {}", file.filename))?;
+
+        if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            return Ok(Vec::new());
+        }
+
+        if !response.status().is_success() {
+            return Err(api_error(
+                response.status(),
+                format!(
                     "Failed to download {}: HTTP {}",
                     file.filename,
                     response.status()
+                ),
+            ));
+        }
+
+        let mut contents = Vec::new();
+        response
+            .read_to_end(&mut contents)
+            .with_context(|| format!("Failed to read from {}", file.filename))?;
+
+        // A server that ignores Range sends the whole file
+        if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            let skip = (offset as usize).min(contents.len());
+            contents.drain(..skip);
+        }
+
+        Ok(contents)
+    }
+
+    /// Open `file` to be read as it arrives, without saving it anywhere
+    pub fn open_output_file(&self, file: &OutputFile) -> Result<OutputStream<'_>> {
+        let download_path = file
+            .download_uri
+            .strip_prefix(&self.base_url)
+            .context("Invalid download URL")?;
+
+        let permit = self.limiter.acquire();
+
+        let response = self
+            .send(self.build_download_request(download_path))
+            .with_context(|| format!("Failed to download {}", file.filename))?;
+
+        if !response.status().is_success() {
+            return Err(api_error(
+                response.status(),
+                format!(
+                    "Failed to download {}: HTTP {}",
+                    file.filename,
+                    response.status()
+                ),
+            ));
+        }
+
+        Ok(OutputStream {
+            response,
+            _permit: permit,
+        })
+    }
+
+    /// Fetch an output file into memory, keeping at most the last `max_bytes`
+    #[cfg(feature = "cli")]
+    pub(crate) fn read_output_file_tail(
+        &self,
+        file: &OutputFile,
+        max_bytes: usize,
+    ) -> Result<Vec<u8>> {
+        let download_path = file
+            .download_uri
+            .strip_prefix(&self.base_url)
+            .context("Invalid download URL")?;
+
+        let _permit = self.limiter.acquire();
+
+        let mut response = self
+            .send(self.build_download_request(download_path))
+            .with_context(|| format!("Failed to download {}", file.filename))?;
+
+        if !response.status().is_success() {
+            return Err(api_error(
+                response.status(),
+                format!(
+                    "Failed to download {}: HTTP {}",
+                    file.filename,
+                    response.status()
+                ),
+            ));
+        }
+
+        let mut contents = Vec::new();
+        let mut buffer = [0u8; 8192];
+
+        loop {
+            let bytes_read = response
+                .read(&mut buffer)
+                .with_context(|| format!("Failed to read from {}", file.filename))?;
+
+            if bytes_read == 0 {
+                break;
+            }
+
+            contents.extend_from_slice(&buffer[..bytes_read]);
+            if contents.len() > max_bytes {
+                contents.drain(..contents.len() - max_bytes);
+            }
+        }
+
+        Ok(contents)
+    }
+
+    /// Download all of a job's output files into `output_dir`, passing each
+    /// [`DownloadEvent`] to `on_event`
+    pub fn download_results<F>(
+        &self,
+        job_url_or_id: &str,
+        output_dir: &Path,
+        on_event: F,
+    ) -> Result<Vec<DownloadedFile>>
+    where
+        F: FnMut(DownloadEvent) + Send,
+    {
+        self.download_results_with(
+            job_url_or_id,
+            output_dir,
+            &DownloadOptions::default(),
+            on_event,
+        )
+    }
+
+    /// Like [`download_results`](Self::download_results), with a file filter
+    /// and up to `options.jobs` files transferred at once.
+    ///
+    /// `on_event` may be called from several worker threads, but never
+    /// concurrently; events for different files interleave.
+    pub fn download_results_with<F>(
+        &self,
+        job_url_or_id: &str,
+        output_dir: &Path,
+        options: &DownloadOptions,
+        on_event: F,
+    ) -> Result<Vec<DownloadedFile>>
+    where
+        F: FnMut(DownloadEvent) + Send,
+    {
+        let job_status = self.get_job_status(job_url_or_id)?;
+        let mut output_files = if options.working_dir {
+            self.list_working_dir(&job_status)?
+        } else {
+            self.get_output_files(&job_status)?
+        };
+        output_files.retain(|f| options.filter.matches(&f.filename));
+
+        std::fs::create_dir_all(long_path(output_dir))
+            .context("Failed to create output directory")?;
+
+        // Remote names are untrusted: never let them leave output_dir. Names
+        // are assigned up front so collisions resolve the same way every run.
+        let mut names = FilenameMapper::new();
+        let planned: Vec<(OutputFile, String)> = output_files
+            .into_iter()
+            .map(|file| {
+                let local_name = names.local_name(&file.filename);
+                (file, local_name)
+            })
+            .collect();
+
+        let queue = Mutex::new(planned.iter().enumerate());
+        let results = Mutex::new(Vec::with_capacity(planned.len()));
+        let first_error = Mutex::new(None);
+        let on_event = Mutex::new(on_event);
+        let emit = |event| (on_event.lock().unwrap_or_else(|e| e.into_inner()))(event);
+        let workers = options.jobs.clamp(1, planned.len().max(1));
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    if first_error
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .is_some()
+                    {
+                        break;
+                    }
+                    let next = queue.lock().unwrap_or_else(|e| e.into_inner()).next();
+                    let Some((index, (file, local_name))) = next else {
+                        break;
+                    };
+
+                    let output_path = output_dir.join(local_name);
+                    let up_to_date = options.skip_unchanged
+                        && file
+                            .size
+                            .is_some_and(|size| is_unchanged(&output_path, size));
+                    let result = if up_to_date {
+                        Ok(file.size.unwrap_or_default())
+                    } else {
+                        emit(DownloadEvent::FileStarted {
+                            filename: file.filename.clone(),
+                            size: file.size,
+                        });
+                        self.download_file_with(file, &output_path, options, |bytes| {
+                            emit(DownloadEvent::Progress {
+                                filename: file.filename.clone(),
+                                downloaded: bytes,
+                                total: file.size,
+                            })
+                        })
+                    };
+
+                    match result {
+                        Ok(size) => {
+                            let downloaded = DownloadedFile {
+                                filename: local_name.clone(),
+                                remote_filename: file.filename.clone(),
+                                path: output_path,
+                                size,
+                                up_to_date,
+                            };
+                            emit(DownloadEvent::FileFinished {
+                                file: downloaded.clone(),
+                            });
+                            results
+                                .lock()
+                                .unwrap_or_else(|e| e.into_inner())
+                                .push((index, downloaded));
+                        }
+                        Err(e) => {
+                            emit(DownloadEvent::FileFailed {
+                                filename: file.filename.clone(),
+                                error: e.to_string(),
+                            });
+                            first_error
+                                .lock()
+                                .unwrap_or_else(|e| e.into_inner())
+                                .get_or_insert(e);
+                        }
+                    }
+                });
+            }
+        });
+
+        if let Some(e) = first_error.into_inner().unwrap_or_else(|e| e.into_inner()) {
+            return Err(e);
+        }
+
+        names.write_record(output_dir)?;
+
+        let mut downloaded = results.into_inner().unwrap_or_else(|e| e.into_inner());
+        downloaded.sort_by_key(|(index, _)| *index);
+        let downloaded: Vec<DownloadedFile> =
+            downloaded.into_iter().map(|(_, file)| file).collect();
+        emit(DownloadEvent::AllDone {
+            files: downloaded.len(),
+            bytes: downloaded.iter().map(|file| file.size).sum(),
+        });
+        Ok(downloaded)
+    }
+
+    /// Download one file listed by [`get_output_files`](Self::get_output_files)
+    /// or [`list_working_dir`](Self::list_working_dir) to `output_path`,
+    /// creating its directory. Returns the file's size.
+    ///
+    /// `output_path` is used as given. NSG's filenames can't be trusted to
+    /// stay inside a directory, so build it with
+    /// [`FilenameMapper`](crate::filenames::FilenameMapper) rather than
+    /// joining `file.filename` directly.
+    pub fn download_file(&self, file: &OutputFile, output_path: &Path) -> Result<u64> {
+        self.download_file_with(file, output_path, &DownloadOptions::default(), |_| {})
+    }
+
+    /// Like [`download_file`](Self::download_file), following `options`'
+    /// `resume` and `working_dir`, and reporting bytes written so far.
+    ///
+    /// Data goes to a `.part` file first, which is only renamed once its size
+    /// matches what NSG reported. A transfer that ends short is tried again,
+    /// as often as the retry policy allows. With `resume`, an existing
+    /// `.part` file is continued with an HTTP Range request. Returns the
+    /// final size.
+    pub fn download_file_with(
+        &self,
+        file: &OutputFile,
+        output_path: &Path,
+        options: &DownloadOptions,
+        mut progress: impl FnMut(u64),
+    ) -> Result<u64> {
+        let policy = self.options.retry;
+        let mut retry = 0;
+
+        loop {
+            let reason = match self.fetch_file(file, output_path, options, &mut progress) {
+                Err(NsgError::Transfer(reason)) if retry < policy.retries => reason,
+                result => return result,
+            };
+
+            retry += 1;
+            let delay = policy.delay(retry);
+            if self.options.verbose >= 1 {
+                eprintln!(
+                    "! {}; retry {}/{} in {:.1}s",
+                    reason,
+                    retry,
+                    policy.retries,
+                    delay.as_secs_f64()
                 );
             }
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// One attempt of [`download_file_with`](Self::download_file_with)
+    fn fetch_file(
+        &self,
+        file: &OutputFile,
+        output_path: &Path,
+        options: &DownloadOptions,
+        progress: &mut impl FnMut(u64),
+    ) -> Result<u64> {
+        let download_path = file
+            .download_uri
+            .strip_prefix(&self.base_url)
+            .context("Invalid download URL")?;
+
+        if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(long_path(parent))
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let part_path = partial_path(output_path);
+        // A part file larger than the remote one is stale: start over
+        // Working directory files may be rewritten between snapshots, so
+        // only appending to a finished file's `.part` is safe. Without a
+        // length there's no telling whether a `.part` file is complete.
+        let existing = match file.size {
+            Some(size) if options.resume && !options.working_dir => {
+                let existing = std::fs::metadata(long_path(&part_path))
+                    .map(|m| m.len())
+                    .unwrap_or(0);
+                if existing > size {
+                    0
+                } else {
+                    existing
+                }
+            }
+            _ => 0,
+        };
 
-            let mut dest = std::fs::File::create(&output_path)
-                .with_context(|| format!("Failed to create {}", output_path.display()))?;
+        let _permit = self.limiter.acquire();
 
-            // Download with progress tracking
-            let total_size = file.size;
-            let mut downloaded_bytes = 0u64;
-            let mut buffer = [0u8; 8192];
+        let mut request = self.build_download_request(download_path);
+        if existing > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+        }
 
-            loop {
-                let bytes_read = response
-                    .read(&mut buffer)
-                    .with_context(|| format!("Failed to read from {}", file.filename))?;
+        let mut response = self
+            .send(request)
+            .with_context(|| format!("Failed to download {}", file.filename))?;
 
-                if bytes_read == 0 {
-                    break;
+        let offset = match response.status() {
+            StatusCode::PARTIAL_CONTENT if existing > 0 => {
+                let start = range_start(response.headers());
+                if start != Some(existing) {
+                    return Err(NsgError::Transfer(format!(
+                        "Failed to resume {}: server sent an unexpected range",
+                        file.filename
+                    )));
                 }
+                existing
+            }
+            // Nothing left to fetch: the part file is already complete
+            StatusCode::RANGE_NOT_SATISFIABLE if existing > 0 && Some(existing) == file.size => {
+                progress(existing);
+                return finish_part(file, &part_path, output_path, options.working_dir);
+            }
+            status if status.is_success() => 0,
+            status => {
+                return Err(api_error(
+                    status,
+                    format!("Failed to download {}: HTTP {}", file.filename, status),
+                ))
+            }
+        };
+
+        // A 200 to a Range request means the server ignored it: start over
+        let mut dest = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(offset > 0)
+            .truncate(offset == 0)
+            .open(long_path(&part_path))
+            .with_context(|| format!("Failed to create {}", part_path.display()))?;
+
+        let mut downloaded_bytes = offset;
+        let mut buffer = [0u8; 8192];
+
+        loop {
+            let bytes_read = response
+                .read(&mut buffer)
+                .with_context(|| format!("Failed to read from {}", file.filename))?;
+
+            if bytes_read == 0 {
+                break;
+            }
+
+            dest.write_all(&buffer[..bytes_read])
+                .with_context(|| format!("Failed to write to {}", file.filename))?;
+
+            downloaded_bytes += bytes_read as u64;
+            progress(downloaded_bytes);
+        }
+
+        drop(dest);
+        finish_part(file, &part_path, output_path, options.working_dir)
+    }
+}
+
+/// The certificates in a PEM bundle, to be trusted in addition to the
+/// system's root store
+pub(crate) fn load_ca_bundle(path: &Path) -> Result<Vec<reqwest::Certificate>> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("Failed to read CA certificate {}", path.display()))?;
+    let certificates = reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| {
+        NsgError::Invalid(format!("Invalid CA certificate {}: {}", path.display(), e))
+    })?;
+    if certificates.is_empty() {
+        return Err(NsgError::Invalid(format!(
+            "No PEM certificates found in {}",
+            path.display()
+        )));
+    }
+    Ok(certificates)
+}
 
-                dest.write_all(&buffer[..bytes_read])
-                    .with_context(|| format!("Failed to write to {}", file.filename))?;
+/// An output file being read from NSG, see
+/// [`NsgClient::open_output_file`]. Counts as a request in flight until
+/// dropped.
+pub struct OutputStream<'a> {
+    response: Response,
+    _permit: Permit<'a>,
+}
+
+impl Read for OutputStream<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.response.read(buf)
+    }
+}
+
+/// Iterator over the pages of the job list, see [`NsgClient::jobs_paged`]
+pub struct JobPages<'a> {
+    client: &'a NsgClient,
+    next: Option<String>,
+    seen: HashSet<String>,
+}
 
-                downloaded_bytes += bytes_read as u64;
-                progress_callback(&file.filename, downloaded_bytes, total_size);
+impl Iterator for JobPages<'_> {
+    type Item = Result<Vec<JobSummary>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let path = self.next.take()?;
+        // A server linking back to a page already read would loop forever
+        if !self.seen.insert(path.clone()) {
+            return None;
+        }
+
+        let page = match self.client.fetch_job_page(&path) {
+            Ok(page) => page,
+            Err(e) => return Some(Err(e)),
+        };
+        if let Some(next) = &page.next {
+            match link_path(&self.client.base_url, &path, next) {
+                Ok(next) => self.next = Some(next),
+                Err(e) => return Some(Err(e)),
             }
+        }
 
-            downloaded.push(DownloadedFile {
-                filename: file.filename,
-                path: output_path,
-                size: file.size,
+        Some(Ok(page.jobs))
+    }
+}
+
+/// API path of `link`, found in the response to `from_path`. Relative links
+/// resolve against that page like a browser would; the result must still be
+/// below `base_url`.
+pub(crate) fn link_path(base_url: &str, from_path: &str, link: &str) -> Result<String> {
+    let page = format!("{}{}", base_url, from_path);
+    let target = reqwest::Url::parse(&page)
+        .and_then(|page| page.join(link))
+        .map_err(|e| NsgError::Parse(format!("Invalid link '{}': {}", link, e)))?;
+
+    target
+        .as_str()
+        .strip_prefix(base_url)
+        .map(str::to_string)
+        .with_context(|| format!("Link to another server: {}", link))
+}
+
+/// The `rel="next"` target of a `Link` header, the other common way to
+/// point at a following page
+pub(crate) fn next_link(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers
+        .get_all(reqwest::header::LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|link| {
+            let mut parts = link.split(';');
+            let target = parts.next()?.trim();
+            let is_next = parts.any(|param| {
+                let param = param.trim().replace(' ', "");
+                param == "rel=next" || param == "rel=\"next\""
             });
+            is_next.then(|| {
+                target
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+        })
+}
+
+/// Whether `path` is a regular file of exactly `size` bytes
+pub(crate) fn is_unchanged(path: &Path, size: u64) -> bool {
+    std::fs::metadata(long_path(path)).is_ok_and(|m| m.is_file() && m.len() == size)
+}
+
+/// The first byte of a `Content-Range: bytes START-END/TOTAL` response
+pub(crate) fn range_start(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    headers
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Check a finished `.part` file against the size NSG reported and move it
+/// into place
+fn finish_part(
+    file: &OutputFile,
+    part_path: &Path,
+    output_path: &Path,
+    may_grow: bool,
+) -> Result<u64> {
+    let size = std::fs::metadata(long_path(part_path))
+        .with_context(|| format!("Failed to read {}", part_path.display()))?
+        .len();
+
+    // Files in a running job's working directory can grow after listing
+    if let Some(expected) = file.size {
+        if size < expected || (size != expected && !may_grow) {
+            return Err(NsgError::Transfer(format!(
+                "Download of {} is incomplete: got {} of {} bytes (run the download again to resume)",
+                file.filename, size, expected
+            )));
         }
+    }
 
-        Ok(downloaded)
+    std::fs::rename(long_path(part_path), long_path(output_path))
+        .with_context(|| format!("Failed to move {} into place", output_path.display()))?;
+
+    Ok(size)
+}
+
+/// The form fields sent for `params` on submission, besides the tool and input.
+///
+/// Plain names become `vparam.<name>`, `metadata.*` names are passed through
+/// unchanged, and `metadata.statusEmail=true` is added unless given.
+pub fn submit_fields(params: &[(String, String)]) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = params
+        .iter()
+        .map(|(name, value)| {
+            let field = if name.starts_with("metadata.") {
+                name.clone()
+            } else {
+                format!("vparam.{}", name)
+            };
+            (field, value.clone())
+        })
+        .collect();
+
+    if !fields
+        .iter()
+        .any(|(field, _)| field == "metadata.statusEmail")
+    {
+        fields.push(("metadata.statusEmail".to_string(), "true".to_string()));
     }
+
+    fields
+}
+
+/// Render a request as a copy-pasteable curl command. The password is never
+/// printed; the command reads it from `$NSG_PASSWORD` instead.
+fn curl_command(
+    method: &reqwest::Method,
+    url: &str,
+    credentials: &Credentials,
+    user_agent: &str,
+    form: &[(String, String)],
+) -> String {
+    let mut cmd = String::from("curl");
+
+    if *method != reqwest::Method::GET && form.is_empty() {
+        cmd.push_str(&format!(" -X {}", method));
+    }
+
+    cmd.push_str(&format!(" -A {}", shell_quote(user_agent)));
+
+    // Double quotes so the shell expands $NSG_PASSWORD; escape everything
+    // else that is special inside them.
+    let username: String = credentials
+        .username
+        .chars()
+        .flat_map(|c| match c {
+            '"' | '\\' | '$' | '`' => vec!['\\', c],
+            _ => vec![c],
+        })
+        .collect();
+    cmd.push_str(&format!(" -u \"{}:$NSG_PASSWORD\"", username));
+    cmd.push_str(&format!(
+        " -H {}",
+        shell_quote(&format!("cipres-appkey: {}", credentials.app_key))
+    ));
+
+    for (name, value) in form {
+        cmd.push_str(&format!(
+            " -F {}",
+            shell_quote(&format!("{}={}", name, value))
+        ));
+    }
+
+    cmd.push(' ');
+    cmd.push_str(&shell_quote(url));
+    cmd
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
 }
@@ -0,0 +1,163 @@
+use crate::agent;
+use crate::commands::output::icon;
+use crate::commands::GlobalArgs;
+use crate::config::Credentials;
+use crate::error::NotLoggedIn;
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use std::io::{self, Write};
+
+#[derive(Debug, Args)]
+pub struct AgentCommand {
+    #[command(subcommand)]
+    action: AgentAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum AgentAction {
+    #[command(about = "Hold credentials in memory and serve them to other nsg commands")]
+    Start {
+        #[arg(
+            short,
+            long,
+            default_value = "8h",
+            help = "How long to keep credentials (e.g. 30m, 8h, 2d)"
+        )]
+        lifetime: String,
+
+        #[arg(
+            long,
+            help = "Prompt for credentials instead of reading the credentials file"
+        )]
+        prompt: bool,
+
+        #[arg(long, help = "Skip connection test")]
+        no_verify: bool,
+    },
+
+    #[command(about = "Show whether an agent is running")]
+    Status,
+
+    #[command(about = "Stop the running agent and forget its credentials")]
+    Stop,
+}
+
+impl AgentCommand {
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        match self.action {
+            AgentAction::Start {
+                lifetime,
+                prompt,
+                no_verify,
+            } => {
+                let lifetime = parse_duration(&lifetime)?;
+
+                let credentials = if prompt {
+                    prompt_credentials()?
+                } else {
+                    match Credentials::load_profile_from_file(global.profile.as_deref()) {
+                        Ok(creds) => creds,
+                        Err(e) if e.is::<NotLoggedIn>() => prompt_credentials()?,
+                        Err(e) => return Err(e),
+                    }
+                };
+
+                if !no_verify {
+                    say!("{} Testing connection to NSG...", icon("→").cyan());
+                    global
+                        .client(credentials.clone())?
+                        .test_connection()
+                        .context("Not starting agent")?;
+                }
+
+                let expires_at = chrono::Utc::now()
+                    .checked_add_signed(lifetime)
+                    .context("Agent lifetime too long")?;
+                let socket = agent::socket_path()?;
+
+                say!(
+                    "{} Agent holding credentials for {} until {}",
+                    icon("✓").green().bold(),
+                    credentials.username.cyan(),
+                    expires_at
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                );
+                say!("   Socket: {}", socket.display().to_string().dimmed());
+                say!("   Stop with {} or Ctrl-C", "nsg agent stop".cyan());
+                say!();
+
+                agent::serve(credentials, global.profile.clone(), expires_at)
+            }
+            AgentAction::Status => {
+                match agent::status() {
+                    Ok(status) => {
+                        println!("{} Agent running", icon("✓").green().bold());
+                        println!("   User:    {}", status.username.cyan());
+                        if let Some(profile) = &status.profile {
+                            println!("   Profile: {}", profile.cyan());
+                        }
+                        println!(
+                            "   Expires: {}",
+                            status
+                                .expires_at
+                                .with_timezone(&chrono::Local)
+                                .format("%Y-%m-%d %H:%M")
+                        );
+                    }
+                    Err(_) => println!("{} No agent running", icon("✗").dimmed()),
+                }
+                Ok(())
+            }
+            AgentAction::Stop => {
+                agent::stop()?;
+                say!("{} Agent stopped", icon("✓").green().bold());
+                Ok(())
+            }
+        }
+    }
+}
+
+fn prompt_credentials() -> Result<Credentials> {
+    let username = prompt("NSG Username: ")?;
+
+    print!("NSG Password: ");
+    io::stdout().flush()?;
+    let password = rpassword::read_password()?;
+
+    let app_key = prompt("NSG Application Key: ")?;
+
+    if username.is_empty() || password.is_empty() || app_key.is_empty() {
+        anyhow::bail!("Username, password and application key are all required");
+    }
+
+    Ok(Credentials::new(username, password, app_key))
+}
+
+fn prompt(label: &str) -> Result<String> {
+    print!("{}", label);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+/// Parse durations like `90`, `30s`, `15m`, `8h` or `2d`
+pub(crate) fn parse_duration(s: &str) -> Result<chrono::Duration> {
+    let s = s.trim();
+    let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let value: i64 = number
+        .parse()
+        .with_context(|| format!("Invalid duration: {}", s))?;
+
+    let duration = match unit {
+        "" | "s" => chrono::TimeDelta::try_seconds(value),
+        "m" => chrono::TimeDelta::try_minutes(value),
+        "h" => chrono::TimeDelta::try_hours(value),
+        "d" => chrono::TimeDelta::try_days(value),
+        _ => anyhow::bail!("Invalid duration unit in '{}' (use s, m, h or d)", s),
+    };
+    duration.with_context(|| format!("Duration too long: {}", s))
+}
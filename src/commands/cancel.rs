@@ -0,0 +1,74 @@
+use crate::cache;
+use crate::commands::output::icon;
+use crate::commands::{resolve, GlobalArgs};
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use std::io::Write;
+
+#[derive(Debug, Args)]
+pub struct CancelCommand {
+    #[arg(help = "Job URL, Job ID or a unique part of one")]
+    job: String,
+
+    #[arg(short, long, help = "Don't ask for confirmation")]
+    yes: bool,
+}
+
+impl CancelCommand {
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+
+        let job = resolve::resolve_jobs(&client, &[&self.job])?.remove(0);
+
+        say!("{} Checking job status...", icon("→").cyan());
+        let status = client.get_job_status(&job)?;
+        cache::record_statuses([&status]);
+
+        say!();
+        say!("Job ID:       {}", status.job_id.cyan());
+        say!("Stage:        {}", status.job_stage.as_str().bold());
+        if let Some(date) = &status.date_submitted {
+            say!("Submitted:    {}", date);
+        }
+        say!();
+
+        if status.terminal_stage {
+            println!(
+                "{} Job has already finished - nothing to cancel",
+                icon("⚠").yellow()
+            );
+            say!(
+                "   To remove it from the server, use {}",
+                format!("nsg delete {}", status.job_id).cyan()
+            );
+            return Ok(());
+        }
+
+        if !self.yes {
+            print!("Cancel this job? Its working directory will be discarded. [y/N] ");
+            std::io::stdout().flush()?;
+
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            if !input.trim().eq_ignore_ascii_case("y") {
+                say!("Not cancelled.");
+                return Ok(());
+            }
+        }
+
+        client
+            .cancel_job(&status.self_uri)
+            .context("Failed to cancel job")?;
+        cache::record_removed(&status.job_id, "CANCELLED");
+
+        say!(
+            "{} Job {} cancelled",
+            icon("✓").green().bold(),
+            status.job_id.cyan()
+        );
+
+        Ok(())
+    }
+}
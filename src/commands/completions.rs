@@ -0,0 +1,24 @@
+use anyhow::Result;
+use clap::Args;
+use clap_complete::Shell;
+use std::io::Write;
+
+#[derive(Debug, Args)]
+pub struct CompletionsCommand {
+    #[arg(value_enum, help = "Shell to generate completions for")]
+    shell: Shell,
+}
+
+impl CompletionsCommand {
+    /// Write the completion script for `command` (the whole `nsg` CLI) to
+    /// stdout, to be sourced or saved by the user's shell setup
+    pub fn execute(self, mut command: clap::Command) -> Result<()> {
+        let name = command.get_name().to_string();
+
+        // clap_complete panics on write errors, so render to memory first
+        let mut script = Vec::new();
+        clap_complete::generate(self.shell, &mut command, name, &mut script);
+        std::io::stdout().write_all(&script)?;
+        Ok(())
+    }
+}
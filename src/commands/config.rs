@@ -0,0 +1,90 @@
+use crate::commands::output::icon;
+use crate::settings::{check_key, Settings, KEYS};
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use colored::Colorize;
+
+#[derive(Debug, Args)]
+pub struct ConfigCommand {
+    #[command(subcommand)]
+    action: ConfigAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigAction {
+    #[command(about = "Print the configured value of a key")]
+    Get {
+        #[arg(help = "Config key, e.g. default_tool")]
+        key: String,
+    },
+
+    #[command(about = "Set a key in ~/.nsg/config.toml")]
+    Set {
+        #[arg(help = "Config key, e.g. default_tool")]
+        key: String,
+
+        #[arg(help = "New value")]
+        value: String,
+    },
+
+    #[command(about = "Remove a key, going back to the built-in default")]
+    Unset {
+        #[arg(help = "Config key, e.g. default_tool")]
+        key: String,
+    },
+
+    #[command(about = "Show every key with its value and default")]
+    List,
+
+    #[command(about = "Print the location of the config file")]
+    Path,
+}
+
+impl ConfigCommand {
+    pub fn execute(self) -> Result<()> {
+        match self.action {
+            ConfigAction::Get { key } => {
+                check_key(&key)?;
+                let value = Settings::values()?
+                    .into_iter()
+                    .find(|(name, _)| *name == key)
+                    .and_then(|(_, value)| value);
+
+                match value {
+                    Some(value) => println!("{}", value),
+                    None => anyhow::bail!("{} is not set", key),
+                }
+            }
+            ConfigAction::Set { key, value } => {
+                Settings::set(&key, &value)?;
+                say!("{} {} = {}", icon("✓").green().bold(), key.cyan(), value);
+            }
+            ConfigAction::Unset { key } => {
+                if Settings::unset(&key)? {
+                    say!("{} {} unset", icon("✓").green().bold(), key.cyan());
+                } else {
+                    say!("{} was not set", key);
+                }
+            }
+            ConfigAction::List => {
+                let values = Settings::values()?;
+                let width = KEYS.iter().map(|(key, _, _)| key.len()).max().unwrap_or(0);
+
+                for ((key, description, default), (_, value)) in KEYS.iter().zip(values) {
+                    match value {
+                        Some(value) => println!("{:<width$}  {}", key.cyan(), value.bold()),
+                        None => println!(
+                            "{:<width$}  {}",
+                            key.cyan(),
+                            format!("{} (default)", default).dimmed()
+                        ),
+                    }
+                    say!("{:<width$}  {}", "", description.dimmed());
+                }
+            }
+            ConfigAction::Path => println!("{}", Settings::path()?.display()),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use crate::cache;
+use crate::commands::output::icon;
+use crate::commands::GlobalArgs;
+use crate::models::JobStatus;
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use std::io::Write;
+
+#[derive(Debug, Args)]
+pub struct DeleteCommand {
+    #[arg(required = true, help = "Job URL(s) or Job ID(s)")]
+    jobs: Vec<String>,
+
+    #[arg(long, help = "Also delete jobs that are still queued or running")]
+    force: bool,
+
+    #[arg(short, long, help = "Don't ask for confirmation")]
+    yes: bool,
+}
+
+impl DeleteCommand {
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+
+        say!("{}", "NSG Job Cleanup".bold().cyan());
+        say!("{}", "=".repeat(80).cyan());
+        say!();
+
+        let mut to_delete: Vec<(JobStatus, Option<(usize, u64)>)> = Vec::new();
+        let mut refused = 0;
+
+        for job in &self.jobs {
+            let status = client.get_job_status(job)?;
+            cache::record_statuses([&status]);
+
+            if !status.terminal_stage && !self.force {
+                eprintln!(
+                    "{} {} is still {} - skipping (cancel it with {}, or use --force)",
+                    icon("✗").red().bold(),
+                    status.job_id.cyan(),
+                    status.job_stage.as_str().bold(),
+                    "nsg cancel".cyan()
+                );
+                refused += 1;
+                continue;
+            }
+
+            // Best-effort: only used to show how much is being thrown away
+            let outputs = client
+                .get_output_files(&status)
+                .ok()
+                .map(|files| (files.len(), files.iter().filter_map(|f| f.size).sum()));
+
+            to_delete.push((status, outputs));
+        }
+
+        if to_delete.is_empty() {
+            say!();
+            say!("Nothing to delete.");
+            if refused > 0 {
+                anyhow::bail!("{} job(s) are not finished", refused);
+            }
+            return Ok(());
+        }
+
+        say!("{}", "Jobs to delete:".bold());
+        for (status, outputs) in &to_delete {
+            say!(
+                "  {}  {:<10} {}  {}",
+                status.job_id.cyan(),
+                status.job_stage,
+                status.date_submitted.as_deref().unwrap_or("-").dimmed(),
+                describe_outputs(*outputs).dimmed()
+            );
+        }
+        say!();
+
+        if !self.yes {
+            print!(
+                "Permanently delete {} job(s) and their results from NSG? [y/N] ",
+                to_delete.len()
+            );
+            std::io::stdout().flush()?;
+
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            if !input.trim().eq_ignore_ascii_case("y") {
+                say!("Cancelled.");
+                return Ok(());
+            }
+            say!();
+        }
+
+        let mut deleted = 0;
+        for (status, outputs) in &to_delete {
+            match client.delete_job(&status.self_uri) {
+                Ok(()) => {
+                    cache::record_removed(&status.job_id, "DELETED");
+                    deleted += 1;
+                    say!(
+                        "{} Deleted {} ({})",
+                        icon("✓").green(),
+                        status.job_id.cyan(),
+                        describe_outputs(*outputs)
+                    );
+                }
+                Err(e) => {
+                    eprintln!("{} {}: {}", icon("✗").red().bold(), status.job_id.cyan(), e);
+                }
+            }
+        }
+
+        say!();
+        say!("{} {} job(s) removed", icon("✓").green().bold(), deleted);
+
+        if deleted < to_delete.len() || refused > 0 {
+            anyhow::bail!(
+                "{} job(s) were not deleted",
+                to_delete.len() - deleted + refused
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn describe_outputs(outputs: Option<(usize, u64)>) -> String {
+    match outputs {
+        Some((count, size)) => format!("{} output file(s), {}", count, format_size(size)),
+        None => "no results".to_string(),
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+
+    if bytes >= GB {
+        format!("{:.2} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.2} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
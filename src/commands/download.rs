@@ -1,170 +1,854 @@
-use crate::client::NsgClient;
-use crate::config::Credentials;
-use anyhow::Result;
+use crate::api::NsgApi;
+use crate::cache::{self, JobCache};
+use crate::client::{DownloadEvent, DownloadOptions, DEFAULT_DOWNLOAD_JOBS};
+use crate::commands::output::{icon, progress_json, quiet};
+use crate::commands::progress::{self, DownloadProgress, UNKNOWN_SIZE};
+use crate::commands::{print_json, resolve, GlobalArgs};
+use crate::extract::{
+    extract_from, extract_tar_gz, list_entries, EntryFilter, Extracted, RESULTS_ARCHIVE,
+};
+use crate::filenames::{FileFilter, MAPPING_FILE, PARTIAL_SUFFIX};
+use crate::manifest::write_manifest;
+use crate::models::{DownloadedFile, JobStage, JobStatus};
+use anyhow::{Context, Result};
 use clap::Args;
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::path::PathBuf;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// What `nsg download --output json` prints
+#[derive(Debug, Serialize)]
+pub(crate) struct DownloadReport {
+    pub job_id: String,
+    pub directory: PathBuf,
+    pub files: Vec<DownloadedFile>,
+    pub manifest: Option<PathBuf>,
+    /// With `--extract`, what unpacking `output.tar.gz` did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extracted: Option<Extracted>,
+}
 
 #[derive(Debug, Args)]
 pub struct DownloadCommand {
-    #[arg(help = "Job URL or Job ID")]
-    job: String,
+    #[arg(help = "Job URL, Job ID or a unique part of one [default: choose from a list]")]
+    job: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["job", "tag", "latest_completed"],
+        help = "Download your most recently submitted job"
+    )]
+    latest: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["job", "tag"],
+        help = "Download your most recently submitted job that completed successfully"
+    )]
+    latest_completed: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "job",
+        help = "Download every job with this local tag, each into <OUTPUT>/<JOB_ID>"
+    )]
+    tag: Option<String>,
 
     #[arg(
         short,
         long,
         default_value = "./nsg_results",
-        help = "Output directory"
+        help = "Output directory; each job's files go into <OUTPUT>/<JOB_ID> unless --flat"
     )]
     output: PathBuf,
+
+    #[arg(
+        long,
+        conflicts_with = "tag",
+        help = "Put the files straight into the output directory instead of a subdirectory named after the job"
+    )]
+    flat: bool,
+
+    #[arg(
+        short,
+        long = "file",
+        value_name = "PATTERN",
+        help = "Only download files matching this glob (repeatable), e.g. --file '*.json'"
+    )]
+    files: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        help = "Skip files matching this glob (repeatable), e.g. --exclude 'output.tar.gz'"
+    )]
+    exclude: Vec<String>,
+
+    #[arg(
+        short,
+        long,
+        value_name = "N",
+        default_value_t = DEFAULT_DOWNLOAD_JOBS as u16,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Files to download in parallel (also capped by --max-concurrency)"
+    )]
+    jobs: u16,
+
+    #[arg(
+        long,
+        help = "Start every file from scratch instead of resuming partial (.part) downloads"
+    )]
+    no_resume: bool,
+
+    #[arg(long, help = "Don't write a MANIFEST.sha256 of the downloaded files")]
+    no_manifest: bool,
+
+    #[arg(
+        long,
+        help = "Download current files from a running job's working directory instead of its final results"
+    )]
+    intermediate: bool,
+
+    #[arg(
+        long,
+        help = "Unpack output.tar.gz into the output directory after downloading"
+    )]
+    extract: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "extract",
+        help = "Don't unpack output.tar.gz, even if config.toml says to"
+    )]
+    no_extract: bool,
+
+    #[arg(long, help = "Delete output.tar.gz once it has been unpacked")]
+    remove_archive: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["tag", "extract_only"],
+        help = "List what's in the job's output.tar.gz instead of downloading anything"
+    )]
+    list_archive: bool,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        help = "Extract only output.tar.gz entries matching this glob (repeatable), reading the archive as it downloads instead of saving it, e.g. --extract-only 'results/*.json'"
+    )]
+    extract_only: Vec<String>,
+
+    #[arg(
+        short,
+        long,
+        help = "Don't ask before downloading an unfinished job or into a non-empty directory"
+    )]
+    yes: bool,
 }
 
 impl DownloadCommand {
-    pub fn execute(self) -> Result<()> {
-        let credentials = Credentials::load()?;
-        let client = NsgClient::new(credentials)?;
-
-        println!("{}", "NSG Results Downloader".bold().cyan());
-        println!("{}", "=".repeat(80).cyan());
-        println!();
-        println!("{} Checking job status...", "→".cyan());
-        println!("   Job: {}", self.job.bold());
-        println!();
-
-        let status = client.get_job_status(&self.job)?;
-
-        println!("Job ID:       {}", status.job_id.cyan());
-        println!("Stage:        {}", status.job_stage.bold());
-
-        if status.job_stage != "COMPLETED" {
-            println!();
-            println!("{} Job is not completed yet", "⚠".yellow().bold());
-            println!("   Current stage: {}", status.job_stage.bold());
-            println!();
-            println!("Results may not be available. Continue anyway? [y/N] ");
-
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            if !input.trim().eq_ignore_ascii_case("y") {
-                println!("Cancelled.");
-                return Ok(());
+    pub(crate) fn new(job: String, output: PathBuf) -> Self {
+        Self {
+            job: Some(job),
+            tag: None,
+            latest: false,
+            latest_completed: false,
+            output,
+            flat: false,
+            files: Vec::new(),
+            exclude: Vec::new(),
+            jobs: DEFAULT_DOWNLOAD_JOBS as u16,
+            no_resume: false,
+            no_manifest: false,
+            intermediate: false,
+            extract: false,
+            no_extract: false,
+            remove_archive: false,
+            list_archive: false,
+            extract_only: Vec::new(),
+            yes: false,
+        }
+    }
+
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        if self.list_archive {
+            return self.list_archive(global);
+        }
+
+        if let Some(tag) = &self.tag {
+            let reports = self.run_tagged(global, tag)?;
+            if global.json_output() {
+                print_json(&reports)?;
             }
+            return Ok(());
         }
 
-        println!();
-        println!(
-            "{} Output directory: {}",
-            "→".cyan(),
-            self.output.display().to_string().bold()
+        if let Some(report) = self.run(global)? {
+            if global.json_output() {
+                print_json(&report)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Download the job's files, returning `None` if the user cancelled
+    pub(crate) fn run(&self, global: &GlobalArgs) -> Result<Option<DownloadReport>> {
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+
+        say!("{}", "NSG Results Downloader".bold().cyan());
+        say!("{}", "=".repeat(80).cyan());
+        say!();
+
+        let job = self.job(&client)?;
+        self.download(&client, &job, &self.output, false)
+    }
+
+    /// The job named on the command line, or the one `--latest`,
+    /// `--latest-completed` or the job picker comes up with
+    fn job(&self, client: &dyn NsgApi) -> Result<String> {
+        if self.latest || self.latest_completed {
+            let job = resolve::latest_job(client, self.latest_completed)?.job_id;
+            say!();
+            return Ok(job);
+        }
+
+        match &self.job {
+            Some(job) => Ok(resolve::resolve_jobs(client, &[job])?.remove(0)),
+            None => resolve::pick_job(client, "nsg download <JOB>"),
+        }
+    }
+
+    /// Print the entries of the job's `output.tar.gz`, reading it from NSG
+    /// without saving it
+    fn list_archive(&self, global: &GlobalArgs) -> Result<()> {
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+        let job = self.job(&client)?;
+
+        let status = client.get_job_status(&job)?;
+        cache::record_statuses([&status]);
+        let files = if self.intermediate {
+            client.list_working_dir(&status)?
+        } else {
+            client.get_output_files(&status)?
+        };
+        let archive = files
+            .into_iter()
+            .find(|file| file.filename == RESULTS_ARCHIVE)
+            .with_context(|| format!("{} has no {}", status.job_id, RESULTS_ARCHIVE))?;
+
+        say!(
+            "{} Reading {} of {} ({})...",
+            icon("→").cyan(),
+            RESULTS_ARCHIVE,
+            status.job_id.cyan(),
+            format_length(archive.size)
         );
-        println!();
+        let bar = reading_bar(archive.size);
+        let entries = list_entries(bar.wrap_read(progress::track(
+            &archive.filename,
+            archive.size,
+            client.open_output_file(&archive)?,
+        )))?;
+        bar.finish_and_clear();
 
-        if self.output.exists() && std::fs::read_dir(&self.output)?.next().is_some() {
-            println!("{} Directory already exists and is not empty", "⚠".yellow());
-            println!("   Files may be overwritten. Continue? [y/N] ");
+        if global.json_output() {
+            return print_json(&entries);
+        }
 
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            if !input.trim().eq_ignore_ascii_case("y") {
-                println!("Cancelled.");
-                return Ok(());
+        say!();
+        let mut total = 0;
+        for entry in &entries {
+            let size = match entry.kind {
+                "file" => format_size(entry.size),
+                kind => kind.to_string(),
+            };
+            total += entry.size;
+            show!("{:>12}  {}", size.dimmed(), entry.path);
+        }
+        say!();
+        say!(
+            "{} entries, {} uncompressed",
+            entries.len(),
+            format_size(total)
+        );
+        say!(
+            "   Extract some with: {}",
+            format!("nsg download {} --extract-only 'PATTERN'", status.job_id).cyan()
+        );
+
+        Ok(())
+    }
+
+    /// Download every job tagged `tag` into its own `<OUTPUT>/<JOB_ID>`
+    fn run_tagged(&self, global: &GlobalArgs, tag: &str) -> Result<Vec<DownloadReport>> {
+        let cache = JobCache::load()?;
+        let jobs: Vec<&str> = cache
+            .jobs
+            .values()
+            .filter(|job| job.has_tag(tag))
+            .map(|job| job.job_id.as_str())
+            .collect();
+        if jobs.is_empty() {
+            anyhow::bail!("No jobs tagged {} (see `nsg tag list`)", tag);
+        }
+
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+
+        say!("{}", "NSG Results Downloader".bold().cyan());
+        say!("{}", "=".repeat(80).cyan());
+        say!();
+        say!(
+            "{} {} job(s) tagged {}",
+            icon("→").cyan(),
+            jobs.len(),
+            tag.magenta()
+        );
+        say!();
+
+        let mut reports = Vec::new();
+        let mut failed = 0;
+        for (i, job) in jobs.iter().enumerate() {
+            say!("{}", format!("[{}/{}]", i + 1, jobs.len()).bold());
+            match self.download(&client, job, &self.output, true) {
+                Ok(Some(report)) => reports.push(report),
+                Ok(None) => {}
+                Err(e) => {
+                    failed += 1;
+                    eprintln!("{} {}: {:#}", icon("✗").red().bold(), job, e);
+                    eprintln!();
+                }
+            }
+        }
+
+        say!(
+            "{} Downloaded {} of {} job(s) tagged {} into {}",
+            icon("✓").green().bold(),
+            reports.len(),
+            jobs.len(),
+            tag.magenta(),
+            self.output.display().to_string().cyan()
+        );
+        say!();
+
+        if failed > 0 {
+            anyhow::bail!(
+                "{} of {} job(s) could not be downloaded",
+                failed,
+                jobs.len()
+            );
+        }
+
+        Ok(reports)
+    }
+
+    /// Download one job's files into `output`, or `output/<JOB_ID>` unless
+    /// `--flat`. In a `batch`, jobs that haven't completed are skipped
+    /// instead of asking whether to continue.
+    fn download(
+        &self,
+        client: &dyn NsgApi,
+        job: &str,
+        output: &Path,
+        batch: bool,
+    ) -> Result<Option<DownloadReport>> {
+        // With --extract-only the archive is read straight from NSG instead
+        let mut exclude = self.exclude.clone();
+        if !self.extract_only.is_empty() {
+            exclude.push(RESULTS_ARCHIVE.to_string());
+        }
+        let options = DownloadOptions {
+            filter: FileFilter::new(&self.files, &exclude)?,
+            jobs: usize::from(self.jobs),
+            resume: !self.no_resume,
+            skip_unchanged: false,
+            working_dir: self.intermediate,
+        };
+
+        say!("{} Checking job status...", icon("→").cyan());
+        say!("   Job: {}", job.bold());
+        say!();
+
+        let status = client.get_job_status(job)?;
+        cache::record_statuses([&status]);
+
+        say!("Job ID:       {}", status.job_id.cyan());
+        say!("Stage:        {}", status.job_stage.as_str().bold());
+
+        if self.intermediate {
+            if status.terminal_stage {
+                say!();
+                say!(
+                    "{} Job has finished - its working directory may already be cleaned up",
+                    icon("⚠").yellow().bold()
+                );
+                say!(
+                    "   Final results: {}",
+                    format!("nsg download {}", status.job_id).cyan()
+                );
+            }
+        } else if status.job_stage != JobStage::Completed && batch {
+            say!(
+                "{} Skipping: job is not completed yet",
+                icon("⚠").yellow().bold()
+            );
+            say!();
+            return Ok(None);
+        } else if status.job_stage != JobStage::Completed {
+            say!();
+            say!("{} Job is not completed yet", icon("⚠").yellow().bold());
+            say!("   Current stage: {}", status.job_stage.as_str().bold());
+            say!();
+            if !self.confirm(
+                "Results may not be available. Continue anyway?",
+                "Job is not completed yet",
+            )? {
+                say!("Cancelled.");
+                return Ok(None);
             }
         }
 
-        println!("{} Downloading output files...", "→".yellow().bold());
-        println!();
+        // Per job, so several jobs' stdout.txt and output.tar.gz can't
+        // overwrite each other
+        let output = if self.flat {
+            output.to_path_buf()
+        } else {
+            output.join(&status.job_id)
+        };
+        let output = output.as_path();
 
-        let pb = ProgressBar::new(0);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-                .unwrap()
-                .progress_chars("#>-"),
+        say!();
+        say!(
+            "{} Output directory: {}",
+            icon("→").cyan(),
+            output.display().to_string().bold()
         );
+        say!();
 
-        let mut current_file = String::new();
+        let partial_files = if !self.no_resume && output.is_dir() {
+            std::fs::read_dir(output)?
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .ends_with(PARTIAL_SUFFIX)
+                })
+                .count()
+        } else {
+            0
+        };
 
-        let downloaded = client.download_results(
-            &self.job,
-            &self.output,
-            |filename, downloaded_bytes, total_bytes| {
-                if current_file != filename {
-                    current_file = filename.to_string();
-                    pb.set_length(total_bytes);
-                    pb.set_position(0);
+        if partial_files > 0 {
+            // Re-running after an interruption: pick up where we left off
+            say!(
+                "{} Resuming {} interrupted download(s)",
+                icon("→").cyan(),
+                partial_files
+            );
+            say!();
+        } else if output.exists() && std::fs::read_dir(output)?.next().is_some() {
+            say!(
+                "{} Directory already exists and is not empty",
+                icon("⚠").yellow()
+            );
+            if !self.confirm(
+                "   Files may be overwritten. Continue?",
+                "Output directory is not empty",
+            )? {
+                say!("Cancelled.");
+                return Ok(None);
+            }
+        }
+
+        if self.intermediate {
+            say!(
+                "{} Downloading working directory snapshot...",
+                icon("→").yellow().bold()
+            );
+        } else {
+            say!("{} Downloading output files...", icon("→").yellow().bold());
+        }
+        say!();
+
+        // One bar per file in flight, so parallel downloads don't fight over
+        // a single line
+        let bars = MultiProgress::new();
+        let mut json_progress = progress_json().then(DownloadProgress::default);
+        if quiet() || json_progress.is_some() {
+            bars.set_draw_target(ProgressDrawTarget::hidden());
+        }
+        let style = ProgressStyle::default_bar()
+            .template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+            .unwrap()
+            .progress_chars("#>-");
+        let mut active: HashMap<String, ProgressBar> = HashMap::new();
+
+        let downloaded = client.download_results(job, output, &options, &mut |event| {
+            if let Some(progress) = &mut json_progress {
+                progress.on_event(&event);
+            }
+            match event {
+                DownloadEvent::FileStarted { filename, size } => {
+                    // The JSON stream has its own warning event
+                    if size.is_none() && json_progress.is_none() {
+                        let _ = bars.println(format!(
+                            "{} {}: {}",
+                            icon("⚠").yellow(),
+                            filename,
+                            UNKNOWN_SIZE
+                        ));
+                    }
+                    let pb = bars.add(match size {
+                        Some(size) => ProgressBar::new(size),
+                        None => ProgressBar::no_length(),
+                    });
+                    pb.set_style(style.clone());
                     pb.set_message(format!("Downloading: {}", filename));
+                    active.insert(filename, pb);
                 }
-                pb.set_position(downloaded_bytes);
-            },
-        )?;
-
-        pb.finish_and_clear();
-
-        if downloaded.is_empty() {
-            println!("{} No output files found", "⚠".yellow());
-            println!();
-            println!("This could mean:");
-            println!("  1. Job hasn't produced output files yet");
-            println!("  2. Job failed without creating outputs");
-            println!("  3. Check stderr.txt and stdout.txt for details");
-            return Ok(());
+                DownloadEvent::Progress {
+                    filename,
+                    downloaded,
+                    ..
+                } => {
+                    if let Some(pb) = active.get(&filename) {
+                        pb.set_position(downloaded);
+                    }
+                }
+                DownloadEvent::FileFinished { file } => {
+                    if let Some(pb) = active.remove(&file.remote_filename) {
+                        pb.finish_and_clear();
+                    }
+                }
+                DownloadEvent::FileFailed { filename, error } => {
+                    if let Some(pb) = active.remove(&filename) {
+                        pb.abandon_with_message(format!(
+                            "{} {}: {}",
+                            icon("✗").red(),
+                            filename,
+                            error
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        })?;
+
+        for pb in active.values() {
+            pb.finish_and_clear();
         }
 
-        println!(
+        let streamed = if self.extract_only.is_empty() {
+            None
+        } else {
+            self.extract_streaming(client, &status, output)?
+        };
+
+        if downloaded.is_empty() && streamed.is_none() {
+            if !options.filter.is_empty() {
+                say!(
+                    "{} No output files matched the --file/--exclude patterns",
+                    icon("⚠").yellow()
+                );
+            } else {
+                say!("{} No output files found", icon("⚠").yellow());
+                say!();
+                say!("This could mean:");
+                say!("  1. Job hasn't produced output files yet");
+                say!("  2. Job failed without creating outputs");
+                say!("  3. Check stderr.txt and stdout.txt for details");
+            }
+
+            return Ok(Some(DownloadReport {
+                job_id: status.job_id,
+                directory: output.to_path_buf(),
+                files: downloaded,
+                manifest: None,
+                extracted: None,
+            }));
+        }
+
+        cache::record_download(&status.job_id, output);
+
+        say!(
             "{} Downloaded {} file(s):",
-            "✓".green().bold(),
+            icon("✓").green().bold(),
             downloaded.len()
         );
-        println!();
+        say!();
 
         let mut total_size = 0u64;
         for file in &downloaded {
             total_size += file.size;
-            println!(
+            say!(
                 "  {} {} ({})",
-                "✓".green(),
+                icon("✓").green(),
                 file.filename.cyan(),
                 format_size(file.size)
             );
+            if file.filename != file.remote_filename {
+                say!(
+                    "      {}",
+                    format!("saved from remote name {:?}", file.remote_filename).dimmed()
+                );
+            }
         }
 
-        println!();
-        println!("{}", "=".repeat(80).green());
-        println!("{} Download complete!", "✓".green().bold());
-        println!("{}", "=".repeat(80).green());
-        println!();
-        println!("Location:     {}", self.output.display().to_string().cyan());
-        println!("Files:        {}", downloaded.len());
-        println!("Total size:   {}", format_size(total_size));
-        println!();
+        if downloaded.iter().any(|f| f.filename != f.remote_filename) {
+            say!();
+            say!(
+                "{} Some remote filenames were unsafe or collided and were renamed",
+                icon("⚠").yellow()
+            );
+            say!(
+                "   Original names are recorded in {}",
+                output.join(MAPPING_FILE).display()
+            );
+        }
+
+        let extracted = if streamed.is_some() {
+            streamed
+        } else if self.extract && !self.no_extract {
+            self.extract_archive(output, &downloaded)?
+        } else {
+            None
+        };
+
+        let manifest = if self.no_manifest {
+            None
+        } else {
+            say!();
+            say!("{} Computing SHA-256 checksums...", icon("→").cyan());
+            // A removed archive can't be checked later, so isn't listed
+            let kept: Vec<DownloadedFile> = downloaded
+                .iter()
+                .filter(|file| file.path.exists())
+                .cloned()
+                .collect();
+            Some(write_manifest(output, &status, &kept)?)
+        };
+
+        say!();
+        say!("{}", "=".repeat(80).green());
+        say!("{} Download complete!", icon("✓").green().bold());
+        say!("{}", "=".repeat(80).green());
+        say!();
+        say!("Location:     {}", output.display().to_string().cyan());
+        say!("Files:        {}", downloaded.len());
+        say!("Total size:   {}", format_size(total_size));
+        if let Some(manifest) = &manifest {
+            say!("Manifest:     {}", manifest.display());
+        }
+        say!();
+
+        if self.intermediate && !status.terminal_stage {
+            say!(
+                "{} These are intermediate files from a running job and may be incomplete",
+                icon("⚠").yellow()
+            );
+            say!();
+        }
 
         if downloaded.iter().any(|f| f.filename == "dda_results.json") {
-            println!("{} DDA results found!", "✓".green());
-            println!();
-            println!("View results:");
-            let path = self.output.join("dda_results.json");
-            println!("  cat {} | jq .", path.display());
+            say!("{} DDA results found!", icon("✓").green());
+            say!();
+            say!("View results:");
+            let path = output.join("dda_results.json");
+            say!("  cat {} | jq .", path.display());
         }
 
         if downloaded.iter().any(|f| f.filename == "stderr.txt") {
-            println!();
-            println!("{} stderr.txt exists - check for errors:", "⚠".yellow());
-            let path = self.output.join("stderr.txt");
-            println!("  cat {}", path.display());
+            say!();
+            say!(
+                "{} stderr.txt exists - check for errors:",
+                icon("⚠").yellow()
+            );
+            let path = output.join("stderr.txt");
+            say!("  cat {}", path.display());
         }
 
         if downloaded.iter().any(|f| f.filename == "stdout.txt") {
-            println!();
-            println!("stdout.txt exists:");
-            let path = self.output.join("stdout.txt");
-            println!("  cat {}", path.display());
+            say!();
+            say!("stdout.txt exists:");
+            let path = output.join("stdout.txt");
+            say!("  cat {}", path.display());
         }
 
-        println!();
+        say!();
 
-        Ok(())
+        Ok(Some(DownloadReport {
+            job_id: status.job_id,
+            directory: output.to_path_buf(),
+            files: downloaded,
+            manifest,
+            extracted,
+        }))
+    }
+
+    /// Ask `question` on stderr, unless `--yes` already answered it. With
+    /// `--quiet` or no terminal to answer on, fail with `problem` instead of
+    /// waiting for input nobody will see a reason to give.
+    fn confirm(&self, question: &str, problem: &str) -> Result<bool> {
+        use std::io::{IsTerminal, Write};
+
+        if self.yes {
+            return Ok(true);
+        }
+        if quiet() || !(std::io::stdin().is_terminal() && std::io::stderr().is_terminal()) {
+            anyhow::bail!("{}; pass --yes to download anyway", problem);
+        }
+
+        eprint!("{} [y/N] ", question);
+        std::io::stderr().flush()?;
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        Ok(input.trim().eq_ignore_ascii_case("y"))
+    }
+
+    /// Unpack the downloaded `output.tar.gz`, if there is one, into
+    /// `output`, deleting it afterwards with `--remove-archive`
+    fn extract_archive(
+        &self,
+        output: &Path,
+        downloaded: &[DownloadedFile],
+    ) -> Result<Option<Extracted>> {
+        say!();
+        let Some(archive) = downloaded
+            .iter()
+            .find(|file| file.remote_filename == RESULTS_ARCHIVE)
+        else {
+            say!(
+                "{} No {} was downloaded, so there is nothing to extract",
+                icon("⚠").yellow(),
+                RESULTS_ARCHIVE
+            );
+            return Ok(None);
+        };
+
+        say!(
+            "{} Extracting {}...",
+            icon("→").cyan(),
+            archive.filename.cyan()
+        );
+        let extracted = extract_tar_gz(&archive.path, output)?;
+        print_extracted(&extracted);
+
+        if self.remove_archive {
+            std::fs::remove_file(&archive.path)
+                .with_context(|| format!("Failed to delete {}", archive.path.display()))?;
+            say!("{} Deleted {}", icon("✓").green(), archive.filename);
+        }
+
+        Ok(Some(extracted))
     }
+
+    /// Extract the `--extract-only` entries of the job's `output.tar.gz`
+    /// into `output` while it downloads, so the archive itself never has
+    /// to fit on disk
+    fn extract_streaming(
+        &self,
+        client: &dyn NsgApi,
+        status: &JobStatus,
+        output: &Path,
+    ) -> Result<Option<Extracted>> {
+        let filter = EntryFilter::new(&self.extract_only)?;
+        let files = if self.intermediate {
+            client.list_working_dir(status)?
+        } else {
+            client.get_output_files(status)?
+        };
+        let Some(archive) = files
+            .into_iter()
+            .find(|file| file.filename == RESULTS_ARCHIVE)
+        else {
+            say!(
+                "{} The job has no {}, so there is nothing to extract",
+                icon("⚠").yellow(),
+                RESULTS_ARCHIVE
+            );
+            return Ok(None);
+        };
+
+        say!();
+        say!(
+            "{} Extracting matching entries from {} ({})...",
+            icon("→").cyan(),
+            RESULTS_ARCHIVE.cyan(),
+            format_length(archive.size)
+        );
+        let bar = reading_bar(archive.size);
+        let extracted = extract_from(
+            bar.wrap_read(progress::track(
+                &archive.filename,
+                archive.size,
+                client.open_output_file(&archive)?,
+            )),
+            output,
+            |path| filter.matches(path),
+        )
+        .with_context(|| format!("Failed to extract {}", RESULTS_ARCHIVE))?;
+        bar.finish_and_clear();
+
+        if extracted.files == 0 {
+            say!(
+                "{} No entries matched --extract-only (see --list-archive)",
+                icon("⚠").yellow()
+            );
+        } else {
+            print_extracted(&extracted);
+        }
+
+        Ok(Some(extracted))
+    }
+}
+
+fn print_extracted(extracted: &Extracted) {
+    say!(
+        "{} Extracted {} file(s) ({})",
+        icon("✓").green().bold(),
+        extracted.files,
+        format_size(extracted.bytes)
+    );
+    if !extracted.skipped.is_empty() {
+        say!(
+            "{} Skipped {} link(s) or unsafe path(s):",
+            icon("⚠").yellow(),
+            extracted.skipped.len()
+        );
+        for name in &extracted.skipped {
+            say!("   {}", name.dimmed());
+        }
+    }
+}
+
+/// Progress of reading a remote archive of `size` bytes, if known
+fn reading_bar(size: Option<u64>) -> ProgressBar {
+    if quiet() || progress_json() {
+        return ProgressBar::hidden();
+    }
+    let bar = match size {
+        Some(size) => ProgressBar::new(size),
+        None => ProgressBar::no_length(),
+    };
+    bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+    bar
+}
+
+/// The size NSG advertised for a file, which it may leave out
+fn format_length(size: Option<u64>) -> String {
+    size.map_or_else(|| "size unknown".to_string(), format_size)
 }
 
 fn format_size(bytes: u64) -> String {
@@ -0,0 +1,305 @@
+use crate::cache::{self, CachedJob, JobCache, JobNote};
+use crate::commands::output::icon;
+use crate::commands::GlobalArgs;
+use crate::models::{tool_from_job_id, JobStatus};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Args)]
+pub struct ExportCommand {
+    #[arg(help = "Job URL or Job ID")]
+    job: String,
+
+    #[arg(
+        long,
+        help = "Write a ZIP bundle with outputs, status, message log and checksums"
+    )]
+    bundle: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Output file (default: stdout, or <JOB_ID>_bundle.zip with --bundle)"
+    )]
+    output: Option<PathBuf>,
+}
+
+/// Everything known about a job, written as `manifest.json` in a bundle
+#[derive(Debug, Serialize)]
+struct Manifest<'a> {
+    format: &'static str,
+    format_version: u32,
+    created_at: DateTime<Utc>,
+    created_by: String,
+    job_id: &'a str,
+    tool: Option<&'a str>,
+    stage: &'a str,
+    failed: bool,
+    date_submitted: Option<&'a str>,
+    url: &'a str,
+    input_file: Option<&'a str>,
+    parameters: BTreeMap<String, String>,
+    metadata: BTreeMap<&'a str, &'a str>,
+    tags: &'a [String],
+    notes: &'a [JobNote],
+    files: Vec<BundledFile>,
+}
+
+#[derive(Debug, Serialize)]
+struct BundledFile {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+impl ExportCommand {
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+
+        if !self.bundle {
+            let status = client.get_job_status(&self.job)?;
+            cache::record_statuses([&status]);
+
+            let cached = cached_job(&status.job_id);
+            let manifest = build_manifest(&status, &cached, Vec::new());
+            let json =
+                serde_json::to_string_pretty(&manifest).context("Failed to serialize job")?;
+
+            match &self.output {
+                Some(path) => {
+                    std::fs::write(path, json + "\n")
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    say!(
+                        "{} Job record written to {}",
+                        icon("✓").green().bold(),
+                        path.display().to_string().cyan()
+                    );
+                }
+                None => println!("{}", json),
+            }
+            return Ok(());
+        }
+
+        say!("{}", "NSG Job Export".bold().cyan());
+        say!("{}", "=".repeat(80).cyan());
+        say!();
+        say!("{} Fetching job status...", icon("→").cyan());
+        say!("   Job: {}", self.job.bold());
+
+        let status = client.get_job_status(&self.job)?;
+        cache::record_statuses([&status]);
+
+        if !status.terminal_stage && !status.failed {
+            eprintln!(
+                "{} Job is still {} - the bundle will only contain outputs produced so far",
+                icon("⚠").yellow(),
+                status.job_stage.as_str().bold()
+            );
+        }
+
+        let output = self
+            .output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(format!("{}_bundle.zip", status.job_id)));
+
+        // Private to us, and removed however the export ends
+        let staging = tempfile::Builder::new()
+            .prefix(&format!("nsg-export-{}-", status.job_id))
+            .tempdir()
+            .context("Failed to create a staging directory")?;
+
+        say!("{} Downloading output files...", icon("→").cyan());
+        let downloaded = if status.results_uri.is_some() {
+            client.download_results(&self.job, staging.path(), |_| {})?
+        } else {
+            Vec::new()
+        };
+
+        say!("{} Writing bundle...", icon("→").cyan());
+
+        let file = File::create(&output)
+            .with_context(|| format!("Failed to create {}", output.display()))?;
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        let mut bundled = Vec::new();
+        for file in &downloaded {
+            let path = format!("outputs/{}", file.filename);
+            let file_options = options.large_file(file.size >= u32::MAX as u64);
+            zip.start_file(path.as_str(), file_options)
+                .with_context(|| format!("Failed to add {} to bundle", path))?;
+            let sha256 = copy_hashed(&file.path, &mut zip)?;
+
+            bundled.push(BundledFile {
+                path,
+                size: file.size,
+                sha256,
+            });
+        }
+
+        let status_json =
+            serde_json::to_string_pretty(&status).context("Failed to serialize job status")?;
+        let message_log = render_message_log(&status);
+
+        let mut checksums = String::new();
+        for (path, contents) in [
+            ("status.json", status_json.as_bytes()),
+            ("messages.log", message_log.as_bytes()),
+        ] {
+            zip.start_file(path, options)
+                .with_context(|| format!("Failed to add {} to bundle", path))?;
+            zip.write_all(contents)?;
+
+            bundled.push(BundledFile {
+                path: path.to_string(),
+                size: contents.len() as u64,
+                sha256: hex_digest(contents),
+            });
+        }
+
+        for file in &bundled {
+            let _ = writeln!(checksums, "{}  {}", file.sha256, file.path);
+        }
+
+        let cached = cached_job(&status.job_id);
+        let file_count = bundled.len();
+        let manifest = build_manifest(&status, &cached, bundled);
+        let manifest_json =
+            serde_json::to_string_pretty(&manifest).context("Failed to serialize manifest")?;
+
+        zip.start_file("manifest.json", options)?;
+        zip.write_all(manifest_json.as_bytes())?;
+        zip.start_file("SHA256SUMS", options)?;
+        zip.write_all(checksums.as_bytes())?;
+
+        zip.finish()
+            .with_context(|| format!("Failed to finish {}", output.display()))?;
+
+        let size = std::fs::metadata(&output).map(|m| m.len()).unwrap_or(0);
+
+        say!();
+        say!("{} Bundle written", icon("✓").green().bold());
+        say!();
+        say!("Location:     {}", output.display().to_string().cyan());
+        say!("Files:        {}", file_count);
+        say!("Size:         {}", format_size(size));
+        say!();
+        say!("Verify after unpacking with:");
+        say!("  {}", "sha256sum -c SHA256SUMS".cyan());
+        say!();
+
+        Ok(())
+    }
+}
+
+fn cached_job(job_id: &str) -> CachedJob {
+    JobCache::load()
+        .ok()
+        .and_then(|cache| cache.get(job_id).cloned())
+        .unwrap_or_else(|| CachedJob::new(job_id))
+}
+
+fn build_manifest<'a>(
+    status: &'a JobStatus,
+    cached: &'a CachedJob,
+    files: Vec<BundledFile>,
+) -> Manifest<'a> {
+    Manifest {
+        format: "nsg-bundle",
+        format_version: BUNDLE_FORMAT_VERSION,
+        created_at: Utc::now(),
+        created_by: crate::client::default_user_agent(),
+        job_id: &status.job_id,
+        tool: cached
+            .tool
+            .as_deref()
+            .or_else(|| tool_from_job_id(&status.job_id)),
+        stage: status.job_stage.as_str(),
+        failed: status.failed,
+        date_submitted: status.date_submitted.as_deref(),
+        url: &status.self_uri,
+        input_file: cached.input_file.as_deref(),
+        parameters: cached
+            .params
+            .iter()
+            .map(|(k, v)| (format!("vparam.{}", k), v.clone()))
+            .collect(),
+        metadata: status
+            .metadata
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect(),
+        tags: &cached.tags,
+        notes: &cached.notes,
+        files,
+    }
+}
+
+/// Copy `path` into `out`, returning the SHA-256 of what was copied
+fn copy_hashed(path: &Path, out: &mut impl Write) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let bytes_read = file
+            .read(&mut buffer)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+        out.write_all(&buffer[..bytes_read])?;
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn render_message_log(status: &JobStatus) -> String {
+    let mut log = String::new();
+    for message in &status.messages {
+        let _ = writeln!(
+            log,
+            "{}  {:<16} {}",
+            message.timestamp.as_deref().unwrap_or("-"),
+            message.stage,
+            message.text
+        );
+    }
+    log
+}
+
+fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+
+    if bytes >= GB {
+        format!("{:.2} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.2} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
@@ -0,0 +1,352 @@
+use crate::client::{parse_base_url, ClientOptions, NsgClient};
+use crate::commands::output::icon;
+use crate::config::Credentials;
+use crate::project::ProjectConfig;
+use crate::retry::RetryPolicy;
+use crate::timeouts::{self, Timeouts};
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Options accepted by every subcommand
+#[derive(Debug, Clone, Default, Args)]
+pub struct GlobalArgs {
+    #[arg(
+        long,
+        global = true,
+        help = "Print the equivalent curl command for every API call (password redacted)"
+    )]
+    pub curl: bool,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_USER_AGENT",
+        value_name = "UA",
+        help = "Override the User-Agent header sent to NSG"
+    )]
+    pub user_agent: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_UA_TAG",
+        value_name = "TAG",
+        help = "Contact/workflow tag appended to the default User-Agent"
+    )]
+    pub ua_tag: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_BASE_URL",
+        value_name = "URL",
+        value_parser = parse_base_url,
+        help = "NSG REST endpoint to use instead of the production server, e.g. a test or mock server"
+    )]
+    pub base_url: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_MAX_CONCURRENCY",
+        value_name = "N",
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Maximum simultaneous API requests/transfers [default: 4]"
+    )]
+    pub max_concurrency: Option<u16>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_RATE_LIMIT",
+        value_name = "PER_SECOND",
+        default_value_t = crate::client::DEFAULT_RATE_LIMIT,
+        value_parser = parse_rate_limit,
+        help = "Send at most this many API requests per second on average (0 disables)"
+    )]
+    pub rate_limit: f64,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_RETRIES",
+        value_name = "N",
+        default_value_t = crate::retry::DEFAULT_RETRIES,
+        help = "Retry requests that time out, lose their connection or get a 5xx response this many times (0 disables)"
+    )]
+    pub retries: u32,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_RETRY_BACKOFF",
+        value_name = "SECS",
+        default_value_t = crate::retry::DEFAULT_RETRY_BACKOFF.as_secs_f64(),
+        value_parser = parse_backoff,
+        help = "Wait before the first retry, doubled for each further one (max 30s)"
+    )]
+    pub retry_backoff: f64,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Wait exactly the backoff between retries instead of a random 50-100% of it"
+    )]
+    pub no_retry_jitter: bool,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_TIMEOUT",
+        value_name = "SECS",
+        default_value_t = crate::timeouts::DEFAULT_TIMEOUT.as_secs(),
+        help = "Give up on an API call that takes longer than this (0 waits forever); uploads get extra time for their size"
+    )]
+    pub timeout: u64,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_CONNECT_TIMEOUT",
+        value_name = "SECS",
+        default_value_t = crate::timeouts::DEFAULT_CONNECT_TIMEOUT.as_secs(),
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Give up on connecting to NSG after this long"
+    )]
+    pub connect_timeout: u64,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_READ_TIMEOUT",
+        value_name = "SECS",
+        default_value_t = crate::timeouts::DEFAULT_READ_TIMEOUT.as_secs(),
+        help = "Give up on a download after this long without data, however long it takes in all (0 waits forever)"
+    )]
+    pub read_timeout: u64,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_CA_CERT",
+        value_name = "PATH",
+        help = "Also trust the root certificates in this PEM file (e.g. of a TLS-intercepting proxy)"
+    )]
+    pub ca_cert: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Do not verify NSG's TLS certificate (unsafe; prefer --ca-cert)"
+    )]
+    pub insecure: bool,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_OFFLINE",
+        help = "Don't contact NSG: list and status show what the local job database last saw"
+    )]
+    pub offline: bool,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_PROFILE",
+        value_name = "NAME",
+        help = "Use the credentials saved under this profile (see nsg login --profile)"
+    )]
+    pub profile: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_CREDENTIALS_FILE",
+        value_name = "PATH",
+        conflicts_with = "profile",
+        help = "Read and save the credentials in this file instead of ~/.nsg/credentials.json"
+    )]
+    pub credentials_file: Option<PathBuf>,
+
+    #[arg(
+        long = "output",
+        value_enum,
+        env = "NSG_OUTPUT",
+        default_value_t = OutputFormat::Text,
+        value_name = "FORMAT",
+        help = "Output format for list, status, submit and download"
+    )]
+    pub output_format: OutputFormat,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        env = "NSG_PROGRESS",
+        default_value_t = ProgressFormat::Bars,
+        value_name = "FORMAT",
+        help = "How downloads report progress: bars, or JSON lines on stderr for programs"
+    )]
+    pub progress: ProgressFormat,
+
+    #[arg(long, global = true, help = "Shorthand for --output json")]
+    pub json: bool,
+
+    #[arg(
+        short,
+        long,
+        global = true,
+        conflicts_with = "verbose",
+        help = "Only print results and errors: no banners, progress or hints"
+    )]
+    pub quiet: bool,
+
+    #[arg(
+        short,
+        long,
+        global = true,
+        action = clap::ArgAction::Count,
+        help = "Log API requests to stderr (-vv adds status and timing)"
+    )]
+    pub verbose: u8,
+
+    #[arg(
+        long,
+        global = true,
+        env = "NSG_DEBUG",
+        help = "Log every API call with its headers and response body, secrets redacted (also RUST_LOG=debug)"
+    )]
+    pub debug: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Disable colored output (also honours NO_COLOR; automatic when piped)"
+    )]
+    pub no_color: bool,
+
+    /// The `.nsg.toml` governing the working directory, if any
+    #[arg(skip)]
+    pub project: Option<ProjectConfig>,
+
+    /// `[tool_aliases]` from `config.toml`
+    #[arg(skip)]
+    pub tool_aliases: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Decorated, human-readable text
+    #[default]
+    Text,
+    /// Machine-readable JSON on stdout; progress and hints go to stderr
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProgressFormat {
+    /// Progress bars on the terminal
+    #[default]
+    Bars,
+    /// One JSON object per line on stderr: start, progress, done and failed
+    /// events with the file, bytes, total and speed
+    Json,
+}
+
+impl GlobalArgs {
+    pub fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            print_curl: self.curl,
+            user_agent: self.user_agent.clone(),
+            user_agent_tag: self.ua_tag.clone(),
+            max_concurrency: self.max_concurrency.map(usize::from),
+            rate_limit: Some(self.rate_limit),
+            verbose: self.verbose,
+            debug: self.debug || rust_log_debug(),
+            base_url: self.base_url.clone(),
+            retry: RetryPolicy {
+                retries: self.retries,
+                backoff: Duration::from_secs_f64(self.retry_backoff),
+                jitter: !self.no_retry_jitter,
+            },
+            timeouts: Timeouts {
+                connect: Duration::from_secs(self.connect_timeout),
+                request: timeouts::limit(self.timeout),
+                read: timeouts::limit(self.read_timeout),
+            },
+            ca_cert: self.ca_cert.clone(),
+            insecure: self.insecure,
+        }
+    }
+
+    /// Whether stdout is reserved for JSON
+    pub fn json_output(&self) -> bool {
+        self.json || self.output_format == OutputFormat::Json
+    }
+
+    /// Credentials of the selected `--profile`, or the default ones
+    pub fn credentials(&self) -> Result<Credentials> {
+        Credentials::load_profile(self.profile.as_deref())
+    }
+
+    /// Build an API client for `credentials` honouring the global flags
+    pub fn client(&self, credentials: Credentials) -> Result<NsgClient> {
+        if self.insecure {
+            hint!(
+                "{} TLS certificate verification is disabled (--insecure)",
+                icon("⚠").yellow().bold()
+            );
+        }
+        Ok(NsgClient::with_options(credentials, self.client_options())?)
+    }
+}
+
+/// Whether `RUST_LOG` asks for debug output from nsg, e.g. `debug`,
+/// `nsg_cli=trace` or `warn,nsg=debug`
+fn rust_log_debug() -> bool {
+    let Ok(filter) = std::env::var("RUST_LOG") else {
+        return false;
+    };
+    filter.split(',').any(|directive| {
+        let (target, level) = directive.trim().split_once('=').unwrap_or(("", directive));
+        matches!(
+            level.trim().to_ascii_lowercase().as_str(),
+            "debug" | "trace"
+        ) && (target.is_empty() || target.starts_with("nsg"))
+    })
+}
+
+/// Slowest `--rate-limit`: one request every 1000 seconds
+const MIN_RATE_LIMIT: f64 = 0.001;
+
+/// `--rate-limit`: requests per second, 0 for no limit
+pub(crate) fn parse_rate_limit(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate == 0.0 || (rate.is_finite() && rate >= MIN_RATE_LIMIT) => Ok(rate),
+        _ => Err(format!(
+            "'{}' is not a number of requests per second of at least {} (0 for no limit)",
+            value, MIN_RATE_LIMIT
+        )),
+    }
+}
+
+/// A retry backoff in seconds: finite and not negative
+pub(crate) fn parse_backoff(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(secs)
+            if secs.is_finite()
+                && (0.0..=crate::retry::MAX_RETRY_DELAY.as_secs_f64()).contains(&secs) =>
+        {
+            Ok(secs)
+        }
+        _ => Err(format!(
+            "'{}' is not a number of seconds between 0 and {}",
+            value,
+            crate::retry::MAX_RETRY_DELAY.as_secs()
+        )),
+    }
+}
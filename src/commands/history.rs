@@ -0,0 +1,172 @@
+use crate::cache::{CachedJob, JobCache};
+use crate::commands::agent::parse_duration;
+use crate::commands::output::icon;
+use crate::commands::{print_json, GlobalArgs};
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::Args;
+use colored::Colorize;
+
+#[derive(Debug, Args)]
+pub struct HistoryCommand {
+    #[arg(
+        long,
+        help = "Only show jobs submitted to this tool (e.g. NEURON_EXPANSE)"
+    )]
+    tool: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "WHEN",
+        value_parser = parse_since,
+        help = "Only show jobs submitted since a date (2024-05-01) or within a duration (7d, 12h)"
+    )]
+    since: Option<DateTime<Utc>>,
+
+    #[arg(long, help = "Only show failed jobs")]
+    failed: bool,
+
+    #[arg(long, help = "Only show jobs with this local tag")]
+    tag: Option<String>,
+
+    #[arg(short, long, help = "Show at most N jobs (newest first)")]
+    limit: Option<usize>,
+}
+
+impl HistoryCommand {
+    /// Show every job recorded in the local job database, newest first.
+    ///
+    /// Works offline and includes jobs NSG no longer knows about (deleted,
+    /// or expired from the server), since nothing here asks the server.
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let cache = JobCache::load()?;
+
+        let mut jobs: Vec<&CachedJob> = cache
+            .jobs
+            .values()
+            .filter(|job| {
+                self.tool.as_deref().is_none_or(|tool| {
+                    job.tool
+                        .as_deref()
+                        .is_some_and(|t| t.eq_ignore_ascii_case(tool))
+                })
+            })
+            .filter(|job| {
+                self.since
+                    .is_none_or(|since| job.submitted().is_some_and(|at| at >= since))
+            })
+            .filter(|job| !self.failed || job.failed)
+            .filter(|job| self.tag.as_deref().is_none_or(|tag| job.has_tag(tag)))
+            .collect();
+
+        // Newest first; jobs without a known submission time go last
+        jobs.sort_by(|a, b| {
+            b.submitted()
+                .cmp(&a.submitted())
+                .then_with(|| b.job_id.cmp(&a.job_id))
+        });
+        if let Some(limit) = self.limit {
+            jobs.truncate(limit);
+        }
+
+        if global.json_output() {
+            return print_json(&jobs);
+        }
+
+        say!("{}", "NSG Job History".bold().cyan());
+        say!("{}", "=".repeat(80).cyan());
+
+        if jobs.is_empty() {
+            say!();
+            say!("{}", "No matching jobs in the local job database".yellow());
+            say!();
+            say!("Jobs are recorded as they are submitted or looked at, e.g. by:");
+            say!("  {}", "nsg list --detailed".cyan());
+            say!();
+            return Ok(());
+        }
+
+        for job in &jobs {
+            print_job(job);
+        }
+
+        say!();
+        say!(
+            "{} job(s) from {}",
+            jobs.len().to_string().bold(),
+            JobCache::database_path()?.display().to_string().dimmed()
+        );
+        say!();
+
+        Ok(())
+    }
+}
+
+fn print_job(job: &CachedJob) {
+    let submitted = job
+        .submitted()
+        .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "unknown date".to_string());
+
+    show!();
+    show!(
+        "{}  {}  {}",
+        submitted.dimmed(),
+        job.job_id.cyan(),
+        outcome(job)
+    );
+    if let Some(tool) = &job.tool {
+        show!("  Tool:    {}", tool);
+    }
+    if let Some(input) = &job.input_file {
+        show!("  Input:   {}", input);
+    }
+    if !job.params.is_empty() {
+        let params: Vec<String> = job
+            .params
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        show!("  Params:  {}", params.join(", "));
+    }
+    if !job.tags.is_empty() {
+        show!("  Tags:    {}", job.tags.join(", ").magenta());
+    }
+    if let Some(note) = job.notes.last() {
+        show!("  Note:    {}", note.text.italic());
+    }
+    if let Some(dir) = &job.download_dir {
+        show!("  Results: {}", dir.display());
+    }
+}
+
+fn outcome(job: &CachedJob) -> String {
+    if job.failed {
+        format!("{} {}", icon("✗").red().bold(), "FAILED".red())
+    } else if job.stage.is_empty() {
+        format!("{} {}", "?".dimmed(), "unknown".dimmed())
+    } else if job.stage == "COMPLETED" {
+        format!("{} {}", icon("✓").green().bold(), job.stage)
+    } else if job.terminal {
+        format!("{} {}", icon("-").dimmed(), job.stage.dimmed())
+    } else {
+        format!("{} {}", icon("→").yellow(), job.stage.yellow())
+    }
+}
+
+/// `--since`: a date (`2024-05-01`), an RFC 3339 timestamp, or a duration
+/// back from now (`7d`, `12h`)
+pub(crate) fn parse_since(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+    if let Ok(at) = s.parse::<DateTime<Utc>>() {
+        return Ok(at);
+    }
+
+    let ago = parse_duration(s)
+        .map_err(|_| "expected a date like 2024-05-01 or a duration like 7d".to_string())?;
+    Utc::now()
+        .checked_sub_signed(ago)
+        .ok_or_else(|| format!("{} ago is too long ago", s))
+}
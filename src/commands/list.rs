@@ -1,12 +1,23 @@
-use crate::client::NsgClient;
-use crate::config::Credentials;
+use crate::api::NsgApi;
+use crate::cache::{self, CachedJob, JobCache};
+use crate::commands::output::{self, icon, truncate};
+use crate::commands::status::stage_label;
+use crate::commands::{offline, print_json, GlobalArgs};
+use crate::error::NsgError;
+use crate::models::{JobStage, JobStatus, JobSummary};
 use anyhow::Result;
-use clap::Args;
+use chrono::{DateTime, Local, SecondsFormat, TimeDelta, Utc};
+use clap::{Args, ValueEnum};
 use colored::Colorize;
+use serde::Serialize;
+use std::collections::HashMap;
 
 #[derive(Debug, Args)]
 pub struct ListCommand {
-    #[arg(long, help = "Fetch detailed status for each job (slower)")]
+    #[arg(
+        long,
+        help = "Fetch the current status of each job shown, --max-concurrency at a time"
+    )]
     detailed: bool,
 
     #[arg(short, long, help = "Limit number of jobs to display")]
@@ -21,36 +32,409 @@ pub struct ListCommand {
 
     #[arg(long, help = "Show all jobs (override default limit)")]
     all: bool,
+
+    #[arg(
+        long,
+        help = "Only show jobs submitted to this tool (e.g. NEURON_EXPANSE)"
+    )]
+    tool: Option<String>,
+
+    #[arg(long, help = "Only show jobs with this local tag")]
+    tag: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        ignore_case = true,
+        help = "Only show jobs in this stage; fetches each job's status"
+    )]
+    stage: Option<StageFilter>,
+
+    #[arg(long, help = "Only show jobs that failed; fetches each job's status")]
+    failed: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Order the jobs shown by submission date (oldest first), job ID or stage"
+    )]
+    sort: Option<SortKey>,
+
+    #[arg(long, help = "Reverse the order the jobs are shown in")]
+    reverse: bool,
+
+    #[arg(
+        long,
+        help = "Also show tags, the latest note and NSG's latest message"
+    )]
+    wide: bool,
+
+    #[arg(
+        long,
+        help = "Never shorten cells to fit the terminal; long lines wrap instead"
+    )]
+    no_truncate: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with_all = ["wide", "no_truncate"],
+        help = "Print one row per job as CSV or TSV, for spreadsheets and awk"
+    )]
+    format: Option<RowFormat>,
+}
+
+/// Delimited formats for `nsg list --format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum RowFormat {
+    Csv,
+    Tsv,
+}
+
+/// Columns of `nsg list --format`, in order. Only ever added to at the end,
+/// so scripts reading them by position keep working.
+const ROW_COLUMNS: [&str; 8] = [
+    "job_id",
+    "name",
+    "tool",
+    "stage",
+    "failed",
+    "submitted",
+    "tags",
+    "url",
+];
+
+/// Orders for `nsg list --sort`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SortKey {
+    Date,
+    Id,
+    Stage,
+}
+
+/// Stages `nsg list --stage` can select. NSG's finer-grained queue stages
+/// (COMMANDRENDERING, INPUTSTAGING, SUBMITTED) all count as QUEUE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "UPPER")]
+enum StageFilter {
+    Completed,
+    Running,
+    Queue,
+    Failed,
+}
+
+impl StageFilter {
+    fn matches(self, status: &JobStatus) -> bool {
+        self.matches_stage(&status.job_stage, status.failed)
+    }
+
+    fn matches_stage(self, stage: &JobStage, failed: bool) -> bool {
+        match self {
+            Self::Completed => *stage == JobStage::Completed && !failed,
+            Self::Running => matches!(stage, JobStage::Running | JobStage::LoadResults),
+            Self::Queue => stage.is_queued(),
+            Self::Failed => is_failure(stage, failed),
+        }
+    }
+}
+
+/// NSG marks most failures with the `failed` flag on a COMPLETED job, but
+/// also has a FAILED stage
+fn has_failed(status: &JobStatus) -> bool {
+    is_failure(&status.job_stage, status.failed)
+}
+
+fn is_failure(stage: &JobStage, failed: bool) -> bool {
+    failed || *stage == JobStage::Failed
 }
 
 impl ListCommand {
-    pub fn execute(self) -> Result<()> {
-        let credentials = Credentials::load()?;
-        let client = NsgClient::new(credentials.clone())?;
-
-        println!("{}", "NSG Job List".bold().cyan());
-        println!("{}", "=".repeat(80).cyan());
-        println!();
-        println!(
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let json = global.json_output();
+        if json && self.format.is_some() {
+            anyhow::bail!("--format can't be combined with --output json");
+        }
+        if self.format.is_some() {
+            output::reserve_stdout();
+        }
+
+        say!("{}", "NSG Job List".bold().cyan());
+        say!("{}", "=".repeat(80).cyan());
+        say!();
+        if global.offline {
+            return self.list_cached(global, json);
+        }
+
+        let credentials = global.credentials()?;
+        let client = global.client(credentials.clone())?;
+        say!(
             "{} Fetching jobs for user: {}",
-            "→".cyan(),
+            icon("→").cyan(),
             credentials.username.bold()
         );
-        println!();
+        say!();
+
+        let mut jobs = match client.list_jobs() {
+            Ok(jobs) => jobs,
+            Err(e @ NsgError::Network { .. }) => {
+                offline::warn_unreachable(&e.into());
+                return self.list_cached(global, json);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if jobs.is_empty() {
+            say!("{}", "No jobs found".yellow());
+            say!();
+            say!("You can submit a test job with:");
+            say!("  {}", "nsg submit <zip_file> --tool PY_EXPANSE".cyan());
+            return print_listing(json, self.format, &[]);
+        }
+
+        let cache = JobCache::load().unwrap_or_default();
+
+        if let Some(tool) = &self.tool {
+            jobs.retain(|job| {
+                cache
+                    .tool_for(&job.job_id)
+                    .is_some_and(|t| t.eq_ignore_ascii_case(tool))
+            });
+
+            if jobs.is_empty() {
+                say!("{}", format!("No jobs found for tool {}", tool).yellow());
+                say!();
+                return print_listing(json, self.format, &[]);
+            }
+        }
+
+        if let Some(tag) = &self.tag {
+            jobs.retain(|job| cache.get(&job.job_id).is_some_and(|j| j.has_tag(tag)));
+
+            if jobs.is_empty() {
+                say!("{}", format!("No jobs tagged {}", tag).yellow());
+                say!();
+                return print_listing(json, self.format, &[]);
+            }
+        }
+
+        // Stage filters need every candidate's status up front; the statuses
+        // are kept so they're shown without being fetched again
+        let mut prefetched = HashMap::new();
+        let mut fetched_all = false;
+        if self.stage.is_some() || self.failed {
+            let statuses = fetch_statuses(&client, &jobs);
+            fetched_all = true;
+
+            let mut unknown = 0;
+            let mut kept = Vec::new();
+            for (job, status) in jobs.into_iter().zip(statuses) {
+                let keep = match &status {
+                    Ok(status) => {
+                        self.stage.is_none_or(|stage| stage.matches(status))
+                            && (!self.failed || has_failed(status))
+                    }
+                    Err(_) => {
+                        unknown += 1;
+                        false
+                    }
+                };
+                if keep {
+                    prefetched.insert(job.job_id.clone(), status);
+                    kept.push(job);
+                }
+            }
+            jobs = kept;
+
+            if unknown > 0 {
+                eprintln!(
+                    "{} Skipped {} job(s) whose status could not be fetched",
+                    icon("⚠").yellow().bold(),
+                    unknown
+                );
+            }
+            if jobs.is_empty() {
+                say!("{}", "No jobs match the stage filter".yellow());
+                say!();
+                return print_listing(json, self.format, &[]);
+            }
+        }
+
+        let total_jobs = jobs.len();
+        self.limit_jobs(&mut jobs);
+        let showing_jobs = jobs.len();
+
+        // Only the jobs to be shown are fetched, and before sorting so the
+        // fresh dates and stages are sorted by
+        if self.detailed && !fetched_all {
+            let statuses = fetch_statuses(&client, &jobs);
+            for (job, status) in jobs.iter().zip(statuses) {
+                prefetched.insert(job.job_id.clone(), status);
+            }
+        }
+        if let Some(key) = self.sort {
+            let sorted = |job: &JobSummary| {
+                let status = prefetched.get(&job.job_id).and_then(|s| s.as_ref().ok());
+                sort_value(key, job, status, &cache)
+            };
+            // Stable, so jobs with equal or unknown keys stay in NSG's order
+            jobs.sort_by_cached_key(|job| sorted(job));
+        }
+        if self.reverse {
+            jobs.reverse();
+        }
+
+        self.say_found(total_jobs, showing_jobs);
+
+        let mut unknown = 0;
+        let listed: Vec<ListedJob> = jobs
+            .iter()
+            .map(|job| {
+                let status = match prefetched.remove(&job.job_id) {
+                    Some(Ok(status)) => Some(status),
+                    Some(Err(_)) => {
+                        unknown += 1;
+                        None
+                    }
+                    None => None,
+                };
+                let cached = cache.get(&job.job_id);
+                ListedJob {
+                    job,
+                    tool: cache.tool_for(&job.job_id),
+                    tags: cached.map(|c| c.tags.as_slice()).unwrap_or_default(),
+                    status,
+                    cached,
+                }
+            })
+            .collect();
+
+        if !json && self.format.is_none() {
+            let width = (!self.no_truncate).then(output::width).flatten();
+            print_table(&listed, &cache, self.wide, width, None);
+        }
+        if unknown > 0 {
+            eprintln!(
+                "{} The status of {} job(s) could not be fetched",
+                icon("⚠").yellow().bold(),
+                unknown
+            );
+        }
+
+        cache::record_statuses(listed.iter().filter_map(|l| l.status.as_ref()));
+
+        say!();
+        say!("{}", "Commands:".bold());
+        say!("  Check job status:    {}", "nsg status <JOB_ID>".cyan());
+        say!("  Download results:    {}", "nsg download <JOB_ID>".cyan());
+
+        if showing_jobs < total_jobs {
+            say!();
+            say!("{}", "Tip:".bold());
+            say!("  Use {} to see all {} jobs", "--all".cyan(), total_jobs);
+            say!("  Use {} to see detailed status", "--detailed".cyan());
+            say!("  Use {} to limit results", "--limit N".cyan());
+            say!("  Use {} to show N most recent jobs", "--recent N".cyan());
+        }
+        say!();
+
+        print_listing(json, self.format, &listed)
+    }
 
-        let mut jobs = client.list_jobs()?;
+    /// The jobs as the local job database last saw them, for `--offline` or
+    /// when NSG can't be reached. Only jobs of the logged-in user that
+    /// haven't been cancelled or deleted from here are shown.
+    fn list_cached(&self, global: &GlobalArgs, json: bool) -> Result<()> {
+        let cache = JobCache::load()?;
+        let owner = global
+            .credentials()
+            .ok()
+            .map(|credentials| format!("/job/{}/", credentials.username));
+        if self.detailed {
+            hint!(
+                "{} --detailed needs NSG; showing the last stage seen",
+                icon("⚠").yellow()
+            );
+        }
 
+        let mut cached: Vec<&CachedJob> = cache
+            .jobs
+            .values()
+            .filter(|job| !matches!(job.stage.as_str(), "CANCELLED" | "DELETED"))
+            .filter(|job| {
+                job.url
+                    .as_deref()
+                    .is_some_and(|url| owner.as_deref().is_none_or(|owner| url.contains(owner)))
+            })
+            .filter(|job| {
+                self.tool.as_deref().is_none_or(|tool| {
+                    cache
+                        .tool_for(&job.job_id)
+                        .is_some_and(|t| t.eq_ignore_ascii_case(tool))
+                })
+            })
+            .filter(|job| self.tag.as_deref().is_none_or(|tag| job.has_tag(tag)))
+            .filter(|job| {
+                let stage = JobStage::from(job.stage.as_str());
+                self.stage
+                    .is_none_or(|filter| filter.matches_stage(&stage, job.failed))
+                    && (!self.failed || is_failure(&stage, job.failed))
+            })
+            .collect();
+        // Oldest first, as NSG lists them
+        cached.sort_by_key(|job| (job.submitted(), job.job_id.clone()));
+
+        let mut jobs: Vec<JobSummary> = cached
+            .iter()
+            .map(|job| JobSummary {
+                job_id: job.job_id.clone(),
+                url: job.url.clone().unwrap_or_default(),
+            })
+            .collect();
         if jobs.is_empty() {
-            println!("{}", "No jobs found".yellow());
-            println!();
-            println!("You can submit a test job with:");
-            println!("  {}", "nsg submit <zip_file> --tool PY_EXPANSE".cyan());
-            return Ok(());
+            say!("{}", "No matching jobs in the local job database".yellow());
+            say!();
+            return print_listing(json, self.format, &[]);
         }
 
         let total_jobs = jobs.len();
+        self.limit_jobs(&mut jobs);
+        if let Some(key) = self.sort {
+            jobs.sort_by_cached_key(|job| sort_value(key, job, None, &cache));
+        }
+        if self.reverse {
+            jobs.reverse();
+        }
+        self.say_found(total_jobs, jobs.len());
 
-        // Apply limit/recent filters
+        let listed: Vec<ListedJob> = jobs
+            .iter()
+            .map(|job| {
+                let cached = cache.get(&job.job_id);
+                ListedJob {
+                    job,
+                    tool: cache.tool_for(&job.job_id),
+                    tags: cached.map(|c| c.tags.as_slice()).unwrap_or_default(),
+                    status: None,
+                    cached,
+                }
+            })
+            .collect();
+        let as_of = listed
+            .iter()
+            .filter_map(|listed| listed.cached.map(|c| c.updated_at))
+            .max();
+
+        if !json && self.format.is_none() {
+            let width = (!self.no_truncate).then(output::width).flatten();
+            print_table(&listed, &cache, self.wide, width, as_of);
+        }
+        say!();
+
+        print_listing(json, self.format, &listed)
+    }
+
+    /// Keep the jobs `--all`, `--limit` or `--recent` ask for
+    fn limit_jobs(&self, jobs: &mut Vec<JobSummary>) {
         if self.all {
             // Show all jobs, no filtering
         } else if let Some(limit) = self.limit {
@@ -60,108 +444,431 @@ impl ListCommand {
             // Default: show N most recent jobs
             jobs.drain(0..jobs.len() - self.recent);
         }
+    }
 
-        let showing_jobs = jobs.len();
+    /// How many jobs were found and are shown, and which filters found them
+    fn say_found(&self, total_jobs: usize, showing_jobs: usize) {
+        let mut for_tool = self
+            .tool
+            .as_deref()
+            .map(|tool| format!(" for tool {}", tool.bold()))
+            .unwrap_or_default();
+        if let Some(stage) = self.stage {
+            let name = stage.to_possible_value().map(|v| v.get_name().to_string());
+            for_tool += &format!(" in stage {}", name.unwrap_or_default().bold());
+        }
+        if self.failed {
+            for_tool += " that failed";
+        }
 
         if showing_jobs < total_jobs {
-            println!(
-                "Found {} job(s) total, showing {}",
+            say!(
+                "Found {} job(s) total{}, showing {}",
                 total_jobs.to_string().bold(),
+                for_tool,
                 showing_jobs.to_string().bold()
             );
         } else {
-            println!("Found {} job(s)", jobs.len().to_string().bold());
+            say!(
+                "Found {} job(s){}",
+                showing_jobs.to_string().bold(),
+                for_tool
+            );
         }
-        println!();
-        println!("{}", "=".repeat(80));
+        say!();
+    }
+}
 
-        for (i, job) in jobs.iter().enumerate() {
-            println!();
-            println!("Job #{}", (i + 1).to_string().bold());
-            println!("  ID:  {}", job.job_id.cyan());
+/// One job in `nsg list --output json`
+#[derive(Debug, Serialize)]
+struct ListedJob<'a> {
+    #[serde(flatten)]
+    job: &'a JobSummary,
+    tool: Option<&'a str>,
+    tags: &'a [String],
+    /// Only with `--detailed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<JobStatus>,
+    #[serde(skip)]
+    cached: Option<&'a CachedJob>,
+}
 
-            if self.detailed {
-                println!("  {}", "Fetching details...".dimmed());
-                match client.get_job_status(&job.url) {
-                    Ok(status) => {
-                        let stage_icon = get_stage_icon(&status.job_stage);
-                        println!("  Status: {} {}", stage_icon, status.job_stage.bold());
-
-                        if status.failed {
-                            println!("  Failed: {} YES", "✗".red().bold());
-                        }
-
-                        if let Some(date) = &status.date_submitted {
-                            println!("  Submitted: {}", format_timestamp(date));
-                        }
-
-                        if !status.messages.is_empty() {
-                            if let Some(latest) = status.messages.last() {
-                                println!(
-                                    "  Latest: [{}] {}",
-                                    latest.stage,
-                                    truncate(&latest.text, 100)
-                                );
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        println!("  Status: {} (failed to fetch)", "?".yellow());
-                    }
-                }
-            } else {
-                println!(
-                    "  Status: {} (use --detailed for full status)",
-                    "?".dimmed()
-                );
-            }
+/// Fetch the status of every job, a few at a time, in the order given
+fn fetch_statuses(client: &dyn NsgApi, jobs: &[JobSummary]) -> Vec<Result<JobStatus, NsgError>> {
+    say!(
+        "  {}",
+        format!(
+            "Fetching status of {} job(s) ({} at a time)...",
+            jobs.len(),
+            client.max_concurrency().min(jobs.len())
+        )
+        .dimmed()
+    );
+    let urls: Vec<&str> = jobs.iter().map(|job| job.url.as_str()).collect();
+    client.get_job_statuses(&urls)
+}
 
-            println!("  URL: {}", job.url.dimmed());
-            println!("{}", "=".repeat(80));
+/// What `nsg list --sort` compares. Jobs whose date or stage isn't known,
+/// from `status` or the local database, sort after all the others.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+    Date(DateTime<Utc>),
+    Id(String),
+    Stage(JobStage, bool),
+    Unknown,
+}
+
+fn sort_value(
+    key: SortKey,
+    job: &JobSummary,
+    status: Option<&JobStatus>,
+    cache: &JobCache,
+) -> SortValue {
+    let cached = cache.get(&job.job_id);
+    match key {
+        SortKey::Id => SortValue::Id(job.job_id.clone()),
+        SortKey::Date => {
+            submitted_at(status, cache.get(&job.job_id)).map_or(SortValue::Unknown, SortValue::Date)
         }
+        SortKey::Stage => match (status, cached) {
+            (Some(status), _) => SortValue::Stage(status.job_stage.clone(), status.failed),
+            (None, Some(cached)) if !cached.stage.is_empty() => {
+                SortValue::Stage(JobStage::from(cached.stage.as_str()), cached.failed)
+            }
+            _ => SortValue::Unknown,
+        },
+    }
+}
+
+fn print_listing(json: bool, format: Option<RowFormat>, listed: &[ListedJob]) -> Result<()> {
+    if json {
+        print_json(&listed)?;
+    }
+    if let Some(format) = format {
+        print_rows(format, listed);
+    }
+    Ok(())
+}
 
-        println!();
-        println!("{}", "Commands:".bold());
-        println!("  Check job status:    {}", "nsg status <JOB_ID>".cyan());
-        println!("  Download results:    {}", "nsg download <JOB_ID>".cyan());
+/// Print the jobs as [`ROW_COLUMNS`], with a header line. The stage and
+/// date come from the local database for jobs whose status wasn't fetched,
+/// and are empty if it doesn't know them either.
+fn print_rows(format: RowFormat, listed: &[ListedJob]) {
+    let line = |fields: &[String]| {
+        let fields: Vec<String> = match format {
+            RowFormat::Csv => fields.iter().map(|field| csv_field(field)).collect(),
+            // TSV has no quoting, so separators in values become spaces
+            RowFormat::Tsv => fields
+                .iter()
+                .map(|field| field.replace(['\t', '\n', '\r'], " "))
+                .collect(),
+        };
+        let separator = match format {
+            RowFormat::Csv => ",",
+            RowFormat::Tsv => "\t",
+        };
+        println!("{}", fields.join(separator));
+    };
 
-        if showing_jobs < total_jobs {
-            println!();
-            println!("{}", "Tip:".bold());
-            println!("  Use {} to see all {} jobs", "--all".cyan(), total_jobs);
-            println!("  Use {} to see detailed status", "--detailed".cyan());
-            println!("  Use {} to limit results", "--limit N".cyan());
-            println!("  Use {} to show N most recent jobs", "--recent N".cyan());
+    line(&ROW_COLUMNS.map(String::from));
+    for listed in listed {
+        let status = listed.status.as_ref();
+        let (stage, failed) = match (status, listed.cached) {
+            (Some(status), _) => (
+                status.job_stage.as_str().to_string(),
+                status.failed.to_string(),
+            ),
+            (None, Some(cached)) if !cached.stage.is_empty() => {
+                (cached.stage.clone(), cached.failed.to_string())
+            }
+            _ => Default::default(),
+        };
+        line(&[
+            listed.job.job_id.clone(),
+            job_name(status).unwrap_or_default(),
+            listed.tool.unwrap_or_default().to_string(),
+            stage,
+            failed,
+            submitted_at(status, listed.cached)
+                .map(|date| date.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .unwrap_or_default(),
+            listed.tags.join(";"),
+            listed.job.url.clone(),
+        ]);
+    }
+}
+
+/// `field` quoted as RFC 4180 asks, if it needs to be
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// The job's `--job-name`, which only NSG knows
+fn job_name(status: Option<&JobStatus>) -> Option<String> {
+    status?
+        .metadata
+        .iter()
+        .find(|(key, _)| key == "clientJobName")
+        .map(|(_, value)| value.clone())
+}
+
+/// When the job was submitted, from `status` or the local database
+fn submitted_at(status: Option<&JobStatus>, cached: Option<&CachedJob>) -> Option<DateTime<Utc>> {
+    status
+        .and_then(|s| s.date_submitted.as_deref())
+        .or_else(|| cached.and_then(|c| c.date_submitted.as_deref()))
+        .and_then(|date| date.parse().ok())
+        .or_else(|| cached.and_then(|c| c.submitted_at))
+}
+
+/// How a table cell is colored
+#[derive(Debug, Clone, Copy)]
+enum Tint {
+    Plain,
+    Cyan,
+    Green,
+    Yellow,
+    Red,
+    Magenta,
+    Dimmed,
+}
+
+impl Tint {
+    fn paint(self, text: String) -> String {
+        match self {
+            Self::Plain => text,
+            Self::Cyan => text.cyan().to_string(),
+            Self::Green => text.green().to_string(),
+            Self::Yellow => text.yellow().to_string(),
+            Self::Red => text.red().bold().to_string(),
+            Self::Magenta => text.magenta().to_string(),
+            Self::Dimmed => text.dimmed().to_string(),
         }
-        println!();
+    }
 
-        Ok(())
+    fn for_stage(stage: &JobStage, failed: bool) -> Self {
+        match stage {
+            _ if failed => Self::Red,
+            JobStage::Completed => Self::Green,
+            JobStage::Failed => Self::Red,
+            JobStage::Running | JobStage::LoadResults => Self::Yellow,
+            stage if stage.is_queued() => Self::Cyan,
+            _ => Self::Plain,
+        }
     }
 }
 
-fn get_stage_icon(stage: &str) -> String {
-    match stage {
-        "COMPLETED" => "✓".green().bold().to_string(),
-        "RUNNING" | "RUN" => "⟳".yellow().bold().to_string(),
-        "QUEUE" | "SUBMITTED" => "⏳".cyan().to_string(),
-        "FAILED" => "✗".red().bold().to_string(),
-        _ => "?".dimmed().to_string(),
+/// A column of the `nsg list` table
+struct Column {
+    header: &'static str,
+    cells: Vec<(String, Tint)>,
+    /// Narrowest the column is cut to when the table is wider than the
+    /// terminal; `None` for columns that are never cut
+    min_width: Option<usize>,
+}
+
+impl Column {
+    fn new(header: &'static str, min_width: Option<usize>) -> Self {
+        Self {
+            header,
+            cells: Vec::new(),
+            min_width,
+        }
+    }
+
+    fn width(&self) -> usize {
+        self.cells
+            .iter()
+            .map(|(text, _)| text.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(self.header.len())
     }
 }
 
-fn format_timestamp(ts: &str) -> String {
-    use chrono::{DateTime, Utc};
-    if let Ok(dt) = ts.parse::<DateTime<Utc>>() {
-        dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
-    } else {
-        ts.to_string()
+/// Print the jobs as a table, cut to `width` columns if given by shrinking
+/// the rightmost columns first. `wide` adds tags, the latest note and the
+/// latest NSG message. `offline_as_of` is when the local job database was
+/// last updated, if NSG wasn't asked at all.
+fn print_table(
+    listed: &[ListedJob],
+    cache: &JobCache,
+    wide: bool,
+    width: Option<usize>,
+    offline_as_of: Option<DateTime<Utc>>,
+) {
+    let now = Utc::now();
+    let mut id = Column::new("JOB", None);
+    let mut name = Column::new("NAME", Some(8));
+    let mut tool = Column::new("TOOL", Some(8));
+    let mut stage = Column::new("STAGE", None);
+    let mut submitted = Column::new("SUBMITTED", None);
+    let mut age = Column::new("AGE", None);
+    let mut tags = Column::new("TAGS", Some(8));
+    let mut note = Column::new("NOTE", Some(8));
+    let mut message = Column::new("LAST MESSAGE", Some(12));
+    let mut stale = false;
+
+    for listed in listed {
+        let job_id = &listed.job.job_id;
+        let status = listed.status.as_ref();
+        let cached = cache.get(job_id);
+
+        id.cells.push((job_id.clone(), Tint::Cyan));
+
+        name.cells.push((
+            job_name(status).unwrap_or_else(|| "-".to_string()),
+            Tint::Plain,
+        ));
+        tool.cells
+            .push((listed.tool.unwrap_or("-").to_string(), Tint::Plain));
+
+        // Without a fresh status, the stage last seen here, marked as such
+        let (label, tint) = match (status, cached) {
+            (Some(status), _) => (
+                stage_label(status.job_stage.as_str(), status.failed),
+                Tint::for_stage(&status.job_stage, status.failed),
+            ),
+            (None, Some(cached)) if !cached.stage.is_empty() => {
+                stale = true;
+                (
+                    format!("{}*", stage_label(&cached.stage, cached.failed)),
+                    Tint::Dimmed,
+                )
+            }
+            _ => ("?".to_string(), Tint::Dimmed),
+        };
+        stage.cells.push((label, tint));
+
+        let date = submitted_at(status, cached);
+        submitted.cells.push((
+            date.map(|date| {
+                date.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|| "-".to_string()),
+            Tint::Plain,
+        ));
+        age.cells.push((
+            date.map(|date| format_age(now - date))
+                .unwrap_or_else(|| "-".to_string()),
+            Tint::Plain,
+        ));
+
+        tags.cells.push((
+            if listed.tags.is_empty() {
+                "-".to_string()
+            } else {
+                listed.tags.join(",")
+            },
+            Tint::Magenta,
+        ));
+        note.cells.push((
+            cached
+                .and_then(|c| c.notes.last())
+                .map(|note| note.text.clone())
+                .unwrap_or_else(|| "-".to_string()),
+            Tint::Plain,
+        ));
+        message.cells.push((
+            status
+                .and_then(|s| s.messages.last())
+                .map(|message| message.text.clone())
+                .unwrap_or_else(|| "-".to_string()),
+            Tint::Dimmed,
+        ));
+    }
+
+    let mut columns = vec![id];
+    // Names are only known from fetched statuses
+    if name.cells.iter().any(|(text, _)| text != "-") {
+        columns.push(name);
+    }
+    columns.extend([tool, stage, submitted, age]);
+    if wide {
+        columns.extend([tags, note, message]);
+    }
+
+    let mut widths: Vec<usize> = columns.iter().map(Column::width).collect();
+    if let Some(width) = width {
+        let total = widths.iter().sum::<usize>() + 2 * (widths.len() - 1);
+        let mut excess = total.saturating_sub(width);
+        for (i, column) in columns.iter().enumerate().rev() {
+            if let Some(min_width) = column.min_width {
+                let cut = widths[i].saturating_sub(min_width).min(excess);
+                widths[i] -= cut;
+                excess -= cut;
+            }
+        }
+    }
+
+    let line = |cells: Vec<(String, Tint)>| {
+        let last = cells.len() - 1;
+        cells
+            .into_iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(i, ((text, tint), &width))| {
+                let text = truncate(&text, width);
+                if i == last {
+                    tint.paint(text)
+                } else {
+                    tint.paint(format!("{:<width$}", text))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+
+    show!(
+        "{}",
+        line(
+            columns
+                .iter()
+                .map(|column| (column.header.to_string(), Tint::Plain))
+                .collect()
+        )
+        .bold()
+    );
+    for row in 0..listed.len() {
+        show!(
+            "{}",
+            line(
+                columns
+                    .iter()
+                    .map(|column| column.cells[row].clone())
+                    .collect()
+            )
+        );
+    }
+
+    if stale {
+        let footnote = match offline_as_of {
+            Some(at) => format!(
+                "* Last stage seen on this machine, {}",
+                offline::stale_as_of(at)
+            ),
+            None => "* Last stage seen on this machine; use --detailed for the current one".into(),
+        };
+        say!();
+        say!("{}", footnote.dimmed());
     }
 }
 
-fn truncate(s: &str, max_len: usize) -> String {
-    if s.len() <= max_len {
-        s.to_string()
+/// Time since submission, in its largest whole unit: `40s`, `12m`, `5h`, `3d`
+fn format_age(age: TimeDelta) -> String {
+    if age.num_minutes() < 1 {
+        format!("{}s", age.num_seconds().max(0))
+    } else if age.num_hours() < 1 {
+        format!("{}m", age.num_minutes())
+    } else if age.num_days() < 2 {
+        format!("{}h", age.num_hours())
     } else {
-        format!("{}...", &s[..max_len])
+        format!("{}d", age.num_days())
     }
 }
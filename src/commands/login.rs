@@ -1,9 +1,12 @@
-use crate::client::NsgClient;
-use crate::config::Credentials;
-use anyhow::Result;
+use crate::commands::output::icon;
+use crate::commands::GlobalArgs;
+use crate::config::{CredentialSource, Credentials, PasswordStore};
+use crate::error::NsgError;
+use anyhow::{Context, Result};
 use clap::Args;
 use colored::Colorize;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
 
 #[derive(Debug, Args)]
 pub struct LoginCommand {
@@ -18,10 +21,23 @@ pub struct LoginCommand {
 
     #[arg(long, help = "Skip connection test")]
     no_verify: bool,
+
+    #[arg(
+        long,
+        help = "Keep the password in the credentials file instead of the OS keyring"
+    )]
+    no_keyring: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "no_keyring",
+        help = "Encrypt the password in the credentials file with a passphrase"
+    )]
+    encrypt: bool,
 }
 
 impl LoginCommand {
-    pub fn execute(self) -> Result<()> {
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
         println!("{}", "NSG Login".bold().cyan());
         println!("{}", "=".repeat(60).cyan());
         println!();
@@ -31,21 +47,25 @@ impl LoginCommand {
         let app_key = self.get_or_prompt_app_key()?;
 
         println!();
-        println!("{} Saving credentials...", "→".cyan());
+        println!("{} Saving credentials...", icon("→").cyan());
 
         let credentials = Credentials::new(username, password, app_key);
 
         if !self.no_verify {
-            println!("{} Testing connection to NSG...", "→".cyan());
-            let client = NsgClient::new(credentials.clone())?;
+            println!("{} Testing connection to NSG...", icon("→").cyan());
+            let client = global.client(credentials.clone())?;
 
             match client.test_connection() {
                 Ok(_) => {
-                    println!("{} Connection successful!", "✓".green().bold());
+                    println!("{} Connection successful!", icon("✓").green().bold());
                 }
                 Err(e) => {
                     eprintln!();
-                    eprintln!("{} {}", "✗".red().bold(), "Authentication failed!".red());
+                    eprintln!(
+                        "{} {}",
+                        icon("✗").red().bold(),
+                        "Authentication failed!".red()
+                    );
                     eprintln!();
                     eprintln!("Error: {}", e);
                     eprintln!();
@@ -63,21 +83,39 @@ impl LoginCommand {
             }
         }
 
-        credentials.save()?;
+        let store = if self.encrypt {
+            let passphrase = prompt_new_passphrase()?;
+            credentials.save_profile_encrypted(global.profile.as_deref(), &passphrase)?;
+            PasswordStore::Encrypted
+        } else {
+            credentials.save_profile(global.profile.as_deref(), !self.no_keyring)?
+        };
 
         println!();
         println!("{}", "=".repeat(60).green());
         println!(
             "{} {}",
-            "✓".green().bold(),
+            icon("✓").green().bold(),
             "Login successful!".green().bold()
         );
         println!("{}", "=".repeat(60).green());
         println!();
         println!(
             "Credentials saved to: {}",
-            Credentials::credentials_location().cyan()
+            Credentials::credentials_location(global.profile.as_deref()).cyan()
         );
+        match store {
+            PasswordStore::Keyring => println!("Password stored in the OS keyring"),
+            PasswordStore::File if self.no_keyring => {}
+            PasswordStore::File => println!(
+                "{} No OS keyring available - the password is kept in the credentials file",
+                icon("⚠").yellow()
+            ),
+            PasswordStore::Encrypted => println!(
+                "Password encrypted with your passphrase; {} asks for it once per session",
+                "nsg agent start".cyan()
+            ),
+        }
         println!();
         println!("You can now use:");
         println!("  {} - List your NSG jobs", "nsg list".cyan());
@@ -149,3 +187,94 @@ impl LoginCommand {
         Ok(app_key)
     }
 }
+
+/// Ask on the terminal for the passphrase of the encrypted credentials at
+/// `path`; installed with [`crate::config::set_passphrase_prompt`]
+pub fn prompt_passphrase(path: &Path) -> Result<String> {
+    rpassword::prompt_password(format!("Passphrase for {}: ", path.display())).context(
+        "No terminal to ask for the passphrase; start 'nsg agent' to unlock the credentials",
+    )
+}
+
+/// Ask twice for the passphrase to encrypt new credentials with
+fn prompt_new_passphrase() -> Result<String> {
+    let passphrase = rpassword::prompt_password("Passphrase: ")?;
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase cannot be empty");
+    }
+    if rpassword::prompt_password("Repeat passphrase: ")? != passphrase {
+        anyhow::bail!("Passphrases don't match");
+    }
+    Ok(passphrase)
+}
+
+/// Whether `err` comes from NSG rejecting the credentials (HTTP 401/403)
+pub fn is_rejected(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| matches!(cause.downcast_ref(), Some(NsgError::Auth { .. })))
+}
+
+/// After NSG rejected the credentials: say where they came from and how to
+/// replace them, and on a terminal offer to log in again right away
+pub fn offer_relogin(global: &GlobalArgs) {
+    let profile = global.profile.as_deref();
+    let login = match (profile, &global.credentials_file) {
+        (Some(name), _) => format!("nsg login --profile {}", name),
+        (None, Some(path)) => format!("nsg --credentials-file {} login", path.display()),
+        (None, None) => "nsg login".to_string(),
+    };
+
+    eprintln!(
+        "{} NSG no longer accepts these credentials: the password may have changed, \
+         or the application key been revoked.",
+        icon("⚠").yellow().bold()
+    );
+    match Credentials::load_profile_with_source(profile).map(|(_, source)| source) {
+        Ok(CredentialSource::Environment) => {
+            eprintln!(
+                "   They come from NSG_USERNAME, NSG_PASSWORD and NSG_APP_KEY; update those."
+            );
+            return;
+        }
+        Ok(CredentialSource::Agent) => {
+            eprintln!(
+                "   They come from a running agent; stop it with {} and run {}.",
+                "nsg agent stop".cyan(),
+                login.cyan()
+            );
+            return;
+        }
+        Ok(CredentialSource::File { .. }) | Err(_) => {}
+    }
+
+    let interactive = !crate::commands::output::quiet()
+        && io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+        && io::stderr().is_terminal();
+    if !interactive {
+        eprintln!("   Run {} to replace them.", login.cyan());
+        return;
+    }
+
+    eprint!("Log in again now? [y/N] ");
+    let _ = io::stderr().flush();
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).is_err() || !input.trim().eq_ignore_ascii_case("y") {
+        eprintln!("   Run {} to replace them.", login.cyan());
+        return;
+    }
+    eprintln!();
+
+    let relogin = LoginCommand {
+        username: None,
+        password: None,
+        app_key: None,
+        no_verify: false,
+        no_keyring: false,
+        encrypt: false,
+    };
+    match relogin.execute(global) {
+        Ok(()) => eprintln!("Run the command again to retry it."),
+        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+    }
+}
@@ -0,0 +1,107 @@
+use crate::agent;
+use crate::commands::output::icon;
+use crate::commands::GlobalArgs;
+use crate::config::Credentials;
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use std::io::Write;
+
+#[derive(Debug, Args)]
+pub struct LogoutCommand {
+    #[arg(long, help = "Remove the default credentials and every saved profile")]
+    all: bool,
+
+    #[arg(short, long, help = "Don't ask for confirmation")]
+    yes: bool,
+}
+
+impl LogoutCommand {
+    /// Delete stored credentials (the default ones, the `--profile` ones, or
+    /// all of them) and stop an agent holding them
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        if self.all && global.profile.is_some() {
+            anyhow::bail!("--all already covers every profile; drop --profile");
+        }
+
+        let profiles: Vec<Option<String>> = if self.all {
+            std::iter::once(None)
+                .chain(Credentials::list_profiles()?.into_iter().map(Some))
+                .collect()
+        } else {
+            vec![global.profile.clone()]
+        };
+
+        // Remember who each profile logs in as, to recognise an agent holding them
+        let stored: Vec<(Option<String>, Option<Credentials>)> = profiles
+            .into_iter()
+            .map(|profile| {
+                let creds = Credentials::load_profile_from_file(profile.as_deref()).ok();
+                (profile, creds)
+            })
+            .filter(|(_, creds)| creds.is_some())
+            .collect();
+
+        let agent_user = agent::status().ok().map(|status| status.username);
+        let stop_agent = agent_user.as_ref().is_some_and(|user| {
+            self.all
+                || stored
+                    .iter()
+                    .any(|(_, creds)| creds.as_ref().is_some_and(|c| &c.username == user))
+        });
+
+        if stored.is_empty() && !stop_agent {
+            say!(
+                "{} Not logged in - no credentials at {}",
+                icon("⚠").yellow(),
+                Credentials::credentials_location(global.profile.as_deref()).cyan()
+            );
+            return Ok(());
+        }
+
+        say!("{}", "Credentials to remove:".bold());
+        for (profile, creds) in &stored {
+            say!(
+                "  {:<12} {}  {}",
+                profile.as_deref().unwrap_or("(default)").cyan(),
+                creds.as_ref().map(|c| c.username.as_str()).unwrap_or("-"),
+                Credentials::credentials_location(profile.as_deref()).dimmed()
+            );
+        }
+        if let (true, Some(user)) = (stop_agent, &agent_user) {
+            say!("  {:<12} {}", "agent".cyan(), user);
+        }
+        say!();
+
+        if !self.yes {
+            print!("Log out and delete these credentials? [y/N] ");
+            std::io::stdout().flush()?;
+
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            if !input.trim().eq_ignore_ascii_case("y") {
+                say!("Not logged out.");
+                return Ok(());
+            }
+        }
+
+        if stop_agent {
+            agent::stop()?;
+            say!("{} Agent stopped", icon("✓").green());
+        }
+
+        for (profile, _) in &stored {
+            Credentials::remove_profile(profile.as_deref())?;
+            say!(
+                "{} Removed {}",
+                icon("✓").green(),
+                Credentials::credentials_location(profile.as_deref())
+            );
+        }
+
+        say!();
+        say!("{} Logged out", icon("✓").green().bold());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use crate::cache;
+use crate::commands::output::icon;
+use crate::commands::GlobalArgs;
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Args)]
+pub struct LogsCommand {
+    #[arg(help = "Job URL or Job ID")]
+    job: String,
+
+    #[arg(long, conflicts_with = "stderr", help = "Only show stdout.txt")]
+    stdout: bool,
+
+    #[arg(long, help = "Only show stderr.txt")]
+    stderr: bool,
+
+    #[arg(
+        long,
+        help = "Print straight to the terminal instead of through $PAGER"
+    )]
+    no_pager: bool,
+}
+
+impl LogsCommand {
+    /// Fetch just the log files of a job, from its results once it has
+    /// finished or from its working directory while it runs
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+
+        let wanted: &[&str] = match (self.stdout, self.stderr) {
+            (true, _) => &["stdout.txt"],
+            (_, true) => &["stderr.txt"],
+            _ => &["stdout.txt", "stderr.txt"],
+        };
+
+        let status = client.get_job_status(&self.job)?;
+        cache::record_statuses([&status]);
+
+        let finished = status.terminal_stage || status.failed;
+        let files = if finished {
+            client.get_output_files(&status)?
+        } else {
+            hint!(
+                "{} Job is {} - showing logs from its working directory",
+                icon("→").cyan(),
+                status.job_stage.as_str().bold()
+            );
+            client.list_working_dir(&status)?
+        };
+
+        let mut output = Vec::new();
+        for (i, name) in wanted.iter().enumerate() {
+            let Some(file) = files.iter().find(|f| f.filename == *name) else {
+                eprintln!("{} {} not found for this job", icon("⚠").yellow(), name);
+                continue;
+            };
+
+            if wanted.len() > 1 {
+                if i > 0 {
+                    output.push(b'\n');
+                }
+                writeln!(output, "==> {} <==", name)?;
+            }
+            output.extend(client.read_file_from(file, 0)?);
+        }
+
+        if output.is_empty() {
+            return Ok(());
+        }
+
+        if self.no_pager || !std::io::stdout().is_terminal() || !page(&output) {
+            let mut stdout = std::io::stdout();
+            stdout.write_all(&output)?;
+            stdout.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Show `content` through `$PAGER` (default `less -FRX`, which exits straight
+/// away when it fits on one screen). Returns false if no pager could be run.
+fn page(content: &[u8]) -> bool {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -FRX".to_string());
+    let mut parts = pager.split_whitespace();
+    let Some(program) = parts.next() else {
+        return false;
+    };
+
+    let Ok(mut child) = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()
+    else {
+        return false;
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user quitting the pager early closes the pipe; that's fine
+        let _ = stdin.write_all(content);
+    }
+    let _ = child.wait();
+    true
+}
@@ -0,0 +1,42 @@
+use crate::commands::output::icon;
+use anyhow::{Context, Result};
+use clap::Args;
+use clap_mangen::Man;
+use colored::Colorize;
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct ManCommand {
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Write nsg.1 and an nsg-<command>.1 page per subcommand into DIR instead of printing nsg.1"
+    )]
+    out_dir: Option<PathBuf>,
+}
+
+impl ManCommand {
+    /// Render man pages for `command` (the whole `nsg` CLI), for distro
+    /// packages to install under `share/man/man1`
+    pub fn execute(self, command: clap::Command) -> Result<()> {
+        let Some(dir) = self.out_dir else {
+            let mut page = Vec::new();
+            Man::new(command).render(&mut page)?;
+            std::io::stdout().write_all(&page)?;
+            return Ok(());
+        };
+
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        clap_mangen::generate_to(command, &dir)
+            .with_context(|| format!("Failed to write man pages to {}", dir.display()))?;
+
+        say!(
+            "{} Man pages written to {}",
+            icon("✓").green().bold(),
+            dir.display().to_string().cyan()
+        );
+        Ok(())
+    }
+}
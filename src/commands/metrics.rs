@@ -0,0 +1,133 @@
+use crate::commands::output::icon;
+use crate::metrics::UsageMetrics;
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use colored::Colorize;
+
+#[derive(Debug, Args)]
+pub struct MetricsCommand {
+    #[command(subcommand)]
+    action: MetricsAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum MetricsAction {
+    #[command(about = "Show locally recorded usage metrics")]
+    Show,
+
+    #[command(about = "Opt in to anonymous usage metrics")]
+    Enable {
+        #[arg(
+            long,
+            help = "Also upload aggregate counts to this URL about once a week"
+        )]
+        upload_url: Option<String>,
+    },
+
+    #[command(about = "Opt out and stop recording usage metrics")]
+    Disable,
+
+    #[command(about = "Clear all recorded usage metrics")]
+    Reset,
+}
+
+impl MetricsCommand {
+    pub fn execute(self) -> Result<()> {
+        let mut metrics = UsageMetrics::load()?;
+
+        match self.action {
+            MetricsAction::Show => print_metrics(&metrics),
+            MetricsAction::Enable { upload_url } => {
+                metrics.enabled = true;
+                if upload_url.is_some() {
+                    metrics.upload_url = upload_url;
+                }
+                metrics.save()?;
+
+                println!("{} Usage metrics enabled", icon("✓").green().bold());
+                println!();
+                println!("Only subcommand names and error categories are recorded.");
+                println!("No job IDs, usernames, file names or parameters are collected.");
+                if let Some(url) = &metrics.upload_url {
+                    println!(
+                        "Aggregate counts will be uploaded weekly to: {}",
+                        url.cyan()
+                    );
+                } else {
+                    println!("Metrics stay on this machine (no upload URL configured).");
+                }
+                println!();
+            }
+            MetricsAction::Disable => {
+                metrics.enabled = false;
+                metrics.upload_url = None;
+                metrics.save()?;
+
+                println!("{} Usage metrics disabled", icon("✓").green().bold());
+                println!(
+                    "  Run {} to also delete recorded counts",
+                    "nsg metrics reset".cyan()
+                );
+            }
+            MetricsAction::Reset => {
+                metrics.reset();
+                metrics.save()?;
+
+                println!("{} Usage metrics cleared", icon("✓").green().bold());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn print_metrics(metrics: &UsageMetrics) {
+    println!("{}", "NSG Usage Metrics".bold().cyan());
+    println!("{}", "=".repeat(60).cyan());
+    println!();
+
+    if metrics.enabled {
+        println!("Status:       {} enabled", icon("✓").green());
+    } else {
+        println!(
+            "Status:       {} disabled (opt in with {})",
+            icon("✗").dimmed(),
+            "nsg metrics enable".cyan()
+        );
+    }
+
+    if let Some(url) = &metrics.upload_url {
+        println!("Upload URL:   {}", url);
+    }
+    if let Some(last) = &metrics.last_upload {
+        println!("Last upload:  {}", last.format("%Y-%m-%d %H:%M:%S UTC"));
+    }
+    if let Some(since) = &metrics.since {
+        println!("Since:        {}", since.format("%Y-%m-%d %H:%M:%S UTC"));
+    }
+    if let Ok(path) = UsageMetrics::metrics_path() {
+        println!("Stored in:    {}", path.display().to_string().dimmed());
+    }
+
+    if metrics.commands.is_empty() {
+        println!();
+        println!("{}", "No usage recorded".yellow());
+        println!();
+        return;
+    }
+
+    println!();
+    println!("{}", "Commands:".bold());
+    for (command, count) in &metrics.commands {
+        println!("  {:<14} {}", command, count);
+    }
+
+    if !metrics.errors.is_empty() {
+        println!();
+        println!("{}", "Errors:".bold());
+        for (category, count) in &metrics.errors {
+            println!("  {:<14} {}", category, count);
+        }
+    }
+    println!();
+}
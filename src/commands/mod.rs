@@ -1,11 +1,113 @@
+/// Decorations (banners, progress, hints): hidden by `--quiet`, and sent to
+/// stderr when stdout is reserved for `--output json`
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if !$crate::commands::output::quiet() {
+            if $crate::commands::output::json() {
+                eprintln!($($arg)*)
+            } else {
+                println!($($arg)*)
+            }
+        }
+    };
+}
+
+/// Notes that always go to stderr (the command's stdout is data), hidden by
+/// `--quiet`
+macro_rules! hint {
+    ($($arg:tt)*) => {
+        if !$crate::commands::output::quiet() {
+            eprintln!($($arg)*)
+        }
+    };
+}
+
+/// A command's human-readable results: shown even with `--quiet`, but
+/// replaced entirely by `--output json`
+macro_rules! show {
+    ($($arg:tt)*) => {
+        if !$crate::commands::output::json() {
+            println!($($arg)*)
+        }
+    };
+}
+
+pub mod agent;
+pub mod cancel;
+pub mod completions;
+pub mod config;
+pub mod delete;
 pub mod download;
+pub mod export;
+pub mod global;
+pub mod history;
 pub mod list;
 pub mod login;
+pub mod logout;
+pub mod logs;
+pub mod man;
+pub mod metrics;
+pub mod note;
+pub mod notify;
+mod offline;
+pub mod open;
+pub mod output;
+mod progress;
+pub mod prompt;
+pub mod report;
+mod resolve;
+pub mod resubmit;
 pub mod status;
 pub mod submit;
+pub mod sync;
+pub mod tag;
+pub mod tail;
+pub mod template;
+pub mod tools;
+#[cfg(feature = "tui")]
+pub mod ui;
+pub mod usage;
+pub mod watch;
+pub mod whoami;
+mod wizard;
 
+pub use agent::AgentCommand;
+pub use cancel::CancelCommand;
+pub use completions::CompletionsCommand;
+pub use config::ConfigCommand;
+pub use delete::DeleteCommand;
 pub use download::DownloadCommand;
+pub use export::ExportCommand;
+pub use global::{GlobalArgs, OutputFormat, ProgressFormat};
+pub use history::HistoryCommand;
 pub use list::ListCommand;
 pub use login::LoginCommand;
+pub use logout::LogoutCommand;
+pub use logs::LogsCommand;
+pub use man::ManCommand;
+pub use metrics::MetricsCommand;
+pub use note::NoteCommand;
+pub use notify::NotifyCommand;
+pub use open::OpenCommand;
+pub use prompt::PromptCommand;
+pub use report::ReportCommand;
+pub use resubmit::ResubmitCommand;
 pub use status::StatusCommand;
 pub use submit::SubmitCommand;
+pub use sync::SyncCommand;
+pub use tag::TagCommand;
+pub use tail::TailCommand;
+pub use template::TemplateCommand;
+pub use tools::ToolsCommand;
+#[cfg(feature = "tui")]
+pub use ui::UiCommand;
+pub use usage::UsageCommand;
+pub use watch::WatchCommand;
+pub use whoami::WhoamiCommand;
+
+/// Print `value` as pretty JSON on stdout
+pub(crate) fn print_json(value: &impl serde::Serialize) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(value)?;
+    println!("{}", json);
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use crate::cache::{job_id_from, JobCache, JobNote};
+use crate::commands::output::icon;
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use colored::Colorize;
+
+#[derive(Debug, Args)]
+pub struct NoteCommand {
+    #[command(subcommand)]
+    action: NoteAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum NoteAction {
+    #[command(about = "Attach a free-text note to a job")]
+    Add {
+        #[arg(help = "Job URL or Job ID")]
+        job: String,
+
+        #[arg(required = true, num_args = 1.., help = "Note text")]
+        text: Vec<String>,
+    },
+
+    #[command(about = "Show the notes attached to a job")]
+    Show {
+        #[arg(help = "Job URL or Job ID")]
+        job: String,
+    },
+
+    #[command(about = "Delete all notes attached to a job")]
+    Clear {
+        #[arg(help = "Job URL or Job ID")]
+        job: String,
+    },
+}
+
+impl NoteCommand {
+    pub fn execute(self) -> Result<()> {
+        let mut cache = JobCache::load()?;
+
+        match self.action {
+            NoteAction::Add { job, text } => {
+                let entry = cache.entry(job_id_from(&job));
+                entry.notes.push(JobNote {
+                    text: text.join(" "),
+                    added_at: chrono::Utc::now(),
+                });
+                println!(
+                    "{} Note added to {} ({} note(s))",
+                    icon("✓").green().bold(),
+                    entry.job_id.cyan(),
+                    entry.notes.len()
+                );
+                cache.save()?;
+            }
+            NoteAction::Show { job } => {
+                let job_id = job_id_from(&job);
+                match cache.get(job_id).filter(|j| !j.notes.is_empty()) {
+                    Some(entry) => {
+                        for note in &entry.notes {
+                            println!(
+                                "{} {}",
+                                note.added_at
+                                    .with_timezone(&chrono::Local)
+                                    .format("%Y-%m-%d %H:%M")
+                                    .to_string()
+                                    .dimmed(),
+                                note.text
+                            );
+                        }
+                    }
+                    None => println!("{}", format!("{} has no notes", job_id).yellow()),
+                }
+            }
+            NoteAction::Clear { job } => {
+                let entry = cache.entry(job_id_from(&job));
+                let removed = entry.notes.len();
+                entry.notes.clear();
+                println!(
+                    "{} Removed {} note(s) from {}",
+                    icon("✓").green().bold(),
+                    removed,
+                    entry.job_id.cyan()
+                );
+                cache.save()?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use crate::commands::output::icon;
+use crate::notify::{JobEvent, NotificationConfig};
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use colored::Colorize;
+
+#[derive(Debug, Args)]
+pub struct NotifyCommand {
+    #[command(subcommand)]
+    action: NotifyAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum NotifyAction {
+    #[command(about = "List configured notification channels")]
+    List,
+
+    #[command(about = "Send a test event through every configured channel")]
+    Test,
+}
+
+impl NotifyCommand {
+    pub fn execute(self) -> Result<()> {
+        let config = NotificationConfig::load()?;
+
+        if config.channels.is_empty() {
+            println!("{}", "No notification channels configured".yellow());
+            println!();
+            println!(
+                "Add channels to {}, e.g.:",
+                NotificationConfig::config_path()?
+                    .display()
+                    .to_string()
+                    .cyan()
+            );
+            println!();
+            println!(
+                r#"  {{ "channels": [ {{ "type": "slack", "url": "https://hooks.slack.com/services/..." }} ] }}"#
+            );
+            println!();
+            println!(
+                "Channel types: {}, {} (url) and {} (smtp_url, from, to)",
+                "webhook".bold(),
+                "slack".bold(),
+                "email".bold()
+            );
+            println!();
+            return Ok(());
+        }
+
+        match self.action {
+            NotifyAction::List => {
+                println!("{}", "Notification Channels".bold().cyan());
+                println!("{}", "=".repeat(60).cyan());
+                println!();
+                for channel in &config.channels {
+                    println!("  {:<10} {}", channel.kind(), channel.label());
+                }
+                println!();
+            }
+            NotifyAction::Test => {
+                println!("{} Sending test notification...", icon("→").cyan());
+                println!();
+
+                let event = JobEvent::test();
+                let mut failures = 0;
+
+                for channel in &config.channels {
+                    match channel.send(&event) {
+                        Ok(()) => println!(
+                            "  {} {:<10} {}",
+                            icon("✓").green().bold(),
+                            channel.kind(),
+                            channel.label()
+                        ),
+                        Err(e) => {
+                            failures += 1;
+                            println!(
+                                "  {} {:<10} {}",
+                                icon("✗").red().bold(),
+                                channel.kind(),
+                                channel.label()
+                            );
+                            println!("      {}", e.to_string().red());
+                        }
+                    }
+                }
+                println!();
+
+                if failures > 0 {
+                    anyhow::bail!(
+                        "{} of {} channel(s) failed to deliver",
+                        failures,
+                        config.channels.len()
+                    );
+                }
+
+                println!("{} All channels delivered", icon("✓").green().bold());
+                println!();
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+//! Falling back to the local job database when NSG can't be reached
+use crate::commands::output::icon;
+use crate::error::NsgError;
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+
+/// Whether `err` means NSG couldn't be reached at all (DNS, connection, TLS
+/// or timeout), as opposed to NSG answering with an error
+pub(crate) fn unreachable(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| matches!(cause.downcast_ref(), Some(NsgError::Network { .. })))
+}
+
+/// Say that `err` is why what follows comes from the local job database
+pub(crate) fn warn_unreachable(err: &anyhow::Error) {
+    hint!(
+        "{} NSG can't be reached ({}); showing what this machine last saw",
+        icon("⚠").yellow().bold(),
+        err
+    );
+}
+
+/// The "stale as of" marker for information last fetched at `at`
+pub(crate) fn stale_as_of(at: DateTime<Utc>) -> String {
+    format!(
+        "stale as of {}",
+        at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+    )
+}
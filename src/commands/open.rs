@@ -0,0 +1,59 @@
+use crate::cache::{self, JobCache};
+use crate::commands::output::icon;
+use crate::commands::{resolve, GlobalArgs};
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+
+#[derive(Debug, Args)]
+pub struct OpenCommand {
+    #[arg(help = "Job URL, Job ID or a unique part of one")]
+    job: String,
+
+    #[arg(long, help = "Print the job's URL instead of opening it in a browser")]
+    print: bool,
+}
+
+impl OpenCommand {
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let url = self.url(global)?;
+
+        if self.print {
+            println!("{}", url);
+            return Ok(());
+        }
+
+        say!("{} Opening {}", icon("→").cyan(), url.cyan());
+        open::that_detached(&url)
+            .with_context(|| format!("Could not open a browser for {}", url))?;
+        hint!(
+            "   If no browser opened, print the URL with {}",
+            format!("nsg open {} --print", self.job).cyan()
+        );
+
+        Ok(())
+    }
+
+    /// The job's REST URL: given, recorded in the local job database, or
+    /// else looked up on NSG
+    fn url(&self, global: &GlobalArgs) -> Result<String> {
+        if self.job.contains("://") {
+            return Ok(self.job.clone());
+        }
+
+        let known = JobCache::load()
+            .ok()
+            .and_then(|cache| cache.get(&self.job).and_then(|job| job.url.clone()));
+        if let Some(url) = known {
+            return Ok(url);
+        }
+
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+        let job = resolve::resolve_jobs(&client, &[&self.job])?.remove(0);
+        let status = client.get_job_status(&job)?;
+        cache::record_statuses([&status]);
+
+        Ok(status.self_uri)
+    }
+}
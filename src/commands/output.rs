@@ -0,0 +1,111 @@
+//! Process-wide output settings, chosen once from the global flags
+use crate::commands::{GlobalArgs, ProgressFormat};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static JSON: AtomicBool = AtomicBool::new(false);
+static PLAIN: AtomicBool = AtomicBool::new(false);
+static PROGRESS_JSON: AtomicBool = AtomicBool::new(false);
+
+/// Apply `--quiet`, `--output json`, `--progress` and `--no-color` for the rest of the
+/// process. Colors are also dropped when `NO_COLOR` is set, and both colors
+/// and unicode icons when stdout is not a terminal, so piping into files or
+/// grep gives clean text.
+pub fn configure(global: &GlobalArgs) {
+    QUIET.store(global.quiet, Ordering::Relaxed);
+    JSON.store(global.json_output(), Ordering::Relaxed);
+    PROGRESS_JSON.store(global.progress == ProgressFormat::Json, Ordering::Relaxed);
+
+    let plain = !std::io::stdout().is_terminal();
+    PLAIN.store(plain, Ordering::Relaxed);
+
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if global.no_color || no_color_env || plain {
+        colored::control::set_override(false);
+    }
+}
+
+/// Banners, progress and hints are suppressed
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Transfers report progress as JSON lines on stderr instead of bars
+pub fn progress_json() -> bool {
+    PROGRESS_JSON.load(Ordering::Relaxed)
+}
+
+/// Stdout is reserved for a JSON document, or other data meant for
+/// programs
+pub fn json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Reserve stdout for data other than JSON (e.g. `nsg list --format csv`)
+/// from here on: banners and progress go to stderr, as with `--output json`
+pub fn reserve_stdout() {
+    JSON.store(true, Ordering::Relaxed);
+}
+
+/// Columns of the terminal stdout goes to; `None` when it's piped, so long
+/// lines stay whole for grep and friends
+pub fn width() -> Option<usize> {
+    if PLAIN.load(Ordering::Relaxed) {
+        return None;
+    }
+    console::Term::stdout()
+        .size_checked()
+        .map(|(_, columns)| usize::from(columns))
+}
+
+/// `text` broken at spaces into lines of at most `width` characters. Its
+/// own line breaks are kept, and a word longer than `width` (a path, say)
+/// gets a line of its own rather than being cut.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let needed = line.chars().count() + 1 + word.chars().count();
+            if !line.is_empty() && needed > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// `text` on one line, cut to at most `max_chars` characters
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= max_chars {
+        return line;
+    }
+    let cut: String = line.chars().take(max_chars.saturating_sub(3)).collect();
+    format!("{}...", cut)
+}
+
+/// `glyph` as given on a terminal, or an ASCII stand-in when output is piped
+pub fn icon(glyph: &'static str) -> &'static str {
+    if !PLAIN.load(Ordering::Relaxed) {
+        return glyph;
+    }
+
+    match glyph {
+        "→" => "->",
+        "✓" => "[ok]",
+        "✗" => "[x]",
+        "⚠" => "[!]",
+        "⟳" => "[~]",
+        "⏳" => "[..]",
+        other => other,
+    }
+}
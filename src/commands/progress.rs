@@ -0,0 +1,238 @@
+//! `--progress json`: transfer progress as newline-delimited JSON events on
+//! stderr, in place of progress bars, for GUIs and pipelines wrapping nsg
+use crate::client::{DownloadEvent, UploadEvent};
+use crate::commands::output;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Least time between two `progress` events for the same file
+const INTERVAL: Duration = Duration::from_millis(250);
+
+/// Why a file without a size from NSG deserves a warning
+pub(crate) const UNKNOWN_SIZE: &str =
+    "NSG didn't report the size, so it can't be checked for completeness";
+
+/// One line of the stream. `speed` is the average in bytes per second since
+/// the file started (counting bytes resumed from an earlier attempt);
+/// `total` is null when NSG didn't say how large it is.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    Start {
+        file: &'a str,
+        total: Option<u64>,
+    },
+    Progress {
+        file: &'a str,
+        bytes: u64,
+        total: Option<u64>,
+        speed: u64,
+    },
+    Done {
+        file: &'a str,
+        bytes: u64,
+        speed: u64,
+        up_to_date: bool,
+    },
+    Failed {
+        file: &'a str,
+        error: &'a str,
+    },
+    Warning {
+        file: &'a str,
+        message: &'a str,
+    },
+}
+
+fn emit(event: &Event) {
+    if let Ok(line) = serde_json::to_string(event) {
+        eprintln!("{}", line);
+    }
+}
+
+/// One file in flight
+struct Transfer {
+    total: Option<u64>,
+    started: Instant,
+    reported: Option<Instant>,
+}
+
+impl Transfer {
+    fn start(file: &str, total: Option<u64>) -> Self {
+        emit(&Event::Start { file, total });
+        Self {
+            total,
+            started: Instant::now(),
+            reported: None,
+        }
+    }
+
+    fn update(&mut self, file: &str, bytes: u64) {
+        if self.reported.is_some_and(|at| at.elapsed() < INTERVAL) {
+            return;
+        }
+        self.reported = Some(Instant::now());
+        emit(&Event::Progress {
+            file,
+            bytes,
+            total: self.total,
+            speed: self.speed(bytes),
+        });
+    }
+
+    fn finish(&self, file: &str, bytes: u64) {
+        emit(&Event::Done {
+            file,
+            bytes,
+            speed: self.speed(bytes),
+            up_to_date: false,
+        });
+    }
+
+    fn speed(&self, bytes: u64) -> u64 {
+        let secs = self.started.elapsed().as_secs_f64().max(0.001);
+        (bytes as f64 / secs) as u64
+    }
+}
+
+/// Turns the events of a download into the JSON stream
+#[derive(Default)]
+pub(crate) struct DownloadProgress {
+    transfers: HashMap<String, Transfer>,
+}
+
+impl DownloadProgress {
+    pub(crate) fn on_event(&mut self, event: &DownloadEvent) {
+        match event {
+            DownloadEvent::FileStarted { filename, size } => {
+                if size.is_none() {
+                    emit(&Event::Warning {
+                        file: filename,
+                        message: UNKNOWN_SIZE,
+                    });
+                }
+                let transfer = Transfer::start(filename, *size);
+                self.transfers.insert(filename.clone(), transfer);
+            }
+            DownloadEvent::Progress {
+                filename,
+                downloaded,
+                ..
+            } => {
+                if let Some(transfer) = self.transfers.get_mut(filename) {
+                    transfer.update(filename, *downloaded);
+                }
+            }
+            DownloadEvent::FileFinished { file } => {
+                match self.transfers.remove(&file.remote_filename) {
+                    Some(transfer) => transfer.finish(&file.remote_filename, file.size),
+                    None => emit(&Event::Done {
+                        file: &file.remote_filename,
+                        bytes: file.size,
+                        speed: 0,
+                        up_to_date: file.up_to_date,
+                    }),
+                }
+            }
+            DownloadEvent::FileFailed { filename, error } => {
+                self.transfers.remove(filename);
+                emit(&Event::Failed {
+                    file: filename,
+                    error,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Turns the events of a submission's upload into the JSON stream. Shared
+/// with the client's upload callback, so the files still in flight can be
+/// reported failed when the submission is.
+#[derive(Default)]
+pub(crate) struct UploadProgress {
+    transfers: Mutex<HashMap<String, Transfer>>,
+}
+
+impl UploadProgress {
+    pub(crate) fn on_event(&self, event: &UploadEvent) {
+        let Ok(mut transfers) = self.transfers.lock() else {
+            return;
+        };
+        match event {
+            UploadEvent::FileStarted { filename, size } => {
+                let transfer = Transfer::start(filename, Some(*size));
+                transfers.insert(filename.clone(), transfer);
+            }
+            UploadEvent::Progress {
+                filename, uploaded, ..
+            } => {
+                if let Some(transfer) = transfers.get_mut(filename) {
+                    transfer.update(filename, *uploaded);
+                }
+            }
+            UploadEvent::FileFinished { filename, size } => {
+                if let Some(transfer) = transfers.remove(filename) {
+                    transfer.finish(filename, *size);
+                }
+            }
+        }
+    }
+
+    /// Report every file not yet sent as failed with `error`
+    pub(crate) fn fail(&self, error: &str) {
+        let Ok(mut transfers) = self.transfers.lock() else {
+            return;
+        };
+        for (file, _) in transfers.drain() {
+            emit(&Event::Failed { file: &file, error });
+        }
+    }
+}
+
+/// A remote file read as a stream (e.g. an archive extracted on the fly),
+/// reported like a download with `--progress json`
+pub(crate) struct Tracked<R> {
+    inner: R,
+    file: String,
+    bytes: u64,
+    transfer: Option<Transfer>,
+}
+
+pub(crate) fn track<R: Read>(file: &str, total: Option<u64>, inner: R) -> Tracked<R> {
+    Tracked {
+        inner,
+        file: file.to_string(),
+        bytes: 0,
+        transfer: output::progress_json().then(|| Transfer::start(file, total)),
+    }
+}
+
+impl<R: Read> Read for Tracked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let result = self.inner.read(buf);
+        if let Some(transfer) = &mut self.transfer {
+            match &result {
+                Ok(0) => {
+                    transfer.finish(&self.file, self.bytes);
+                    self.transfer = None;
+                }
+                Ok(n) => {
+                    self.bytes += *n as u64;
+                    transfer.update(&self.file, self.bytes);
+                }
+                Err(e) => {
+                    emit(&Event::Failed {
+                        file: &self.file,
+                        error: &e.to_string(),
+                    });
+                    self.transfer = None;
+                }
+            }
+        }
+        result
+    }
+}
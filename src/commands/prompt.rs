@@ -0,0 +1,68 @@
+use crate::cache::JobCache;
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct PromptCommand {
+    #[arg(
+        long,
+        help = "Template with {running}, {queued}, {failed} and {active} placeholders"
+    )]
+    format: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "24",
+        value_name = "HOURS",
+        help = "Count failures seen within this many hours"
+    )]
+    failed_within: i64,
+}
+
+impl PromptCommand {
+    /// Print a terse job summary for shell prompts, e.g. `nsg:2run/1q/1fail`.
+    ///
+    /// Reads only the local job cache - no network, no credentials - so it
+    /// is cheap enough to run on every prompt. Prints nothing when there is
+    /// nothing to report.
+    pub fn execute(self) -> Result<()> {
+        let cache = JobCache::load().unwrap_or_default();
+        let failed_since = Utc::now() - Duration::hours(self.failed_within);
+
+        let running = cache.jobs.values().filter(|j| j.is_running()).count();
+        let queued = cache.jobs.values().filter(|j| j.is_queued()).count();
+        let failed = cache
+            .jobs
+            .values()
+            .filter(|j| j.failed && j.updated_at >= failed_since)
+            .count();
+
+        let snippet = match &self.format {
+            Some(format) => format
+                .replace("{running}", &running.to_string())
+                .replace("{queued}", &queued.to_string())
+                .replace("{failed}", &failed.to_string())
+                .replace("{active}", &(running + queued).to_string()),
+            None => {
+                let parts: Vec<String> = [(running, "run"), (queued, "q"), (failed, "fail")]
+                    .iter()
+                    .filter(|(count, _)| *count > 0)
+                    .map(|(count, label)| format!("{}{}", count, label))
+                    .collect();
+
+                if parts.is_empty() {
+                    String::new()
+                } else {
+                    format!("nsg:{}", parts.join("/"))
+                }
+            }
+        };
+
+        if !snippet.is_empty() {
+            println!("{}", snippet);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use crate::cache;
+use crate::commands::output::icon;
+use crate::commands::GlobalArgs;
+use crate::models::{tool_from_job_id, JobStage, JobStatus, OutputFile};
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+const LOG_PREVIEW_BYTES: usize = 64 * 1024;
+const LOG_FILES: [&str; 2] = ["stdout.txt", "stderr.txt"];
+
+#[derive(Debug, Args)]
+pub struct ReportCommand {
+    #[arg(help = "Job URL or Job ID")]
+    job: String,
+
+    #[arg(short, long, help = "Output HTML file (default: <JOB_ID>_report.html)")]
+    output: Option<PathBuf>,
+
+    #[arg(long, help = "Don't inline stdout/stderr previews")]
+    no_logs: bool,
+}
+
+struct LogPreview {
+    filename: String,
+    contents: String,
+    truncated: bool,
+}
+
+impl ReportCommand {
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+
+        say!("{}", "NSG Job Report".bold().cyan());
+        say!("{}", "=".repeat(80).cyan());
+        say!();
+        say!("{} Fetching job status...", icon("→").cyan());
+        say!("   Job: {}", self.job.bold());
+
+        let status = client.get_job_status(&self.job)?;
+        cache::record_statuses([&status]);
+
+        let files = if status.results_uri.is_some() {
+            say!("{} Fetching output file list...", icon("→").cyan());
+            match client.get_output_files(&status) {
+                Ok(files) => files,
+                Err(e) => {
+                    eprintln!("{} Could not list output files: {}", icon("⚠").yellow(), e);
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        let mut logs = Vec::new();
+        if !self.no_logs {
+            for file in files
+                .iter()
+                .filter(|f| LOG_FILES.contains(&f.filename.as_str()))
+            {
+                say!("{} Fetching {}...", icon("→").cyan(), file.filename);
+                match client.read_output_file_tail(file, LOG_PREVIEW_BYTES) {
+                    Ok(bytes) => logs.push(LogPreview {
+                        filename: file.filename.clone(),
+                        contents: String::from_utf8_lossy(&bytes).into_owned(),
+                        truncated: file.size.map_or(bytes.len() >= LOG_PREVIEW_BYTES, |size| {
+                            size > LOG_PREVIEW_BYTES as u64
+                        }),
+                    }),
+                    Err(e) => {
+                        eprintln!(
+                            "{} Could not fetch {}: {}",
+                            icon("⚠").yellow(),
+                            file.filename,
+                            e
+                        )
+                    }
+                }
+            }
+        }
+
+        let html = render_report(&status, &files, &logs);
+
+        let output = self
+            .output
+            .unwrap_or_else(|| PathBuf::from(format!("{}_report.html", status.job_id)));
+
+        std::fs::write(&output, html)
+            .with_context(|| format!("Failed to write report to {}", output.display()))?;
+
+        say!();
+        say!(
+            "{} Report written to {}",
+            icon("✓").green().bold(),
+            output.display().to_string().cyan()
+        );
+        say!();
+
+        Ok(())
+    }
+}
+
+fn render_report(status: &JobStatus, files: &[OutputFile], logs: &[LogPreview]) -> String {
+    let mut html = String::new();
+
+    let _ = write!(
+        html,
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>NSG job report: {id}</title>
+<style>
+body {{ font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2em auto; max-width: 1000px; color: #222; }}
+h1 {{ font-size: 1.5em; word-break: break-all; }}
+h2 {{ font-size: 1.15em; border-bottom: 1px solid #ddd; padding-bottom: 0.2em; margin-top: 1.8em; }}
+table {{ border-collapse: collapse; width: 100%; }}
+th, td {{ text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #eee; vertical-align: top; }}
+th {{ background: #f6f6f6; }}
+td.num {{ text-align: right; font-variant-numeric: tabular-nums; }}
+pre {{ background: #f6f8fa; padding: 0.8em; overflow-x: auto; font-size: 0.85em; white-space: pre-wrap; }}
+.stage {{ font-weight: bold; }}
+.failed {{ color: #b00020; font-weight: bold; }}
+.completed {{ color: #1b7f3b; }}
+.muted {{ color: #777; font-size: 0.9em; }}
+</style>
+</head>
+<body>
+<h1>NSG job report: {id}</h1>
+<p class="muted">Generated {generated} by nsg-cli {version}</p>
+"#,
+        id = escape(&status.job_id),
+        generated = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        version = env!("CARGO_PKG_VERSION"),
+    );
+
+    html.push_str("<h2>Status</h2>\n<table>\n");
+    row(&mut html, "Job ID", &escape(&status.job_id));
+    if let Some(tool) = tool_from_job_id(&status.job_id) {
+        row(&mut html, "Tool", &escape(tool));
+    }
+    let stage_class = if status.job_stage == JobStage::Completed && !status.failed {
+        "stage completed"
+    } else {
+        "stage"
+    };
+    row(
+        &mut html,
+        "Stage",
+        &format!(
+            r#"<span class="{}">{}</span>"#,
+            stage_class,
+            escape(status.job_stage.as_str())
+        ),
+    );
+    if status.failed {
+        row(&mut html, "Failed", r#"<span class="failed">YES</span>"#);
+    }
+    if let Some(date) = &status.date_submitted {
+        row(&mut html, "Submitted", &escape(&format_timestamp(date)));
+    }
+    row(&mut html, "URL", &escape(&status.self_uri));
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Parameters</h2>\n");
+    if status.metadata.is_empty() {
+        html.push_str("<p class=\"muted\">No submission metadata reported by NSG.</p>\n");
+    } else {
+        html.push_str("<table>\n<tr><th>Key</th><th>Value</th></tr>\n");
+        for (key, value) in &status.metadata {
+            row(&mut html, &escape(key), &escape(value));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h2>Message timeline</h2>\n");
+    if status.messages.is_empty() {
+        html.push_str("<p class=\"muted\">No messages.</p>\n");
+    } else {
+        html.push_str("<table>\n<tr><th>Time</th><th>Stage</th><th>Message</th></tr>\n");
+        for msg in &status.messages {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(
+                    &msg.timestamp
+                        .as_deref()
+                        .map(format_timestamp)
+                        .unwrap_or_default()
+                ),
+                escape(&msg.stage),
+                escape(&msg.text)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h2>Output files</h2>\n");
+    if files.is_empty() {
+        html.push_str("<p class=\"muted\">No output files available.</p>\n");
+    } else {
+        let total: u64 = files.iter().filter_map(|f| f.size).sum();
+        html.push_str("<table>\n<tr><th>File</th><th>Size</th><th>Bytes</th></tr>\n");
+        for file in files {
+            let _ = writeln!(
+                html,
+                r#"<tr><td>{}</td><td class="num">{}</td><td class="num">{}</td></tr>"#,
+                escape(&file.filename),
+                file.size.map_or_else(|| "unknown".to_string(), format_size),
+                file.size.map(|size| size.to_string()).unwrap_or_default()
+            );
+        }
+        let _ = writeln!(
+            html,
+            r#"<tr><th>{} file(s)</th><th class="num">{}</th><th class="num">{}</th></tr>"#,
+            files.len(),
+            format_size(total),
+            total
+        );
+        html.push_str("</table>\n");
+    }
+
+    for log in logs {
+        let _ = writeln!(html, "<h2>{}</h2>", escape(&log.filename));
+        if log.truncated {
+            let _ = writeln!(
+                html,
+                "<p class=\"muted\">Showing the last {} only.</p>",
+                format_size(LOG_PREVIEW_BYTES as u64)
+            );
+        }
+        if log.contents.trim().is_empty() {
+            html.push_str("<p class=\"muted\">(empty)</p>\n");
+        } else {
+            let _ = writeln!(html, "<pre>{}</pre>", escape(&log.contents));
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn row(html: &mut String, label: &str, value: &str) {
+    let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", label, value);
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_timestamp(ts: &str) -> String {
+    use chrono::{DateTime, Utc};
+    if let Ok(dt) = ts.parse::<DateTime<Utc>>() {
+        dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+    } else {
+        ts.to_string()
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+
+    if bytes >= GB {
+        format!("{:.2} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.2} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
@@ -0,0 +1,213 @@
+//! Turning what the user typed into the job it means
+use crate::api::NsgApi;
+use crate::cache::{self, JobCache};
+use crate::commands::output::icon;
+use crate::commands::status::stage_label;
+use crate::models::{JobStage, JobStatus};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use colored::Colorize;
+use dialoguer::FuzzySelect;
+use std::io::IsTerminal;
+
+/// Jobs offered by [`pick_job`]
+const PICKER_JOBS: usize = 50;
+
+/// Every one of the user's jobs, most recently submitted first. NSG doesn't
+/// promise any order for the job list, so every job's status is fetched for
+/// its submission date.
+fn newest_first(client: &dyn NsgApi) -> Result<Vec<JobStatus>> {
+    let jobs = client.list_jobs()?;
+    let urls: Vec<&str> = jobs.iter().map(|job| job.url.as_str()).collect();
+    let mut statuses: Vec<JobStatus> = client
+        .get_job_statuses(&urls)
+        .into_iter()
+        .collect::<crate::error::Result<_>>()?;
+    cache::record_statuses(statuses.iter());
+
+    // Stable, so of jobs submitted at the same time the one NSG lists last
+    // comes first
+    statuses.reverse();
+    statuses.sort_by_key(|status| std::cmp::Reverse(submitted(status)));
+    Ok(statuses)
+}
+
+fn submitted(status: &JobStatus) -> Option<DateTime<Utc>> {
+    status
+        .date_submitted
+        .as_deref()
+        .and_then(|date| date.parse().ok())
+}
+
+/// The user's most recently submitted job, or with `completed` the most
+/// recent one that completed without failing
+pub(crate) fn latest_job(client: &dyn NsgApi, completed: bool) -> Result<JobStatus> {
+    let latest = newest_first(client)?
+        .into_iter()
+        .find(|status| !completed || (status.job_stage == JobStage::Completed && !status.failed));
+
+    match latest {
+        Some(status) => {
+            say!(
+                "{} Latest{} job: {}",
+                icon("→").cyan(),
+                if completed { " completed" } else { "" },
+                status.job_id.cyan().bold()
+            );
+            Ok(status)
+        }
+        None if completed => anyhow::bail!("None of your jobs has completed successfully"),
+        None => anyhow::bail!("You have no jobs"),
+    }
+}
+
+/// Let the user choose one of their recent jobs from a searchable list, for
+/// commands run without a job. `usage` (e.g. `nsg status <JOB>`) is given
+/// in the error when there's no terminal to ask on.
+pub(crate) fn pick_job(client: &dyn NsgApi, usage: &str) -> Result<String> {
+    if !(std::io::stdin().is_terminal() && std::io::stderr().is_terminal()) {
+        anyhow::bail!("No job given (usage: {})", usage);
+    }
+
+    hint!("{} Fetching your jobs...", icon("→").cyan());
+    let mut statuses = newest_first(client)?;
+    statuses.truncate(PICKER_JOBS);
+    if statuses.is_empty() {
+        anyhow::bail!("You have no jobs");
+    }
+
+    let id_width = statuses.iter().map(|s| s.job_id.len()).max().unwrap_or(0);
+    let labels: Vec<String> = statuses
+        .iter()
+        .map(|status| stage_label(status.job_stage.as_str(), status.failed))
+        .collect();
+    let stage_width = labels.iter().map(String::len).max().unwrap_or(0);
+    let items: Vec<String> = statuses
+        .iter()
+        .zip(&labels)
+        .map(|(status, label)| {
+            let date = submitted(status)
+                .map(|date| {
+                    date.with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            format!(
+                "{:<id_width$}  {:<stage_width$}  {}",
+                status.job_id, label, date
+            )
+        })
+        .collect();
+
+    let choice = FuzzySelect::new()
+        .with_prompt("Job (type to search, Esc to cancel)")
+        .items(&items)
+        .default(0)
+        .max_length(15)
+        .interact_opt()
+        .context("Failed to read the choice")?;
+    match choice {
+        Some(index) => Ok(statuses.swap_remove(index).job_id),
+        None => anyhow::bail!("No job chosen"),
+    }
+}
+
+/// Most candidates listed when a partial job ID is ambiguous
+const MAX_CANDIDATES: usize = 10;
+
+/// The full job IDs (or URLs) `jobs` refer to. Besides a URL or job ID,
+/// each may be a unique prefix or substring of one of the user's job IDs,
+/// ignoring case: `4c1d` for `NGBW-JOB-PY_EXPANSE-4C1D...`. IDs in the
+/// local job database are taken as they are; otherwise the job list is
+/// fetched from NSG, once.
+pub(crate) fn resolve_jobs<S: AsRef<str>>(client: &dyn NsgApi, jobs: &[S]) -> Result<Vec<String>> {
+    let cache = JobCache::load().unwrap_or_default();
+    let mut listed: Option<Vec<String>> = None;
+
+    let mut resolved = Vec::with_capacity(jobs.len());
+    for job in jobs {
+        let job = job.as_ref();
+        if job.contains('/') || cache.get(job).is_some() {
+            resolved.push(job.to_string());
+            continue;
+        }
+
+        let ids = match &listed {
+            Some(ids) => ids,
+            None => listed.insert(
+                client
+                    .list_jobs()?
+                    .into_iter()
+                    .map(|summary| summary.job_id)
+                    .collect(),
+            ),
+        };
+        let id = match_job(ids, job)?;
+        if id != job {
+            hint!("{} {} is {}", icon("→").cyan(), job, id.cyan());
+        }
+        resolved.push(id);
+    }
+
+    Ok(resolved)
+}
+
+/// [`resolve_jobs`] without NSG: the IDs of the jobs in the local job
+/// database that `jobs` refer to
+pub(crate) fn resolve_cached<S: AsRef<str>>(cache: &JobCache, jobs: &[S]) -> Result<Vec<String>> {
+    let ids: Vec<String> = cache.jobs.keys().cloned().collect();
+
+    jobs.iter()
+        .map(|job| {
+            let job = cache::job_id_from(job.as_ref());
+            let id = match_job(&ids, job)?;
+            if cache.get(&id).is_none() {
+                anyhow::bail!("{} is not in the local job database", job);
+            }
+            Ok(id)
+        })
+        .collect()
+}
+
+/// The one of `ids` that `job` names exactly, starts or else appears in
+fn match_job(ids: &[String], job: &str) -> Result<String> {
+    let wanted = job.to_ascii_uppercase();
+    if let Some(id) = ids.iter().find(|id| id.eq_ignore_ascii_case(job)) {
+        return Ok(id.clone());
+    }
+
+    let mut matches: Vec<&String> = ids
+        .iter()
+        .filter(|id| id.to_ascii_uppercase().starts_with(&wanted))
+        .collect();
+    if matches.is_empty() {
+        matches = ids
+            .iter()
+            .filter(|id| id.to_ascii_uppercase().contains(&wanted))
+            .collect();
+    }
+
+    match matches.as_slice() {
+        [id] => Ok(id.to_string()),
+        // Left for NSG to report as not found, with its usual error
+        [] if wanted.starts_with("NGBW-JOB-") => Ok(job.to_string()),
+        [] => anyhow::bail!("None of your jobs matches '{}' (see `nsg list`)", job),
+        matches => {
+            let mut shown: Vec<&str> = matches
+                .iter()
+                .take(MAX_CANDIDATES)
+                .map(|id| id.as_str())
+                .collect();
+            if matches.len() > MAX_CANDIDATES {
+                shown.push("...");
+            }
+            anyhow::bail!(
+                "'{}' matches {} jobs; give more of the ID:\n  {}",
+                job,
+                matches.len(),
+                shown.join("\n  ")
+            )
+        }
+    }
+}
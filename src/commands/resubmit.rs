@@ -0,0 +1,168 @@
+use crate::cache::{job_id_from, JobCache};
+use crate::commands::output::icon;
+use crate::commands::submit::{inputs_sha256, parse_input, parse_param};
+use crate::commands::{GlobalArgs, SubmitCommand};
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct ResubmitCommand {
+    #[arg(help = "Job URL or Job ID of a job submitted from this machine")]
+    job: String,
+
+    #[arg(
+        long = "param",
+        value_name = "KEY=VALUE",
+        value_parser = parse_param,
+        help = "Change or add a tool parameter (repeatable), e.g. --param runtime_=8"
+    )]
+    params: Vec<(String, String)>,
+
+    #[arg(
+        long = "input",
+        value_name = "NAME=PATH",
+        value_parser = parse_input,
+        help = "Send a different file as input.NAME (repeatable), e.g. --input infile_=fixed.zip"
+    )]
+    inputs: Vec<(String, PathBuf)>,
+
+    #[arg(
+        long,
+        help = "Wait until the job finishes; exits non-zero if it failed"
+    )]
+    wait: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        default_missing_value = "./nsg_results",
+        help = "Wait for the job, then download its results [default DIR: ./nsg_results, or default_output_dir]"
+    )]
+    download: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value = "30",
+        value_name = "SECONDS",
+        help = "Seconds between status checks with --wait/--download"
+    )]
+    interval: u64,
+
+    #[arg(
+        long,
+        help = "Show a desktop notification when the job finishes (with --wait/--download)"
+    )]
+    notify: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "notify",
+        help = "Don't show a desktop notification, even if config.toml says to"
+    )]
+    no_notify: bool,
+
+    #[arg(
+        long,
+        help = "Skip checking --param values against the tool's description"
+    )]
+    no_lint: bool,
+
+    #[arg(
+        long,
+        help = "Skip checking the input archive's layout and size before uploading"
+    )]
+    no_validate: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["wait", "download"],
+        help = "Check the input and parameters and print the request instead of submitting; NSG is not contacted"
+    )]
+    dry_run: bool,
+}
+
+impl ResubmitCommand {
+    /// Submit a past job again with the tool, inputs and parameters recorded
+    /// in the local job database, after applying any overrides
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let job_id = job_id_from(&self.job);
+        let cache = JobCache::load()?;
+        let job = cache.get(job_id).with_context(|| {
+            format!(
+                "{} is not in the local job database; only jobs submitted with nsg can be resubmitted",
+                job_id
+            )
+        })?;
+        let tool = job
+            .tool
+            .clone()
+            .with_context(|| format!("The tool {} ran is not known", job_id))?;
+
+        if job.inputs.is_empty() && self.inputs.is_empty() {
+            match &job.input_file {
+                Some(name) => anyhow::bail!(
+                    "Where {} ({}) was submitted from wasn't recorded; give it with --input infile_=PATH",
+                    job_id,
+                    name
+                ),
+                None => anyhow::bail!(
+                    "{} wasn't submitted from this machine; give its input with --input infile_=PATH",
+                    job_id
+                ),
+            }
+        }
+
+        let mut inputs = job.inputs.clone();
+        let changed_inputs = !self.inputs.is_empty();
+        inputs.extend(self.inputs);
+        for (name, path) in &inputs {
+            if !path.exists() {
+                anyhow::bail!(
+                    "Input {} of {} no longer exists: {} (use --input {}=PATH)",
+                    name,
+                    job_id,
+                    path.display(),
+                    name
+                );
+            }
+        }
+        let inputs: Vec<(String, PathBuf)> = inputs.into_iter().collect();
+
+        // Archives edited since are sent as they are now, which may be the
+        // point of resubmitting, but shouldn't go unnoticed
+        if !changed_inputs && inputs.iter().all(|(_, path)| path.is_file()) {
+            if let (Some(recorded), Ok(current)) = (&job.input_sha256, inputs_sha256(&inputs)) {
+                if *recorded != current {
+                    eprintln!(
+                        "{} The input has changed since {} was submitted",
+                        icon("⚠").yellow().bold(),
+                        job_id.cyan()
+                    );
+                }
+            }
+        }
+
+        let mut params = job.params.clone();
+        params.extend(self.params);
+
+        say!(
+            "{} Resubmitting {} ({})",
+            icon("→").cyan(),
+            job_id.cyan().bold(),
+            tool
+        );
+
+        SubmitCommand::resubmit(job_id, tool, inputs, params.into_iter().collect())
+            .waiting(
+                self.wait,
+                self.download,
+                self.interval,
+                self.notify && !self.no_notify,
+            )
+            .checking(self.dry_run, self.no_lint, self.no_validate)
+            .execute(global)
+    }
+}
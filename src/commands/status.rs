@@ -1,102 +1,403 @@
-use crate::client::NsgClient;
-use crate::config::Credentials;
+use crate::api::NsgApi;
+use crate::cache::{self, CachedJob, JobCache};
+use crate::commands::history::parse_since;
+use crate::commands::output::{self, icon};
+use crate::commands::{offline, print_json, resolve, GlobalArgs};
+use crate::error::NsgError;
+use crate::models::{JobMessage, JobStage, JobStatus};
 use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
 use clap::Args;
 use colored::Colorize;
 
 #[derive(Debug, Args)]
 pub struct StatusCommand {
-    #[arg(help = "Job URL or Job ID")]
-    job: String,
+    #[arg(
+        help = "Job URL, Job ID or a unique part of one; several are fetched in parallel and shown as a table [default: choose from a list]"
+    )]
+    jobs: Vec<String>,
+
+    #[arg(short, long, help = "Show every job message instead of the last 5")]
+    all_messages: bool,
+
+    #[arg(
+        long,
+        value_name = "WHEN",
+        value_parser = parse_since,
+        help = "Only show messages since a date or time (2024-05-01, RFC 3339) or within a duration (2h, 1d)"
+    )]
+    since: Option<DateTime<Utc>>,
 }
 
+/// Messages shown without `--all-messages` or `--since`
+const RECENT_MESSAGES: usize = 5;
+
 impl StatusCommand {
-    pub fn execute(self) -> Result<()> {
-        let credentials = Credentials::load()?;
-        let client = NsgClient::new(credentials)?;
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let json = global.json_output();
+        if global.offline {
+            return self.show_cached(json);
+        }
+
+        match self.show_live(global) {
+            Err(e) if offline::unreachable(&e) && !self.jobs.is_empty() => {
+                offline::warn_unreachable(&e);
+                self.show_cached(json)
+            }
+            result => result,
+        }
+    }
+
+    fn show_live(&self, global: &GlobalArgs) -> Result<()> {
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+        let jobs = if self.jobs.is_empty() {
+            vec![resolve::pick_job(&client, "nsg status <JOB>...")?]
+        } else {
+            resolve::resolve_jobs(&client, &self.jobs)?
+        };
 
-        println!("{}", "NSG Job Status".bold().cyan());
-        println!("{}", "=".repeat(80).cyan());
-        println!();
-        println!("{} Checking job status...", "→".cyan());
-        println!("   Job: {}", self.job.bold());
-        println!();
+        match jobs.as_slice() {
+            [job] => self.show_job(&client, job, global.json_output()),
+            jobs => compare_jobs(&client, jobs, global.json_output()),
+        }
+    }
 
-        let status = client.get_job_status(&self.job)?;
+    /// Everything about a single job
+    fn show_job(&self, client: &dyn NsgApi, job: &str, json: bool) -> Result<()> {
+        say!("{}", "NSG Job Status".bold().cyan());
+        say!("{}", "=".repeat(80).cyan());
+        say!();
+        say!("{} Checking job status...", icon("→").cyan());
+        say!("   Job: {}", job.bold());
+        say!();
 
-        println!("{} Job found", "✓".green().bold());
-        println!();
-        println!("{}", "Job Status Information".bold());
-        println!("{}", "=".repeat(80));
-        println!();
-        println!("Job ID:       {}", status.job_id.cyan());
+        let status = client.get_job_status(job)?;
+        cache::record_statuses([&status]);
 
-        let stage_icon = get_stage_icon(&status.job_stage);
-        println!("Stage:        {} {}", stage_icon, status.job_stage.bold());
+        say!("{} Job found", icon("✓").green().bold());
+        say!();
+        say!("{}", "Job Status Information".bold());
+        say!("{}", "=".repeat(80));
+        say!();
+        show!("Job ID:       {}", status.job_id.cyan());
+
+        let stage_icon = icon(status.job_stage.icon());
+        show!(
+            "Stage:        {} {}",
+            stage_icon,
+            status.job_stage.as_str().bold()
+        );
 
         if status.failed {
-            println!("Failed:       {} YES", "✗".red().bold());
+            show!("Failed:       {} YES", icon("✗").red().bold());
         }
 
         if let Some(date) = &status.date_submitted {
-            println!("Submitted:    {}", format_timestamp(date));
+            show!("Submitted:    {}", format_timestamp(date));
+        }
+
+        for (key, label) in [
+            ("clientJobName", "Job name:"),
+            ("clientJobId", "Client ID:"),
+        ] {
+            if let Some((_, value)) = status.metadata.iter().find(|(k, _)| k == key) {
+                show!("{:<14}{}", label, value.cyan());
+            }
         }
 
         if status.results_uri.is_some() {
-            println!("Results:      {} Available", "✓".green());
+            show!("Results:      {} Available", icon("✓").green());
         } else {
-            println!("Results:      {} Not yet available", "⏳".yellow());
+            show!("Results:      {} Not yet available", icon("⏳").yellow());
         }
 
-        if !status.messages.is_empty() {
-            println!();
-            println!("{}", "Recent Messages:".bold());
-            let recent = if status.messages.len() > 5 {
-                &status.messages[status.messages.len() - 5..]
+        self.print_messages(&status.messages);
+
+        say!();
+        say!("{}", "=".repeat(80));
+        say!();
+
+        print_next_action(&status.job_stage, job);
+
+        if json {
+            print_json(&status)?;
+        }
+
+        Ok(())
+    }
+
+    /// What the local job database last saw of each job, for `--offline`
+    /// or when NSG can't be reached
+    fn show_cached(&self, json: bool) -> Result<()> {
+        if self.jobs.is_empty() {
+            anyhow::bail!("No job given (usage: nsg status <JOB>...); offline there is no job list to choose from");
+        }
+        let cache = JobCache::load()?;
+        let ids = resolve::resolve_cached(&cache, &self.jobs)?;
+        let jobs: Vec<&CachedJob> = ids.iter().filter_map(|id| cache.get(id)).collect();
+
+        say!("{}", "NSG Job Status (offline)".bold().cyan());
+        say!("{}", "=".repeat(80).cyan());
+
+        for job in &jobs {
+            show!();
+            show!("Job ID:       {}", job.job_id.cyan());
+            if job.stage.is_empty() {
+                show!("Stage:        {}", "unknown".dimmed());
             } else {
-                &status.messages[..]
-            };
-
-            for msg in recent {
-                println!();
-                println!(
-                    "  [{}] {}",
-                    msg.stage.cyan(),
-                    msg.timestamp.as_deref().unwrap_or("")
+                show!(
+                    "Stage:        {} {}",
+                    stage_label(&job.stage, job.failed).bold(),
+                    format!("({})", offline::stale_as_of(job.updated_at)).dimmed()
                 );
-                if !msg.text.is_empty() {
-                    let text = if msg.text.len() > 200 {
-                        format!("{}...", &msg.text[..200])
-                    } else {
-                        msg.text.clone()
-                    };
-                    println!("    {}", text);
+            }
+            if job.failed {
+                show!("Failed:       {} YES", icon("✗").red().bold());
+            }
+            if let Some(submitted) = job.submitted() {
+                show!(
+                    "Submitted:    {}",
+                    submitted.format("%Y-%m-%d %H:%M:%S UTC")
+                );
+            }
+            if let Some(tool) = &job.tool {
+                show!("Tool:         {}", tool);
+            }
+            if let Some(dir) = &job.download_dir {
+                show!("Results:      downloaded to {}", dir.display());
+            }
+        }
+
+        say!();
+        say!(
+            "{}",
+            "Last seen on this machine; run nsg status again once NSG can be reached".dimmed()
+        );
+        say!();
+
+        match jobs.as_slice() {
+            [job] if json => print_json(job),
+            jobs if json => print_json(&jobs),
+            _ => Ok(()),
+        }
+    }
+
+    /// The job's messages in full, wrapped to the terminal: the last few, all
+    /// of them, or those since `--since`
+    fn print_messages(&self, messages: &[JobMessage]) {
+        if messages.is_empty() {
+            return;
+        }
+
+        let (shown, heading): (Vec<&JobMessage>, String) = if let Some(since) = self.since {
+            let shown = messages
+                .iter()
+                .filter(|message| {
+                    message
+                        .timestamp
+                        .as_deref()
+                        .and_then(|at| at.parse::<DateTime<Utc>>().ok())
+                        .is_some_and(|at| at >= since)
+                })
+                .collect();
+            let since = since.with_timezone(&Local).format("%Y-%m-%d %H:%M");
+            (shown, format!("Messages since {}:", since))
+        } else if self.all_messages || messages.len() <= RECENT_MESSAGES {
+            (messages.iter().collect(), "Messages:".to_string())
+        } else {
+            (
+                messages[messages.len() - RECENT_MESSAGES..]
+                    .iter()
+                    .collect(),
+                format!(
+                    "Recent Messages ({} of {}, --all-messages for all):",
+                    RECENT_MESSAGES,
+                    messages.len()
+                ),
+            )
+        };
+
+        show!();
+        show!("{}", heading.bold());
+        if shown.is_empty() {
+            show!("  {}", "none".dimmed());
+        }
+
+        const INDENT: usize = 4;
+        for message in shown {
+            show!();
+            show!(
+                "  [{}] {}",
+                message.stage.cyan(),
+                message
+                    .timestamp
+                    .as_deref()
+                    .map(format_timestamp)
+                    .unwrap_or_default()
+            );
+            if message.text.is_empty() {
+                continue;
+            }
+            match output::width() {
+                Some(width) => {
+                    for line in output::wrap(&message.text, width.saturating_sub(INDENT).max(20)) {
+                        show!("{:INDENT$}{}", "", line);
+                    }
+                }
+                None => {
+                    for line in message.text.lines() {
+                        show!("{:INDENT$}{}", "", line);
+                    }
                 }
             }
         }
+    }
+}
+
+/// One row per job, for keeping an eye on a sweep. Jobs that can't be
+/// fetched get a row with the error and make the command fail at the end.
+fn compare_jobs(client: &dyn NsgApi, jobs: &[String], json: bool) -> Result<()> {
+    say!("{}", "NSG Job Status".bold().cyan());
+    say!("{}", "=".repeat(80).cyan());
+    say!();
+    say!(
+        "{} Checking {} jobs ({} at a time)...",
+        icon("→").cyan(),
+        jobs.len(),
+        client.max_concurrency().min(jobs.len())
+    );
+    say!();
+
+    let urls: Vec<&str> = jobs.iter().map(String::as_str).collect();
+    let mut results = client.get_job_statuses(&urls);
+    // Nothing to compare if NSG can't be reached at all
+    if results
+        .iter()
+        .all(|result| matches!(result, Err(NsgError::Network { .. })))
+    {
+        if let Some(Err(e)) = results.pop() {
+            return Err(e.into());
+        }
+    }
+    let statuses: Vec<&JobStatus> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+    cache::record_statuses(statuses.iter().copied());
 
-        println!();
-        println!("{}", "=".repeat(80));
-        println!();
+    let id_width = jobs
+        .iter()
+        .zip(&results)
+        .map(|(job, result)| match result {
+            Ok(status) => status.job_id.len(),
+            Err(_) => job.len(),
+        })
+        .max()
+        .unwrap_or(0)
+        .max("JOB".len());
+    let stage_width = statuses
+        .iter()
+        .map(|status| stage_label(status.job_stage.as_str(), status.failed).len())
+        .max()
+        .unwrap_or(0)
+        .max("STAGE".len());
 
-        print_next_action(&status.job_stage, &self.job);
+    show!(
+        "{}",
+        format!(
+            "{:<id_width$}  {:<stage_width$}  {:<16}  {:<7}  LAST MESSAGE",
+            "JOB", "STAGE", "SUBMITTED", "RESULTS"
+        )
+        .bold()
+    );
 
-        Ok(())
+    let mut errors = 0;
+    for (job, result) in jobs.iter().zip(&results) {
+        let status = match result {
+            Ok(status) => status,
+            Err(e) => {
+                errors += 1;
+                let message = e.to_string();
+                show!(
+                    "{:<id_width$}  {}",
+                    job.cyan(),
+                    message.lines().next().unwrap_or_default().red()
+                );
+                continue;
+            }
+        };
+
+        let stage = format!(
+            "{:<stage_width$}",
+            stage_label(status.job_stage.as_str(), status.failed)
+        );
+        let stage = if status.failed {
+            stage.red().bold()
+        } else {
+            stage.bold()
+        };
+        let submitted = status
+            .date_submitted
+            .as_deref()
+            .and_then(|date| date.parse::<DateTime<Utc>>().ok())
+            .map(|date| {
+                date.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|| "-".to_string());
+        let results = if status.results_uri.is_some() {
+            "yes"
+        } else {
+            "-"
+        };
+        let message = status
+            .messages
+            .last()
+            .map(|message| output::truncate(&message.text, 60))
+            .unwrap_or_default();
+
+        show!(
+            "{:<id_width$}  {}  {:<16}  {:<7}  {}",
+            status.job_id.cyan(),
+            stage,
+            submitted,
+            results,
+            message.dimmed()
+        );
     }
+    say!();
+
+    let done = statuses.iter().filter(|s| s.terminal_stage).count();
+    let failed = statuses.iter().filter(|s| s.failed).count();
+    say!(
+        "{} of {} finished, {} failed",
+        done.to_string().bold(),
+        statuses.len(),
+        failed
+    );
+    say!();
+
+    if json {
+        print_json(&statuses)?;
+    }
+
+    if errors > 0 {
+        anyhow::bail!("{} of {} jobs could not be checked", errors, jobs.len());
+    }
+
+    Ok(())
 }
 
-fn get_stage_icon(stage: &str) -> &'static str {
-    match stage {
-        "COMPLETED" => "✓",
-        "RUNNING" | "RUN" => "⟳",
-        "QUEUE" | "SUBMITTED" => "⏳",
-        "FAILED" => "✗",
-        _ => "?",
+/// The stage as NSG spells it, marked if the job failed (a failed job can
+/// still reach COMPLETED)
+pub(crate) fn stage_label(stage: &str, failed: bool) -> String {
+    if failed {
+        format!("{} (failed)", stage)
+    } else {
+        stage.to_string()
     }
 }
 
 fn format_timestamp(ts: &str) -> String {
-    use chrono::{DateTime, Utc};
     if let Ok(dt) = ts.parse::<DateTime<Utc>>() {
         dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
     } else {
@@ -104,41 +405,41 @@ fn format_timestamp(ts: &str) -> String {
     }
 }
 
-fn print_next_action(stage: &str, job_id: &str) {
+fn print_next_action(stage: &JobStage, job_id: &str) {
     match stage {
-        "COMPLETED" => {
-            println!(
+        JobStage::Completed => {
+            say!(
                 "{} Job completed! You can now download results.",
-                "✓".green().bold()
+                icon("✓").green().bold()
             );
-            println!();
-            println!("To download all results:");
-            println!("  {}", format!("nsg download {}", job_id).cyan());
+            say!();
+            say!("To download all results:");
+            say!("  {}", format!("nsg download {}", job_id).cyan());
         }
-        "FAILED" => {
-            println!(
+        JobStage::Failed => {
+            say!(
                 "{} Job failed. Check messages above for error details.",
-                "✗".red().bold()
+                icon("✗").red().bold()
             );
         }
-        "QUEUE" | "SUBMITTED" => {
-            println!("{} Job is queued. Check again later.", "⏳".yellow());
-            println!();
-            println!("To check status again:");
-            println!("  {}", format!("nsg status {}", job_id).cyan());
+        stage if stage.is_queued() => {
+            say!("{} Job is queued. Check again later.", icon("⏳").yellow());
+            say!();
+            say!("To check status again:");
+            say!("  {}", format!("nsg status {}", job_id).cyan());
         }
-        "RUN" | "RUNNING" => {
-            println!(
+        JobStage::Running | JobStage::LoadResults => {
+            say!(
                 "{} Job is running. Check back later for completion.",
-                "⟳".yellow()
+                icon("⟳").yellow()
             );
-            println!();
-            println!("To check status again:");
-            println!("  {}", format!("nsg status {}", job_id).cyan());
+            say!();
+            say!("To check status again:");
+            say!("  {}", format!("nsg status {}", job_id).cyan());
         }
         _ => {
-            println!("{} Unknown job stage: {}", "?".yellow(), stage);
+            say!("{} Unknown job stage: {}", "?".yellow(), stage);
         }
     }
-    println!();
+    say!();
 }
@@ -1,85 +1,1077 @@
-use crate::client::NsgClient;
-use crate::config::Credentials;
+use crate::api::NsgApi;
+use crate::archive::{check_input_zip, inspect_zip, zip_directory, zip_with_directories};
+use crate::cache::{self, JobCache};
+use crate::client::{submit_fields, DownloadOptions, NSG_BASE_URL};
+use crate::commands::download::DownloadReport;
+use crate::commands::output::{icon, progress_json};
+use crate::commands::progress::UploadProgress;
+use crate::commands::{print_json, watch, wizard, DownloadCommand, GlobalArgs};
+use crate::error::JobFailed;
+use crate::lint::{closest_match, lint_params};
+use crate::models::{JobStage, JobStatus, ToolParameter};
+use crate::project::PROJECT_FILE;
+use crate::templates::Template;
+use crate::{tool_aliases, tool_cache};
 use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
 use clap::Args;
 use colored::Colorize;
-use std::path::PathBuf;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// How far back an identical submission counts as a likely accident
+const DUPLICATE_WINDOW_HOURS: i64 = 24;
 
 #[derive(Debug, Args)]
 pub struct SubmitCommand {
-    #[arg(help = "Path to ZIP file containing job data")]
-    zip_file: PathBuf,
+    #[arg(
+        help = "ZIP file containing job data, or a directory to zip (sent as input.infile_) [default: input from .nsg.toml]"
+    )]
+    zip_file: Option<PathBuf>,
+
+    #[arg(
+        long = "input",
+        value_name = "NAME=PATH",
+        value_parser = parse_input,
+        help = "Additional input file sent as input.NAME (repeatable), e.g. --input params_=run.cfg"
+    )]
+    inputs: Vec<(String, PathBuf)>,
 
-    #[arg(short, long, default_value = "PY_EXPANSE", help = "NSG tool to use")]
+    #[arg(
+        short,
+        long,
+        default_value = "PY_EXPANSE",
+        help = "NSG tool ID, or an alias such as neuron, netpyne or python"
+    )]
     tool: String,
 
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = ["tool", "wizard"],
+        help = "Use the tool and parameters saved with nsg template save; --param still overrides"
+    )]
+    template: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "JOB_ID",
+        help = "Wait for this job to complete successfully before submitting (repeatable); stops if it fails"
+    )]
+    after: Vec<String>,
+
+    #[arg(
+        long,
+        requires = "after",
+        help = "Add the --after jobs' results to the input archive, under <folder>/upstream/<JOB_ID>/"
+    )]
+    inject_outputs: bool,
+
     #[arg(long, help = "Don't wait for job submission confirmation")]
     no_wait: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "no_wait",
+        help = "Wait until the job finishes; exits non-zero if it failed"
+    )]
+    wait: bool,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        num_args = 0..=1,
+        default_missing_value = "./nsg_results",
+        conflicts_with = "no_wait",
+        help = "Wait for the job, then download its results [default DIR: ./nsg_results, or default_output_dir]"
+    )]
+    download: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value = "30",
+        value_name = "SECONDS",
+        help = "Seconds between status checks with --wait/--download"
+    )]
+    interval: u64,
+
+    #[arg(
+        long,
+        help = "Show a desktop notification when the job finishes (with --wait/--download)"
+    )]
+    notify: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "notify",
+        help = "Don't show a desktop notification, even if config.toml says to"
+    )]
+    no_notify: bool,
+
+    #[arg(
+        long = "param",
+        value_name = "KEY=VALUE",
+        value_parser = parse_param,
+        help = "Tool parameter sent as vparam.KEY, or metadata.KEY sent as-is (repeatable), e.g. --param runtime_=4"
+    )]
+    params: Vec<(String, String)>,
+
+    #[arg(
+        long,
+        help = "Skip checking the tool name and --param values against NSG's tool list and descriptions"
+    )]
+    no_lint: bool,
+
+    #[arg(
+        long,
+        help = "Skip checking the input archive's layout and size before uploading"
+    )]
+    no_validate: bool,
+
+    #[arg(
+        long,
+        help = "Submit even if an identical job was submitted in the last 24 hours"
+    )]
+    allow_duplicate: bool,
+
+    #[arg(
+        long,
+        help = "Choose the tool, parameters and input interactively, step by step"
+    )]
+    wizard: bool,
+
+    #[arg(
+        long,
+        value_name = "HOURS",
+        value_parser = parse_runtime,
+        help = "Maximum run time in hours (vparam.runtime_), e.g. 2.5"
+    )]
+    runtime: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Number of compute nodes (vparam.number_nodes_)"
+    )]
+    nodes: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Number of cores per node (vparam.number_cores_)"
+    )]
+    cores: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Your own name for the job (metadata.clientJobName)"
+    )]
+    job_name: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ID",
+        help = "Your own ID for the job, e.g. a run or experiment ID (metadata.clientJobId)"
+    )]
+    client_job_id: Option<String>,
+
+    #[arg(long, help = "Don't have NSG email you when the job finishes")]
+    no_status_email: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["wait", "download"],
+        help = "Check the input and parameters and print the request instead of submitting; NSG is not contacted"
+    )]
+    dry_run: bool,
+
+    /// Job this submission repeats (`nsg resubmit`)
+    #[arg(skip)]
+    resubmit_of: Option<String>,
 }
 
 impl SubmitCommand {
-    pub fn execute(self) -> Result<()> {
-        if !self.zip_file.exists() {
-            anyhow::bail!("ZIP file not found: {}", self.zip_file.display());
+    /// A submission repeating `job_id` with the given tool, inputs and
+    /// parameters, exactly as given: no project defaults are mixed in and
+    /// it doesn't count as an accidental duplicate
+    pub(crate) fn resubmit(
+        job_id: &str,
+        tool: String,
+        inputs: Vec<(String, PathBuf)>,
+        params: Vec<(String, String)>,
+    ) -> Self {
+        Self {
+            zip_file: None,
+            inputs,
+            tool,
+            template: None,
+            after: Vec::new(),
+            inject_outputs: false,
+            no_wait: false,
+            wait: false,
+            download: None,
+            interval: 30,
+            notify: false,
+            no_notify: false,
+            params,
+            no_lint: false,
+            no_validate: false,
+            allow_duplicate: true,
+            wizard: false,
+            runtime: None,
+            nodes: None,
+            cores: None,
+            job_name: None,
+            client_job_id: None,
+            no_status_email: false,
+            dry_run: false,
+            resubmit_of: Some(job_id.to_string()),
         }
+    }
 
-        if !self.zip_file.extension().map_or(false, |ext| ext == "zip") {
-            eprintln!("{} File does not have .zip extension", "⚠".yellow());
-            eprintln!("   Continuing anyway...");
-            eprintln!();
+    /// Wait for the job to finish, download its results to `download`, and
+    /// `notify` the desktop once it has
+    pub(crate) fn waiting(
+        mut self,
+        wait: bool,
+        download: Option<PathBuf>,
+        interval: u64,
+        notify: bool,
+    ) -> Self {
+        self.wait = wait;
+        self.download = download;
+        self.interval = interval;
+        self.notify = notify;
+        self
+    }
+
+    /// Only check and print the request (`--dry-run`), and/or skip the
+    /// parameter lint (`--no-lint`) or input checks (`--no-validate`)
+    pub(crate) fn checking(mut self, dry_run: bool, no_lint: bool, no_validate: bool) -> Self {
+        self.dry_run = dry_run;
+        self.no_lint = no_lint;
+        self.no_validate = no_validate;
+        self
+    }
+
+    pub fn execute(mut self, global: &GlobalArgs) -> Result<()> {
+        let json = global.json_output();
+
+        let project = global.project.as_ref();
+        if self.zip_file.is_none() && self.inputs.is_empty() {
+            self.zip_file = project.and_then(|project| project.input.clone());
         }
 
-        let credentials = Credentials::load()?;
-        let client = NsgClient::new(credentials.clone())?;
+        if let Some(name) = &self.template {
+            let template = Template::load(name)?;
+            if let Some(tool) = template.tool {
+                self.tool = tool;
+            }
+            // Template parameters sit below any --param for the same name,
+            // and above the project's
+            for (name, value) in template.params {
+                if !self.params.iter().any(|(n, _)| *n == name) {
+                    self.params.push((name, value));
+                }
+            }
+        }
+        self.tool = tool_aliases::resolve(&self.tool, &global.tool_aliases);
 
-        println!("{}", "NSG Job Submission".bold().cyan());
-        println!("{}", "=".repeat(80).cyan());
-        println!();
-        println!("Tool:     {}", self.tool.bold());
-        println!("User:     {}", credentials.username.cyan());
-        println!("File:     {}", self.zip_file.display().to_string().cyan());
-        println!(
-            "Size:     {} bytes",
-            format_size(std::fs::metadata(&self.zip_file)?.len())
-        );
-        println!();
+        if self.wizard {
+            if json {
+                anyhow::bail!("--wizard is interactive and can't be combined with JSON output");
+            }
+            let client = global.client(global.credentials()?)?;
+            let Some(choices) = wizard::run(&client, &self.tool, self.zip_file.as_deref())? else {
+                say!("Cancelled.");
+                return Ok(());
+            };
 
-        println!("{} Submitting job to NSG...", "→".yellow().bold());
+            self.tool = choices.tool;
+            self.zip_file = Some(choices.zip_file);
+            self.params = choices.params;
+            // Every value was already checked as it was entered
+            self.no_lint = true;
+        } else if let (Some(project), None) = (project, &self.resubmit_of) {
+            // Project parameters sit below any --param for the same name
+            for (name, value) in project.params.iter().rev() {
+                if !self.params.iter().any(|(n, _)| n == name) {
+                    self.params.insert(0, (name.clone(), value.clone()));
+                }
+            }
+        }
+
+        if self.zip_file.is_none() && self.inputs.is_empty() && !self.wizard {
+            anyhow::bail!(
+                "No input given: pass a ZIP file or --input, or set `input` in {}",
+                PROJECT_FILE
+            );
+        }
 
-        let status = client
-            .submit_job(&self.zip_file, &self.tool)
-            .context("Failed to submit job")?;
+        self.apply_field_flags();
+
+        let mut inputs = Vec::new();
+        if let Some(zip_file) = &self.zip_file {
+            inputs.push(("infile_".to_string(), zip_file.clone()));
+        }
+        for (name, path) in &self.inputs {
+            if inputs.iter().any(|(n, _)| n == name) {
+                anyhow::bail!("Input '{}' given more than once", name);
+            }
+            inputs.push((name.clone(), path.clone()));
+        }
 
-        println!();
-        println!("{}", "=".repeat(80).green());
-        println!("{} Job submitted successfully!", "✓".green().bold());
-        println!("{}", "=".repeat(80).green());
-        println!();
-        println!("Job ID:   {}", status.job_id.cyan().bold());
-        println!("Stage:    {}", status.job_stage.bold());
-        println!("URL:      {}", status.self_uri.dimmed());
+        // Recorded with the job, so `nsg resubmit` finds the originals
+        let sources = inputs.clone();
+
+        // Directories are zipped on the fly into a private scratch directory
+        // (random name, owner-only) that is removed however the submission ends
+        let staging = tempfile::Builder::new()
+            .prefix("nsg-submit-")
+            .tempdir()
+            .context("Failed to create a staging directory")?;
+        for (index, (_, path)) in inputs.iter_mut().enumerate() {
+            if path.is_dir() {
+                let name = path
+                    .canonicalize()?
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "input".to_string());
+                // One subdirectory per input: two directories may share a
+                // basename, and the upload keeps the archive's file name
+                let dir = staging.path().join(index.to_string());
+                std::fs::create_dir(&dir)
+                    .with_context(|| format!("Failed to create {}", dir.display()))?;
+                let zipped = dir.join(format!("{}.zip", name));
+                say!(
+                    "{} Zipping {} into {}.zip...",
+                    icon("→").cyan(),
+                    path.display(),
+                    name
+                );
+                zip_directory(path, &zipped)?;
+                *path = zipped;
+            } else if !path.is_file() {
+                anyhow::bail!("Input file not found: {}", path.display());
+            }
+        }
+
+        if let Some(zip_file) = &self.zip_file {
+            if !zip_file.is_dir() && zip_file.extension().is_none_or(|ext| ext != "zip") {
+                eprintln!("{} File does not have .zip extension", icon("⚠").yellow());
+                eprintln!("   Continuing anyway...");
+                eprintln!();
+            }
+        }
+
+        if !self.no_validate {
+            self.validate_input(&inputs)?;
+        }
+
+        if self.dry_run {
+            return self.dry_run(global, &inputs);
+        }
+
+        let credentials = global.credentials()?;
+        let client = global.client(credentials.clone())?;
+
+        if !self.no_lint {
+            // The cached list may predate a tool NSG has just added
+            let tools = tool_cache::tool_ids(&client, false).and_then(|tools| {
+                if tools.contains(&self.tool) {
+                    Ok(tools)
+                } else {
+                    tool_cache::tool_ids(&client, true)
+                }
+            });
+            self.check_tool(tools)?;
+        }
+
+        let vparams = self.vparams();
+        if !vparams.is_empty() && !self.no_lint {
+            self.lint(&vparams, tool_cache::tool_parameters(&client, &self.tool))?;
+        }
+
+        // Before waiting on --after, so a duplicate doesn't wait hours to fail
+        let mut input_sha256 = inputs_sha256(&inputs)?;
+        if !self.allow_duplicate {
+            self.check_duplicates(&input_sha256)?;
+        }
+
+        if !self.after.is_empty() {
+            self.wait_for_upstream(&client, staging.path(), &mut inputs)?;
+            if self.inject_outputs {
+                input_sha256 = inputs_sha256(&inputs)?;
+                if !self.allow_duplicate {
+                    self.check_duplicates(&input_sha256)?;
+                }
+            }
+        }
+
+        self.print_summary(global, Some(&credentials.username), &inputs)?;
+
+        say!("{} Submitting job to NSG...", icon("→").yellow().bold());
+
+        let status = if progress_json() {
+            let progress = Arc::new(UploadProgress::default());
+            let events = Arc::clone(&progress);
+            client
+                .submit_job_with_progress(&self.tool, &inputs, &self.params, move |event| {
+                    events.on_event(&event)
+                })
+                .inspect_err(|e| progress.fail(&e.to_string()))?
+        } else {
+            client.submit_job_with_inputs(&self.tool, &inputs, &self.params)?
+        };
+        let input_names: Vec<String> = inputs
+            .iter()
+            .filter_map(|(_, path)| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        cache::record_submission(
+            &status,
+            &self.tool,
+            Some(&input_names.join(", ")),
+            &sources,
+            Some(&input_sha256),
+            &self.params,
+        );
+        if let Some(original) = &self.resubmit_of {
+            cache::add_note(&status.job_id, &format!("Resubmission of {}", original));
+        }
+        if !self.after.is_empty() {
+            cache::add_note(
+                &status.job_id,
+                &format!("Submitted after {}", self.after.join(", ")),
+            );
+        }
+
+        say!();
+        say!("{}", "=".repeat(80).green());
+        say!("{} Job submitted successfully!", icon("✓").green().bold());
+        say!("{}", "=".repeat(80).green());
+        say!();
+        show!("Job ID:   {}", status.job_id.cyan().bold());
+        show!("Stage:    {}", status.job_stage.as_str().bold());
+        show!("URL:      {}", status.self_uri.dimmed());
 
         if let Some(date) = &status.date_submitted {
-            println!("Submitted: {}", date);
-        }
-
-        println!();
-        println!("{}", "Next Steps:".bold());
-        println!("  1. Check job status:");
-        println!("     {}", format!("nsg status {}", status.job_id).cyan());
-        println!();
-        println!("  2. When completed, download results:");
-        println!("     {}", format!("nsg download {}", status.job_id).cyan());
-        println!();
-        println!("  3. View all jobs:");
-        println!("     {}", "nsg list".cyan());
-        println!();
-        println!("{}", "NSG Portal:".bold());
-        println!("  {}", "https://www.nsgportal.org/".cyan());
-        println!();
+            show!("Submitted: {}", date);
+        }
+
+        if self.wait || self.download.is_some() {
+            say!();
+            let finished = watch::watch_job(&client, &status.self_uri, self.interval)?;
+            watch::job_finished(&finished, self.notify && !self.no_notify);
+            say!();
+
+            let mut download = None;
+            if let Some(dir) = self.download {
+                if finished.results_uri.is_some() {
+                    download = DownloadCommand::new(finished.self_uri.clone(), dir).run(global)?;
+                } else {
+                    eprintln!("{} Job has no results to download", icon("⚠").yellow());
+                }
+            }
+
+            if json {
+                print_json(&SubmitReport {
+                    status: &finished,
+                    download,
+                })?;
+            }
+
+            if finished.failed {
+                return Err(JobFailed {
+                    job_id: finished.job_id,
+                }
+                .into());
+            }
+
+            say!(
+                "{} Job {} finished",
+                icon("✓").green().bold(),
+                finished.job_id.cyan()
+            );
+            return Ok(());
+        }
+
+        say!();
+        say!("{}", "Next Steps:".bold());
+        say!("  1. Check job status:");
+        say!("     {}", format!("nsg status {}", status.job_id).cyan());
+        say!();
+        say!("  2. When completed, download results:");
+        say!("     {}", format!("nsg download {}", status.job_id).cyan());
+        say!();
+        say!("  3. View all jobs:");
+        say!("     {}", "nsg list".cyan());
+        say!();
+        say!("{}", "NSG Portal:".bold());
+        say!("  {}", "https://www.nsgportal.org/".cyan());
+        say!();
+
+        if json {
+            print_json(&SubmitReport {
+                status: &status,
+                download: None,
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// What `nsg submit --dry-run --output json` prints: the request that would
+/// have been sent
+#[derive(Debug, Serialize)]
+struct DryRunReport {
+    method: &'static str,
+    url: String,
+    fields: Vec<FormField>,
+}
+
+/// One multipart form field: text, or an uploaded file
+#[derive(Debug, Serialize)]
+struct FormField {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    /// Files in the archive, for ZIP inputs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    entries: Option<usize>,
+}
+
+impl FormField {
+    fn text(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+            file: None,
+            size: None,
+            entries: None,
+        }
+    }
+}
+
+/// What `nsg submit --output json` prints: the job's status, plus the
+/// download when `--download` was given
+#[derive(Debug, Serialize)]
+struct SubmitReport<'a> {
+    #[serde(flatten)]
+    status: &'a JobStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    download: Option<DownloadReport>,
+}
+
+impl SubmitCommand {
+    /// `--dry-run`: every local check a submission makes, then the form
+    /// fields that would be posted instead of posting them
+    fn dry_run(&self, global: &GlobalArgs, inputs: &[(String, PathBuf)]) -> Result<()> {
+        // Only for the URL; a dry run must work before `nsg login`
+        let username = global.credentials().ok().map(|c| c.username);
+        self.print_summary(global, username.as_deref(), inputs)?;
+
+        let mut fields = vec![FormField::text("tool", &self.tool)];
+        for (name, path) in inputs {
+            let is_zip = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+            // A main input without .zip only gets a warning when submitted
+            // for real, so don't fail on it here either
+            let archive = if is_zip {
+                Some(inspect_zip(path)?)
+            } else {
+                None
+            };
+
+            fields.push(FormField {
+                name: format!("input.{}", name),
+                value: None,
+                file: Some(path.clone()),
+                size: Some(std::fs::metadata(path)?.len()),
+                entries: archive.map(|archive| archive.entries),
+            });
+        }
+        fields.extend(
+            submit_fields(&self.params)
+                .iter()
+                .map(|(name, value)| FormField::text(name, value)),
+        );
+
+        if !self.no_lint {
+            if let Ok(tools) = tool_cache::cached_tool_ids() {
+                self.check_tool(Ok(tools))?;
+            }
+        }
+
+        let vparams = self.vparams();
+        if !vparams.is_empty() && !self.no_lint {
+            self.lint(&vparams, tool_cache::cached_tool_parameters(&self.tool))?;
+        }
+
+        if !self.allow_duplicate {
+            self.check_duplicates(&inputs_sha256(inputs)?)?;
+        }
+
+        let report = DryRunReport {
+            method: "POST",
+            url: format!(
+                "{}/job/{}",
+                global.base_url.as_deref().unwrap_or(NSG_BASE_URL),
+                username.as_deref().unwrap_or("<USERNAME>")
+            ),
+            fields,
+        };
+
+        if global.json_output() {
+            return print_json(&report);
+        }
+
+        show!("{} {}", report.method.bold(), report.url);
+        let width = report
+            .fields
+            .iter()
+            .map(|f| f.name.len())
+            .max()
+            .unwrap_or(0);
+        for field in &report.fields {
+            let value = match (&field.value, &field.file) {
+                (Some(value), _) => value.clone(),
+                (None, Some(file)) => {
+                    let name = file
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    let mut details = format_size(field.size.unwrap_or_default());
+                    match field.entries {
+                        Some(entries) => details.push_str(&format!(", {} entries", entries)),
+                        None if field.name == "input.infile_" => {
+                            details.push_str(", not a ZIP archive")
+                        }
+                        None => {}
+                    }
+                    format!("@{} ({})", name.cyan(), details)
+                }
+                (None, None) => String::new(),
+            };
+            show!("  {:<width$}  {}", field.name, value, width = width);
+        }
+        say!();
+        say!(
+            "{} Dry run: nothing was sent. Run again without {} to submit.",
+            icon("✓").green().bold(),
+            "--dry-run".cyan()
+        );
+        say!();
+
+        Ok(())
+    }
+
+    /// The banner and what is about to be submitted
+    fn print_summary(
+        &self,
+        global: &GlobalArgs,
+        username: Option<&str>,
+        inputs: &[(String, PathBuf)],
+    ) -> Result<()> {
+        if self.dry_run {
+            say!("{}", "NSG Job Submission (dry run)".bold().cyan());
+        } else {
+            say!("{}", "NSG Job Submission".bold().cyan());
+        }
+        say!("{}", "=".repeat(80).cyan());
+        say!();
+        if let Some(project) = &global.project {
+            say!("Project:  {}", project.path.display().to_string().dimmed());
+        }
+        say!("Tool:     {}", self.tool.bold());
+        if let Some(username) = username {
+            say!("User:     {}", username.cyan());
+        }
+        for (name, path) in inputs {
+            let size = format_size(std::fs::metadata(path)?.len());
+            if name == "infile_" {
+                say!("File:     {}", path.display().to_string().cyan());
+                say!("Size:     {}", size);
+            } else {
+                say!(
+                    "Input:    {} = {} ({})",
+                    name,
+                    path.display().to_string().cyan(),
+                    size
+                );
+            }
+        }
+        for job in &self.after {
+            if self.inject_outputs {
+                say!("After:    {} (results added to the input)", job.cyan());
+            } else {
+                say!("After:    {}", job.cyan());
+            }
+        }
+        for (name, value) in &self.params {
+            match name.strip_prefix("metadata.") {
+                Some(field) => say!("Metadata: {} = {}", field, value.bold()),
+                None => say!("Param:    {} = {}", name, value.bold()),
+            }
+        }
+        say!();
+
+        Ok(())
+    }
+
+    /// `--after`: wait for every upstream job to complete, failing if one
+    /// doesn't, and with `--inject-outputs` swap the main input for a copy
+    /// holding their results
+    fn wait_for_upstream(
+        &self,
+        client: &dyn NsgApi,
+        staging: &Path,
+        inputs: &mut [(String, PathBuf)],
+    ) -> Result<()> {
+        let mut results = Vec::new();
+
+        for job in &self.after {
+            say!(
+                "{} Waiting for upstream job {}",
+                icon("→").cyan(),
+                job.bold()
+            );
+            let status = watch::watch_job(client, job, self.interval)?;
+            say!();
+            if status.failed || status.job_stage != JobStage::Completed {
+                return Err(anyhow::Error::new(JobFailed {
+                    job_id: status.job_id,
+                })
+                .context("Upstream job did not complete; nothing was submitted"));
+            }
+
+            if self.inject_outputs {
+                let dir = staging.join("upstream").join(&status.job_id);
+                say!(
+                    "{} Downloading results of {}...",
+                    icon("→").cyan(),
+                    status.job_id
+                );
+                let files = client.download_results(
+                    &status.self_uri,
+                    &dir,
+                    &DownloadOptions::default(),
+                    &mut |_| {},
+                )?;
+                say!("   {} file(s)", files.len());
+                results.push((format!("upstream/{}", status.job_id), dir));
+            }
+        }
+
+        if results.is_empty() {
+            return Ok(());
+        }
+
+        let Some((_, input)) = inputs.iter_mut().find(|(name, _)| name == "infile_") else {
+            anyhow::bail!("--inject-outputs needs a ZIP input to add the results to");
+        };
+        let name = input
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "input".to_string());
+        let combined = staging.join(format!("{}-with-upstream.zip", name));
+        zip_with_directories(input, &results, &combined)
+            .context("Failed to add the upstream results to the input")?;
+        say!(
+            "{} Added upstream results to a copy of {}.zip",
+            icon("✓").green().bold(),
+            name
+        );
+        say!();
+        *input = combined;
 
         Ok(())
     }
+
+    /// Fail on any way the main input archive breaks NSG's conventions,
+    /// rather than uploading it for a job that fails on the cluster
+    fn validate_input(&self, inputs: &[(String, PathBuf)]) -> Result<()> {
+        let Some((_, path)) = inputs.iter().find(|(name, _)| name == "infile_") else {
+            return Ok(());
+        };
+        if path
+            .extension()
+            .is_none_or(|ext| !ext.eq_ignore_ascii_case("zip"))
+        {
+            return Ok(());
+        }
+
+        let issues = check_input_zip(path, &self.tool)?;
+        if !issues.is_empty() {
+            eprintln!();
+            for issue in &issues {
+                eprintln!("  {} {}", icon("✗").red().bold(), issue);
+            }
+            eprintln!();
+            anyhow::bail!(
+                "{} problem(s) with input archive {} (use --no-validate to submit anyway)",
+                issues.len(),
+                path.display()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Tool parameters among `--param`s; metadata.* isn't described by the
+    /// tool, so it isn't linted
+    fn vparams(&self) -> Vec<(String, String)> {
+        self.params
+            .iter()
+            .filter(|(name, _)| !name.starts_with("metadata."))
+            .cloned()
+            .collect()
+    }
+
+    /// Fail if `--tool` isn't one NSG offers, before anything is uploaded,
+    /// suggesting the closest one. If there is no tool list to check
+    /// against, only warn.
+    fn check_tool(&self, tools: Result<Vec<String>>) -> Result<()> {
+        let tools = match tools {
+            Ok(tools) => tools,
+            Err(e) => {
+                eprintln!(
+                    "{} Could not check the tool against NSG's tool list: {}",
+                    icon("⚠").yellow(),
+                    e
+                );
+                return Ok(());
+            }
+        };
+        if tools.contains(&self.tool) {
+            return Ok(());
+        }
+
+        match closest_match(&self.tool, tools.iter().map(String::as_str)) {
+            Some(suggestion) => anyhow::bail!(
+                "Unknown tool {} - did you mean {}? (see `nsg tools`, or use --no-lint to submit anyway)",
+                self.tool,
+                suggestion
+            ),
+            None => anyhow::bail!(
+                "Unknown tool {} (see `nsg tools`, or use --no-lint to submit anyway)",
+                self.tool
+            ),
+        }
+    }
+
+    /// Fail on any parameter that doesn't fit the tool's description. If
+    /// there is no description to check against, only warn.
+    fn lint(
+        &self,
+        vparams: &[(String, String)],
+        definition: Result<Vec<ToolParameter>>,
+    ) -> Result<()> {
+        say!("{} Checking parameters...", icon("→").cyan());
+
+        match definition {
+            Ok(definition) => {
+                let issues = lint_params(&definition, vparams);
+                if !issues.is_empty() {
+                    eprintln!();
+                    for issue in &issues {
+                        eprintln!("  {} {}", icon("✗").red().bold(), issue);
+                    }
+                    eprintln!();
+                    anyhow::bail!(
+                        "{} parameter problem(s) for tool {} (use --no-lint to submit anyway)",
+                        issues.len(),
+                        self.tool
+                    );
+                }
+                say!("{} Parameters OK", icon("✓").green());
+            }
+            Err(e) => {
+                eprintln!(
+                    "{} Could not check parameters against the tool description: {}",
+                    icon("⚠").yellow(),
+                    e
+                );
+            }
+        }
+        say!();
+
+        Ok(())
+    }
+
+    /// Turn the resource and metadata flags into form fields, overriding any
+    /// `--param` given for the same field.
+    ///
+    /// Resource values are checked against the tool's own limits by the
+    /// parameter lint like any other `--param`.
+    fn apply_field_flags(&mut self) {
+        let mut fields = Vec::new();
+        if let Some(hours) = self.runtime {
+            fields.push(("runtime_", hours.to_string()));
+        }
+        if let Some(nodes) = self.nodes {
+            fields.push(("number_nodes_", nodes.to_string()));
+        }
+        if let Some(cores) = self.cores {
+            fields.push(("number_cores_", cores.to_string()));
+        }
+        if let Some(name) = self.job_name.take() {
+            fields.push(("metadata.clientJobName", name));
+        }
+        if let Some(id) = self.client_job_id.take() {
+            fields.push(("metadata.clientJobId", id));
+        }
+        if self.no_status_email {
+            fields.push(("metadata.statusEmail", "false".to_string()));
+        }
+
+        for (field, value) in fields {
+            self.params.retain(|(name, _)| name != field);
+            self.params.push((field.to_string(), value));
+        }
+    }
+
+    /// Refuse to resubmit the same input, tool and parameters by accident.
+    ///
+    /// Interactive users are asked; scripts must pass `--allow-duplicate`.
+    fn check_duplicates(&self, input_sha256: &str) -> Result<()> {
+        let Ok(cache) = JobCache::load() else {
+            return Ok(());
+        };
+
+        let params: BTreeMap<String, String> = self.params.iter().cloned().collect();
+        let since = Utc::now() - Duration::hours(DUPLICATE_WINDOW_HOURS);
+        let duplicates = cache.find_duplicates(&self.tool, input_sha256, &params, since);
+
+        if duplicates.is_empty() {
+            return Ok(());
+        }
+
+        eprintln!(
+            "{} The same input, tool and parameters were already submitted:",
+            icon("⚠").yellow().bold()
+        );
+        for job in &duplicates {
+            eprintln!(
+                "   {}  {}  {}",
+                job.job_id.cyan(),
+                job.submitted_at
+                    .map(|at| at.format("%Y-%m-%d %H:%M UTC").to_string())
+                    .unwrap_or_default(),
+                job.stage.dimmed()
+            );
+        }
+        eprintln!();
+
+        if self.dry_run {
+            return Ok(());
+        }
+
+        if std::io::stdin().is_terminal() {
+            eprint!("Submit again anyway? [y/N] ");
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            if input.trim().eq_ignore_ascii_case("y") {
+                eprintln!();
+                return Ok(());
+            }
+        }
+
+        anyhow::bail!("Duplicate submission skipped (use --allow-duplicate to submit anyway)")
+    }
+}
+
+/// One hash identifying all inputs: the file's own SHA-256 for the usual
+/// single archive, otherwise a hash over each `name:hash` pair
+pub(crate) fn inputs_sha256(inputs: &[(String, PathBuf)]) -> Result<String> {
+    if let [(_, path)] = inputs {
+        return sha256_file(path);
+    }
+
+    let mut hasher = Sha256::new();
+    for (name, path) in inputs {
+        hasher.update(format!("{}:{}\n", name, sha256_file(path)?));
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let bytes_read = file
+            .read(&mut buffer)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub(crate) fn parse_input(s: &str) -> Result<(String, PathBuf), String> {
+    let (name, path) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=PATH, got '{}'", s))?;
+    let name = name.trim().trim_start_matches("input.");
+
+    if name.is_empty() || path.is_empty() {
+        return Err(format!("expected NAME=PATH, got '{}'", s));
+    }
+
+    Ok((name.to_string(), PathBuf::from(path)))
+}
+
+pub(crate) fn parse_runtime(s: &str) -> Result<f64, String> {
+    let hours: f64 = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("expected a number of hours, got '{}'", s))?;
+
+    if !hours.is_finite() || hours <= 0.0 {
+        return Err(format!("run time must be more than 0 hours, got {}", s));
+    }
+
+    Ok(hours)
+}
+
+pub(crate) fn parse_param(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", s))?;
+    let key = key.trim().trim_start_matches("vparam.");
+
+    if key.is_empty() || key == "metadata." {
+        return Err(format!("missing parameter name in '{}'", s));
+    }
+
+    // Tool parameters never contain dots; metadata.* is forwarded verbatim
+    if key.contains('.') && !key.starts_with("metadata.") {
+        return Err(format!(
+            "unsupported field '{}' (use a tool parameter name, vparam.NAME or metadata.NAME)",
+            key
+        ));
+    }
+
+    Ok((key.to_string(), value.to_string()))
 }
 
 fn format_size(bytes: u64) -> String {
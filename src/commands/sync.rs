@@ -0,0 +1,161 @@
+use crate::cache;
+use crate::client::{DownloadEvent, DownloadOptions, DEFAULT_DOWNLOAD_JOBS};
+use crate::commands::output::{icon, progress_json};
+use crate::commands::progress::DownloadProgress;
+use crate::commands::GlobalArgs;
+use crate::filenames::FileFilter;
+use crate::manifest::write_manifest;
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct SyncCommand {
+    #[arg(help = "Job URL or Job ID")]
+    job: String,
+
+    #[arg(help = "Local directory to mirror the job's output files into")]
+    dir: PathBuf,
+
+    #[arg(
+        short,
+        long = "file",
+        value_name = "PATTERN",
+        help = "Only sync files matching this glob (repeatable)"
+    )]
+    files: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        help = "Skip files matching this glob (repeatable)"
+    )]
+    exclude: Vec<String>,
+
+    #[arg(
+        short,
+        long,
+        value_name = "N",
+        default_value_t = DEFAULT_DOWNLOAD_JOBS as u16,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Files to download in parallel (also capped by --max-concurrency)"
+    )]
+    jobs: u16,
+}
+
+impl SyncCommand {
+    /// Bring `dir` in line with the job's outputs, fetching only files that
+    /// are missing locally or whose size differs (or isn't known). Never
+    /// prompts, so it is safe to run from cron.
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let options = DownloadOptions {
+            filter: FileFilter::new(&self.files, &self.exclude)?,
+            jobs: usize::from(self.jobs),
+            resume: true,
+            skip_unchanged: true,
+            working_dir: false,
+        };
+
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+
+        let status = client.get_job_status(&self.job)?;
+        cache::record_statuses([&status]);
+
+        say!(
+            "{} Syncing {} into {}",
+            icon("→").cyan(),
+            status.job_id.bold(),
+            self.dir.display().to_string().cyan()
+        );
+
+        if status.results_uri.is_none() {
+            println!(
+                "{} Job is {} and has no results yet - nothing to sync",
+                icon("⚠").yellow(),
+                status.job_stage.as_str().bold()
+            );
+            return Ok(());
+        }
+
+        if !status.terminal_stage {
+            println!(
+                "{} Job is still {} - syncing the outputs available so far",
+                icon("⚠").yellow(),
+                status.job_stage.as_str().bold()
+            );
+        }
+
+        let mut json_progress = progress_json().then(DownloadProgress::default);
+        let synced = client.download_results_with(&self.job, &self.dir, &options, |event| {
+            if let Some(progress) = &mut json_progress {
+                progress.on_event(&event);
+            }
+            match event {
+                DownloadEvent::FileStarted {
+                    filename,
+                    size: None,
+                } => {
+                    say!(
+                        "  {} NSG didn't report the size of {}, so it is fetched every time",
+                        icon("⚠").yellow(),
+                        filename
+                    );
+                }
+                DownloadEvent::FileFinished { file } if !file.up_to_date => {
+                    say!(
+                        "  {} {} ({})",
+                        icon("✓").green(),
+                        file.remote_filename.cyan(),
+                        format_size(file.size)
+                    );
+                }
+                _ => {}
+            }
+        })?;
+
+        if !synced.is_empty() {
+            write_manifest(&self.dir, &status, &synced)?;
+            cache::record_download(&status.job_id, &self.dir);
+        }
+
+        let (up_to_date, fetched): (Vec<_>, Vec<_>) =
+            synced.iter().partition(|file| file.up_to_date);
+        let fetched_size: u64 = fetched.iter().map(|file| file.size).sum();
+
+        if fetched.is_empty() {
+            say!(
+                "{} Already up to date ({} file(s))",
+                icon("✓").green().bold(),
+                up_to_date.len()
+            );
+        } else {
+            say!(
+                "{} Downloaded {} file(s) ({}), {} already up to date",
+                icon("✓").green().bold(),
+                fetched.len(),
+                format_size(fetched_size),
+                up_to_date.len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+
+    if bytes >= GB {
+        format!("{:.2} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.2} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
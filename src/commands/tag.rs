@@ -0,0 +1,133 @@
+use crate::cache::{job_id_from, JobCache};
+use crate::commands::output::icon;
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use colored::Colorize;
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct TagCommand {
+    #[command(subcommand)]
+    action: Option<TagAction>,
+
+    // `nsg tag <JOB> <TAG>...` is short for `nsg tag add <JOB> <TAG>...`
+    #[arg(required = true, help = "Job URL or Job ID")]
+    job: Option<String>,
+
+    #[arg(required = true, help = "Tags to add")]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Subcommand)]
+enum TagAction {
+    #[command(about = "Attach one or more tags to a job")]
+    Add {
+        #[arg(help = "Job URL or Job ID")]
+        job: String,
+
+        #[arg(required = true, help = "Tags to add")]
+        tags: Vec<String>,
+    },
+
+    #[command(about = "Remove tags from a job")]
+    Remove {
+        #[arg(help = "Job URL or Job ID")]
+        job: String,
+
+        #[arg(required = true, help = "Tags to remove")]
+        tags: Vec<String>,
+    },
+
+    #[command(about = "List tags of one job, or all tags in use")]
+    List {
+        #[arg(help = "Job URL or Job ID")]
+        job: Option<String>,
+    },
+}
+
+impl TagCommand {
+    pub fn execute(self) -> Result<()> {
+        let mut cache = JobCache::load()?;
+
+        let action = match (self.action, self.job) {
+            (Some(action), _) => action,
+            (None, Some(job)) => TagAction::Add {
+                job,
+                tags: self.tags,
+            },
+            (None, None) => unreachable!("clap requires a job without a subcommand"),
+        };
+
+        match action {
+            TagAction::Add { job, tags } => {
+                let entry = cache.entry(job_id_from(&job));
+                for tag in tags {
+                    validate_tag(&tag)?;
+                    if !entry.has_tag(&tag) {
+                        entry.tags.push(tag);
+                    }
+                }
+                entry.tags.sort();
+                println!(
+                    "{} {}: {}",
+                    icon("✓").green().bold(),
+                    entry.job_id.cyan(),
+                    entry.tags.join(", ")
+                );
+                cache.save()?;
+            }
+            TagAction::Remove { job, tags } => {
+                let entry = cache.entry(job_id_from(&job));
+                entry.tags.retain(|t| !tags.contains(t));
+                println!(
+                    "{} {}: {}",
+                    icon("✓").green().bold(),
+                    entry.job_id.cyan(),
+                    if entry.tags.is_empty() {
+                        "(no tags)".dimmed().to_string()
+                    } else {
+                        entry.tags.join(", ")
+                    }
+                );
+                cache.save()?;
+            }
+            TagAction::List { job: Some(job) } => {
+                let job_id = job_id_from(&job);
+                match cache.get(job_id).filter(|j| !j.tags.is_empty()) {
+                    Some(entry) => {
+                        for tag in &entry.tags {
+                            println!("{}", tag);
+                        }
+                    }
+                    None => println!("{}", format!("{} has no tags", job_id).yellow()),
+                }
+            }
+            TagAction::List { job: None } => {
+                let mut counts = std::collections::BTreeMap::new();
+                for tag in cache.jobs.values().flat_map(|j| &j.tags) {
+                    *counts.entry(tag.as_str()).or_insert(0) += 1;
+                }
+
+                if counts.is_empty() {
+                    println!("{}", "No tags yet".yellow());
+                    println!();
+                    println!("Tag a job with:");
+                    println!("  {}", "nsg tag add <JOB_ID> <TAG>".cyan());
+                }
+
+                for (tag, count) in counts {
+                    println!("{:<24} {} job(s)", tag, count);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_tag(tag: &str) -> Result<()> {
+    if tag.is_empty() || tag.chars().any(|c| c.is_whitespace() || c == ',') {
+        anyhow::bail!("Invalid tag '{}': tags can't contain spaces or commas", tag);
+    }
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use crate::cache;
+use crate::commands::output::icon;
+use crate::commands::{resolve, watch, GlobalArgs};
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use std::io::Write;
+use std::time::Duration;
+
+#[derive(Debug, Args)]
+pub struct TailCommand {
+    #[arg(help = "Job URL, Job ID or a unique part of one [default: choose from a list]")]
+    job: Option<String>,
+
+    #[arg(long, help = "Follow stderr.txt instead of stdout.txt")]
+    stderr: bool,
+
+    #[arg(
+        short,
+        long,
+        default_value = "30",
+        value_name = "SECONDS",
+        help = "Seconds between polls of the working directory"
+    )]
+    interval: u64,
+
+    #[arg(long, help = "Print what is there now and exit instead of following")]
+    no_follow: bool,
+}
+
+impl TailCommand {
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+        let job = match &self.job {
+            Some(job) => resolve::resolve_jobs(&client, &[job])?.remove(0),
+            None => resolve::pick_job(&client, "nsg tail <JOB>")?,
+        };
+
+        let filename = if self.stderr {
+            "stderr.txt"
+        } else {
+            "stdout.txt"
+        };
+
+        hint!(
+            "{} Tailing {} of {} (Ctrl-C to stop)",
+            icon("→").cyan(),
+            filename.bold(),
+            job.bold()
+        );
+
+        let mut offset = 0u64;
+        let mut stdout = std::io::stdout();
+
+        loop {
+            let status = client.get_job_status(&job)?;
+            cache::record_statuses([&status]);
+            let finished = status.terminal_stage || status.failed;
+
+            // Once a job finishes its working directory is cleaned up and the
+            // logs move to the results, so pick up the remainder from there.
+            let files = if finished {
+                client.get_output_files(&status)
+            } else {
+                client.list_working_dir(&status)
+            };
+
+            if let Some(file) = files
+                .ok()
+                .and_then(|files| files.into_iter().find(|f| f.filename == filename))
+            {
+                if file.size.is_some_and(|size| size < offset) {
+                    eprintln!("{} {} was truncated", icon("⚠").yellow(), filename);
+                    offset = 0;
+                }
+
+                // Without a length, ask anyway: nothing new comes back empty
+                if file.size.is_none_or(|size| size > offset) {
+                    let bytes = client.read_file_from(&file, offset)?;
+                    stdout.write_all(&bytes)?;
+                    stdout.flush()?;
+                    offset += bytes.len() as u64;
+                }
+            }
+
+            if finished {
+                eprintln!();
+                eprintln!(
+                    "{} Job reached stage {}",
+                    if status.failed {
+                        icon("✗").red().bold()
+                    } else {
+                        icon("✓").green().bold()
+                    },
+                    status.job_stage.as_str().bold()
+                );
+                if !self.no_follow {
+                    watch::job_finished(&status, false);
+                }
+                break;
+            }
+
+            if self.no_follow {
+                break;
+            }
+
+            std::thread::sleep(Duration::from_secs(self.interval.max(1)));
+        }
+
+        Ok(())
+    }
+}
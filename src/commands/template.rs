@@ -0,0 +1,229 @@
+use crate::commands::output::icon;
+use crate::commands::submit::{parse_param, parse_runtime};
+use crate::commands::{print_json, GlobalArgs};
+use crate::templates::Template;
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Args)]
+pub struct TemplateCommand {
+    #[command(subcommand)]
+    action: TemplateAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum TemplateAction {
+    #[command(about = "Save a tool and parameters under a name for nsg submit --template")]
+    Save {
+        #[arg(help = "Template name, e.g. neuron-8h")]
+        name: String,
+
+        #[arg(short, long, help = "NSG tool to use")]
+        tool: Option<String>,
+
+        #[arg(
+            long = "param",
+            value_name = "KEY=VALUE",
+            value_parser = parse_param,
+            help = "Tool parameter or metadata.KEY field (repeatable), e.g. --param runtime_=8"
+        )]
+        params: Vec<(String, String)>,
+
+        #[arg(
+            long,
+            value_name = "HOURS",
+            value_parser = parse_runtime,
+            help = "Maximum run time in hours (vparam.runtime_)"
+        )]
+        runtime: Option<f64>,
+
+        #[arg(
+            long,
+            value_name = "N",
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "Number of compute nodes (vparam.number_nodes_)"
+        )]
+        nodes: Option<u32>,
+
+        #[arg(
+            long,
+            value_name = "N",
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "Number of cores per node (vparam.number_cores_)"
+        )]
+        cores: Option<u32>,
+
+        #[arg(short, long, help = "What the template is for, shown by list")]
+        description: Option<String>,
+
+        #[arg(short, long, help = "Replace an existing template of the same name")]
+        force: bool,
+    },
+
+    #[command(about = "List the saved templates")]
+    List,
+
+    #[command(about = "Show what a template sets")]
+    Show {
+        #[arg(help = "Template name")]
+        name: String,
+    },
+
+    #[command(about = "Open a template in $VISUAL or $EDITOR")]
+    Edit {
+        #[arg(help = "Template name")]
+        name: String,
+    },
+
+    #[command(about = "Delete a template")]
+    Delete {
+        #[arg(help = "Template name")]
+        name: String,
+    },
+
+    #[command(about = "Print the file a template is stored in")]
+    Path {
+        #[arg(help = "Template name")]
+        name: String,
+    },
+}
+
+impl TemplateCommand {
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        match self.action {
+            TemplateAction::Save {
+                name,
+                tool,
+                params,
+                runtime,
+                nodes,
+                cores,
+                description,
+                force,
+            } => {
+                let fields = [
+                    ("runtime_", runtime.map(|hours| hours.to_string())),
+                    ("number_nodes_", nodes.map(|n| n.to_string())),
+                    ("number_cores_", cores.map(|n| n.to_string())),
+                ];
+                let mut params: BTreeMap<String, String> = params.into_iter().collect();
+                for (field, value) in fields {
+                    if let Some(value) = value {
+                        params.insert(field.to_string(), value);
+                    }
+                }
+                if tool.is_none() && params.is_empty() {
+                    anyhow::bail!("Nothing to save: give --tool and/or --param");
+                }
+
+                let template = Template {
+                    name,
+                    description,
+                    tool,
+                    params,
+                };
+                let path = template.save(force)?;
+                say!(
+                    "{} Saved template {} to {}",
+                    icon("✓").green().bold(),
+                    template.name.cyan(),
+                    path.display()
+                );
+                say!(
+                    "   Use it with: {}",
+                    format!("nsg submit <ZIP_FILE> --template {}", template.name).cyan()
+                );
+            }
+            TemplateAction::List => {
+                let templates = Template::list()?;
+                if global.json_output() {
+                    return print_json(&templates);
+                }
+                if templates.is_empty() {
+                    say!("No templates saved (create one with nsg template save)");
+                    return Ok(());
+                }
+
+                let width = templates.iter().map(|t| t.name.len()).max().unwrap_or(0);
+                for template in &templates {
+                    let params: Vec<String> = template
+                        .params
+                        .iter()
+                        .map(|(name, value)| format!("{}={}", name, value))
+                        .collect();
+                    show!(
+                        "{:<width$}  {:<16}  {}",
+                        template.name.cyan(),
+                        template.tool.as_deref().unwrap_or("-"),
+                        params.join(" ").dimmed()
+                    );
+                    if let Some(description) = &template.description {
+                        show!("{:<width$}  {}", "", description);
+                    }
+                }
+            }
+            TemplateAction::Show { name } => {
+                let template = Template::load(&name)?;
+                if global.json_output() {
+                    return print_json(&template);
+                }
+
+                show!("{}", template.name.cyan().bold());
+                if let Some(description) = &template.description {
+                    show!("  {}", description);
+                }
+                show!("  Tool:   {}", template.tool.as_deref().unwrap_or("-"));
+                for (name, value) in &template.params {
+                    show!("  Param:  {} = {}", name, value);
+                }
+                say!(
+                    "  File:   {}",
+                    Template::path(&name)?.display().to_string().dimmed()
+                );
+            }
+            TemplateAction::Edit { name } => {
+                let path = Template::path(&name)?;
+                // Fails with the usual message if there's no such template
+                Template::load(&name)?;
+
+                let editor = std::env::var("VISUAL")
+                    .or_else(|_| std::env::var("EDITOR"))
+                    .unwrap_or_else(|_| "vi".to_string());
+                let mut words = editor.split_whitespace();
+                let program = words.next().unwrap_or("vi");
+                let status = std::process::Command::new(program)
+                    .args(words)
+                    .arg(&path)
+                    .status()
+                    .with_context(|| format!("Failed to start editor '{}'", editor))?;
+                if !status.success() {
+                    anyhow::bail!("{} exited with {}", editor, status);
+                }
+
+                Template::load(&name)
+                    .context("The edited template is invalid; fix it with nsg template edit")?;
+                say!(
+                    "{} Template {} updated",
+                    icon("✓").green().bold(),
+                    name.cyan()
+                );
+            }
+            TemplateAction::Delete { name } => {
+                if Template::delete(&name)? {
+                    say!(
+                        "{} Template {} deleted",
+                        icon("✓").green().bold(),
+                        name.cyan()
+                    );
+                } else {
+                    anyhow::bail!("No template named '{}'", name);
+                }
+            }
+            TemplateAction::Path { name } => println!("{}", Template::path(&name)?.display()),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+use crate::api::NsgApi;
+use crate::commands::GlobalArgs;
+use crate::models::ToolParameter;
+use crate::{tool_aliases, tool_cache};
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Args)]
+pub struct ToolsCommand {
+    #[command(subcommand)]
+    action: Option<ToolsAction>,
+
+    #[arg(
+        short,
+        long,
+        help = "Only show tools whose ID or name contains this text (case-insensitive)"
+    )]
+    filter: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+enum ToolsAction {
+    #[command(about = "Show a tool's parameters, defaults and allowed values")]
+    Info {
+        #[arg(help = "Tool ID or alias, e.g. PY_EXPANSE or neuron")]
+        tool: String,
+
+        #[arg(long, help = "Also show hidden and non-settable parameters")]
+        all: bool,
+    },
+}
+
+impl ToolsCommand {
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+
+        match &self.action {
+            Some(ToolsAction::Info { tool, all }) => {
+                let tool = tool_aliases::resolve(tool, &global.tool_aliases);
+                print_tool_info(&client, &tool, *all)
+            }
+            None => self.list(&client, global),
+        }
+    }
+
+    fn list(&self, client: &dyn NsgApi, global: &GlobalArgs) -> Result<()> {
+        say!("{}", "NSG Tools".bold().cyan());
+        say!("{}", "=".repeat(80).cyan());
+        say!();
+
+        let mut tools = client.list_tools()?;
+        tools.sort_by(|a, b| a.tool_id.cmp(&b.tool_id));
+
+        if let Some(filter) = &self.filter {
+            let filter = filter.to_lowercase();
+            tools.retain(|t| {
+                t.tool_id.to_lowercase().contains(&filter)
+                    || t.name.to_lowercase().contains(&filter)
+            });
+        }
+
+        if tools.is_empty() {
+            println!("{}", "No tools found".yellow());
+            return Ok(());
+        }
+
+        let width = tools.iter().map(|t| t.tool_id.len()).max().unwrap_or(0);
+        for tool in &tools {
+            println!(
+                "  {:<width$}  {}",
+                tool.tool_id.cyan(),
+                tool.name,
+                width = width
+            );
+        }
+
+        say!();
+        say!("Total: {} tool(s)", tools.len());
+        say!();
+
+        let mut aliases: BTreeMap<String, String> = tool_aliases::BUILTIN
+            .iter()
+            .map(|(alias, id)| (alias.to_string(), id.to_string()))
+            .collect();
+        aliases.extend(global.tool_aliases.clone());
+        say!("Aliases:");
+        for (alias, id) in &aliases {
+            say!("  {:<width$}  {}", alias.cyan(), id, width = width);
+        }
+        say!();
+        say!(
+            "Submit with: {}",
+            "nsg submit job.zip --tool <TOOL_ID>".cyan()
+        );
+        say!();
+
+        Ok(())
+    }
+}
+
+/// Parameter types a user can set with `--param`
+const SETTABLE_TYPES: [&str; 6] = ["Integer", "Float", "String", "Switch", "Excl", "List"];
+
+fn print_tool_info(client: &dyn NsgApi, tool: &str, all: bool) -> Result<()> {
+    let params = tool_cache::tool_parameters(client, tool)
+        .with_context(|| format!("Failed to fetch the description of tool {}", tool))?;
+
+    say!("{}", format!("NSG Tool: {}", tool).bold().cyan());
+    say!("{}", "=".repeat(80).cyan());
+    say!();
+
+    let shown: Vec<&ToolParameter> = params
+        .iter()
+        .filter(|p| all || (!p.hidden && SETTABLE_TYPES.contains(&p.param_type.as_str())))
+        .collect();
+
+    if shown.is_empty() {
+        say!("{}", "No user-settable parameters".yellow());
+        say!();
+        return Ok(());
+    }
+
+    for param in &shown {
+        let mut title = format!("{}", param.name.cyan().bold());
+        if param.mandatory {
+            title.push_str(&format!(" {}", "(required)".red()));
+        }
+        if param.hidden {
+            title.push_str(&format!(" {}", "(hidden)".dimmed()));
+        }
+        println!("{}  {}", title, param.param_type.dimmed());
+
+        if let Some(prompt) = &param.prompt {
+            println!("    {}", prompt);
+        }
+        if let Some(default) = &param.default {
+            println!("    Default: {}", default.bold());
+        }
+        if param.min.is_some() || param.max.is_some() {
+            println!(
+                "    Range:   {} to {}",
+                param.min.map(|v| v.to_string()).unwrap_or("-∞".into()),
+                param.max.map(|v| v.to_string()).unwrap_or("∞".into())
+            );
+        }
+        if !param.allowed_values.is_empty() {
+            println!("    Allowed: {}", param.allowed_values.join(", "));
+        }
+        println!();
+    }
+
+    say!("{} parameter(s)", shown.len());
+    if !all && shown.len() < params.len() {
+        say!(
+            "{}",
+            format!(
+                "{} hidden or non-settable parameter(s) not shown (use --all)",
+                params.len() - shown.len()
+            )
+            .dimmed()
+        );
+    }
+    say!();
+    say!(
+        "Set with: {}",
+        format!("nsg submit job.zip --tool {} --param NAME=VALUE", tool).cyan()
+    );
+    say!();
+
+    Ok(())
+}
@@ -0,0 +1,466 @@
+use crate::api::NsgApi;
+use crate::cache::{self, JobCache};
+use crate::client::DownloadOptions;
+use crate::commands::output::icon;
+use crate::commands::GlobalArgs;
+use crate::manifest::write_manifest;
+use crate::models::{JobStage, JobStatus, JobSummary};
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use clap::Args;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the screen is redrawn and keys are read while idle
+const TICK: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Args)]
+pub struct UiCommand {
+    #[arg(
+        short,
+        long,
+        default_value = "30",
+        value_name = "SECONDS",
+        help = "Seconds between refreshes"
+    )]
+    interval: u64,
+
+    #[arg(
+        long,
+        default_value = "20",
+        help = "Show only the N most recent jobs (0 shows all)"
+    )]
+    recent: usize,
+
+    #[arg(
+        short,
+        long,
+        default_value = "./nsg_results",
+        help = "Directory downloads go to, one subdirectory per job"
+    )]
+    output: PathBuf,
+}
+
+/// One line of the job table
+struct JobRow {
+    summary: JobSummary,
+    status: Option<JobStatus>,
+    tool: Option<String>,
+    tags: Vec<String>,
+}
+
+/// Results of work done off the UI thread
+enum Update {
+    Refreshed(Result<Vec<JobRow>, String>),
+    Downloaded(String, Result<usize, String>),
+    Cancelled(String, Result<(), String>),
+}
+
+struct App {
+    jobs: Vec<JobRow>,
+    table: TableState,
+    show_messages: bool,
+    /// Job waiting for a y/n before it is cancelled
+    confirm_cancel: Option<String>,
+    /// Last thing that happened, shown above the key help
+    notice: String,
+    refreshing: bool,
+    last_refresh: Option<(Instant, DateTime<Local>)>,
+}
+
+impl UiCommand {
+    /// Full-screen dashboard: a live job table with messages of the selected
+    /// job, and keys to download or cancel it
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        if !std::io::stdout().is_terminal() {
+            anyhow::bail!("nsg ui needs an interactive terminal; use nsg list --detailed instead");
+        }
+
+        let credentials = global.credentials()?;
+        let client: Arc<dyn NsgApi + Send + Sync> = Arc::new(global.client(credentials)?);
+
+        let mut terminal = ratatui::try_init()?;
+        let result = self.run(&mut terminal, client);
+        ratatui::restore();
+
+        result
+    }
+
+    fn run(
+        &self,
+        terminal: &mut DefaultTerminal,
+        client: Arc<dyn NsgApi + Send + Sync>,
+    ) -> Result<()> {
+        let (sender, updates) = mpsc::channel();
+        let interval = Duration::from_secs(self.interval.max(1));
+
+        let mut app = App {
+            jobs: Vec::new(),
+            table: TableState::default(),
+            show_messages: true,
+            confirm_cancel: None,
+            notice: "Loading jobs...".to_string(),
+            refreshing: false,
+            last_refresh: None,
+        };
+
+        loop {
+            let due = app
+                .last_refresh
+                .is_none_or(|(at, _)| at.elapsed() >= interval);
+            if due && !app.refreshing {
+                app.refreshing = true;
+                self.spawn_refresh(&client, &sender);
+            }
+
+            terminal.draw(|frame| app.draw(frame))?;
+
+            while let Ok(update) = updates.try_recv() {
+                app.apply(update);
+            }
+
+            if !event::poll(TICK)? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if let Some(job_id) = app.confirm_cancel.take() {
+                if key.code == KeyCode::Char('y') {
+                    app.notice = format!("Cancelling {}...", job_id);
+                    spawn_cancel(&client, &sender, job_id);
+                } else {
+                    app.notice = "Not cancelled".to_string();
+                }
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => break,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                KeyCode::Down | KeyCode::Char('j') => app.table.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => app.table.select_previous(),
+                KeyCode::Home | KeyCode::Char('g') => app.table.select_first(),
+                KeyCode::End | KeyCode::Char('G') => app.table.select_last(),
+                KeyCode::Enter | KeyCode::Char('m') => app.show_messages = !app.show_messages,
+                KeyCode::Char('r') if !app.refreshing => {
+                    app.refreshing = true;
+                    app.notice = "Refreshing...".to_string();
+                    self.spawn_refresh(&client, &sender);
+                }
+                KeyCode::Char('d') => {
+                    if let Some(row) = app.selected() {
+                        app.notice = match row.status.clone() {
+                            Some(status) if status.job_stage == JobStage::Completed => {
+                                let notice = format!("Downloading {}...", status.job_id);
+                                self.spawn_download(&client, &sender, status);
+                                notice
+                            }
+                            _ => format!("{} has not completed yet", row.summary.job_id),
+                        };
+                    }
+                }
+                KeyCode::Char('c') => {
+                    if let Some(row) = app.selected() {
+                        if row.status.as_ref().is_some_and(|s| s.terminal_stage) {
+                            app.notice = format!("{} has already finished", row.summary.job_id);
+                        } else {
+                            let job_id = row.summary.job_id.clone();
+                            app.notice = format!("Cancel {}? [y/N]", job_id);
+                            app.confirm_cancel = Some(job_id);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn spawn_refresh(&self, client: &Arc<dyn NsgApi + Send + Sync>, sender: &mpsc::Sender<Update>) {
+        let client = Arc::clone(client);
+        let sender = sender.clone();
+        let recent = self.recent;
+
+        thread::spawn(move || {
+            let result = fetch_rows(client.as_ref(), recent).map_err(|e| format!("{:#}", e));
+            let _ = sender.send(Update::Refreshed(result));
+        });
+    }
+
+    fn spawn_download(
+        &self,
+        client: &Arc<dyn NsgApi + Send + Sync>,
+        sender: &mpsc::Sender<Update>,
+        status: JobStatus,
+    ) {
+        let client = Arc::clone(client);
+        let sender = sender.clone();
+        let dir = self.output.join(&status.job_id);
+
+        thread::spawn(move || {
+            let result = (|| -> Result<usize> {
+                let files = client.download_results(
+                    &status.self_uri,
+                    &dir,
+                    &DownloadOptions::default(),
+                    &mut |_| {},
+                )?;
+                if !files.is_empty() {
+                    cache::record_download(&status.job_id, &dir);
+                    write_manifest(&dir, &status, &files)?;
+                }
+                Ok(files.len())
+            })();
+            let _ = sender.send(Update::Downloaded(
+                status.job_id,
+                result.map_err(|e| format!("{:#}", e)),
+            ));
+        });
+    }
+}
+
+fn spawn_cancel(
+    client: &Arc<dyn NsgApi + Send + Sync>,
+    sender: &mpsc::Sender<Update>,
+    job_id: String,
+) {
+    let client = Arc::clone(client);
+    let sender = sender.clone();
+
+    thread::spawn(move || {
+        let result = client.cancel_job(&job_id).map_err(|e| e.to_string());
+        if result.is_ok() {
+            cache::record_removed(&job_id, "CANCELLED");
+        }
+        let _ = sender.send(Update::Cancelled(job_id, result));
+    });
+}
+
+/// List the most recent jobs and fetch their statuses, `max_concurrency`
+/// at a time. Newest first.
+fn fetch_rows(client: &(dyn NsgApi + Sync), recent: usize) -> Result<Vec<JobRow>> {
+    let mut jobs = client.list_jobs()?;
+    if recent > 0 && jobs.len() > recent {
+        jobs.drain(0..jobs.len() - recent);
+    }
+    jobs.reverse();
+
+    let queue = Mutex::new(jobs.into_iter().enumerate());
+    let rows = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..client.max_concurrency() {
+            scope.spawn(|| loop {
+                let next = queue.lock().unwrap_or_else(|e| e.into_inner()).next();
+                let Some((index, summary)) = next else {
+                    break;
+                };
+                let status = client.get_job_status(&summary.url).ok();
+                rows.lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push((index, summary, status));
+            });
+        }
+    });
+    let mut rows = rows.into_inner().unwrap_or_else(|e| e.into_inner());
+
+    // Workers finish in any order; put the newest job back on top
+    rows.sort_by_key(|(index, _, _)| *index);
+
+    cache::record_statuses(rows.iter().filter_map(|(_, _, status)| status.as_ref()));
+    let cache = JobCache::load().unwrap_or_default();
+
+    Ok(rows
+        .into_iter()
+        .map(|(_, summary, status)| JobRow {
+            tool: cache.tool_for(&summary.job_id).map(str::to_string),
+            tags: cache
+                .get(&summary.job_id)
+                .map(|job| job.tags.clone())
+                .unwrap_or_default(),
+            summary,
+            status,
+        })
+        .collect())
+}
+
+impl App {
+    fn selected(&self) -> Option<&JobRow> {
+        self.table.selected().and_then(|i| self.jobs.get(i))
+    }
+
+    fn apply(&mut self, update: Update) {
+        match update {
+            Update::Refreshed(Ok(jobs)) => {
+                // Keep the same job selected across refreshes
+                let selected = self.selected().map(|row| row.summary.job_id.clone());
+                self.jobs = jobs;
+                let index = selected
+                    .and_then(|id| self.jobs.iter().position(|row| row.summary.job_id == id))
+                    .or(if self.jobs.is_empty() { None } else { Some(0) });
+                self.table.select(index);
+
+                self.refreshing = false;
+                self.last_refresh = Some((Instant::now(), Local::now()));
+                if self.confirm_cancel.is_none() {
+                    self.notice = format!("{} job(s)", self.jobs.len());
+                }
+            }
+            Update::Refreshed(Err(e)) => {
+                self.refreshing = false;
+                self.last_refresh = Some((Instant::now(), Local::now()));
+                self.notice = format!("Refresh failed: {}", e);
+            }
+            Update::Downloaded(job_id, Ok(0)) => {
+                self.notice = format!("{}: no output files found", job_id);
+            }
+            Update::Downloaded(job_id, Ok(count)) => {
+                self.notice = format!("{}: downloaded {} file(s)", job_id, count);
+            }
+            Update::Downloaded(job_id, Err(e)) => {
+                self.notice = format!("{}: download failed: {}", job_id, e);
+            }
+            Update::Cancelled(job_id, Ok(())) => {
+                self.notice = format!("{} cancelled", job_id);
+                // Show the new stage on the next loop iteration
+                self.last_refresh = None;
+            }
+            Update::Cancelled(job_id, Err(e)) => {
+                self.notice = format!("{}: cancel failed: {}", job_id, e);
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let messages_height = if self.show_messages { 10 } else { 0 };
+        let [table_area, messages_area, footer_area] = Layout::vertical([
+            Constraint::Min(5),
+            Constraint::Length(messages_height),
+            Constraint::Length(2),
+        ])
+        .areas(frame.area());
+
+        let refreshed = match (&self.last_refresh, self.refreshing) {
+            (_, true) => "refreshing...".to_string(),
+            (Some((_, at)), false) => format!("updated {}", at.format("%H:%M:%S")),
+            (None, false) => String::new(),
+        };
+        let title = Line::from(vec![
+            Span::styled(" NSG Jobs ", Style::new().bold().fg(Color::Cyan)),
+            Span::styled(format!("({}) ", refreshed), Style::new().dim()),
+        ]);
+
+        let rows = self.jobs.iter().map(|row| {
+            let (stage, style) = match &row.status {
+                Some(status) if status.failed => (
+                    format!("{} {} (failed)", icon("✗"), status.job_stage),
+                    stage_style(&JobStage::Failed),
+                ),
+                Some(status) => (
+                    format!("{} {}", icon(status.job_stage.icon()), status.job_stage),
+                    stage_style(&status.job_stage),
+                ),
+                None => ("? unknown".to_string(), Style::new().dim()),
+            };
+            let submitted = row
+                .status
+                .as_ref()
+                .and_then(|s| s.date_submitted.as_deref())
+                .and_then(|date| date.parse::<DateTime<Local>>().ok())
+                .map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+
+            Row::new(vec![
+                Span::styled(row.summary.job_id.clone(), Style::new().fg(Color::Cyan)),
+                Span::raw(row.tool.clone().unwrap_or_default()),
+                Span::styled(stage, style),
+                Span::raw(submitted),
+                Span::styled(row.tags.join(", "), Style::new().fg(Color::Magenta)),
+            ])
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Min(32),
+                Constraint::Length(18),
+                Constraint::Length(20),
+                Constraint::Length(16),
+                Constraint::Min(10),
+            ],
+        )
+        .header(
+            Row::new(["Job ID", "Tool", "Stage", "Submitted", "Tags"])
+                .style(Style::new().bold())
+                .bottom_margin(1),
+        )
+        .block(Block::new().borders(Borders::ALL).title(title))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+
+        if self.show_messages {
+            let lines: Vec<Line> = match self.selected().and_then(|row| row.status.as_ref()) {
+                Some(status) if !status.messages.is_empty() => status
+                    .messages
+                    .iter()
+                    .rev()
+                    .map(|message| {
+                        Line::from(vec![
+                            Span::styled(
+                                format!("[{}] ", message.stage),
+                                Style::new().fg(Color::Yellow),
+                            ),
+                            Span::raw(message.text.clone()),
+                        ])
+                    })
+                    .collect(),
+                Some(_) => vec![Line::styled("No messages", Style::new().dim())],
+                None => vec![Line::styled("No status", Style::new().dim())],
+            };
+            let title = self
+                .selected()
+                .map(|row| format!(" Messages: {} (newest first) ", row.summary.job_id))
+                .unwrap_or_else(|| " Messages ".to_string());
+            frame.render_widget(
+                Paragraph::new(lines)
+                    .wrap(Wrap { trim: false })
+                    .block(Block::new().borders(Borders::ALL).title(title)),
+                messages_area,
+            );
+        }
+
+        let keys = "↑/↓ select  enter messages  d download  c cancel  r refresh  q quit";
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::raw(self.notice.clone()),
+                Line::styled(keys, Style::new().dim()),
+            ]),
+            footer_area,
+        );
+    }
+}
+
+fn stage_style(stage: &JobStage) -> Style {
+    match stage {
+        JobStage::Completed => Style::new().fg(Color::Green).bold(),
+        JobStage::Failed => Style::new().fg(Color::Red).bold(),
+        JobStage::Running | JobStage::LoadResults => Style::new().fg(Color::Yellow).bold(),
+        stage if stage.is_queued() => Style::new().fg(Color::Cyan),
+        _ => Style::new().dim(),
+    }
+}
@@ -0,0 +1,307 @@
+use crate::cache::{self, CachedJob, JobCache};
+use crate::commands::history::parse_since;
+use crate::commands::output::icon;
+use crate::commands::{print_json, GlobalArgs};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Args)]
+pub struct UsageCommand {
+    #[arg(
+        long,
+        value_name = "WHEN",
+        value_parser = parse_since,
+        help = "Only count jobs submitted since a date (2024-05-01) or within a duration (30d)"
+    )]
+    since: Option<DateTime<Utc>>,
+
+    #[arg(
+        long,
+        help = "Only count jobs submitted to this tool (e.g. NEURON_EXPANSE)"
+    )]
+    tool: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SU",
+        help = "Your allocation in SUs (core-hours), to show how much of it is left"
+    )]
+    allocation: Option<f64>,
+
+    #[arg(
+        long,
+        help = "First fetch the status of finished jobs whose run time isn't recorded yet"
+    )]
+    refresh: bool,
+}
+
+/// What `nsg usage` reports
+#[derive(Debug, Default, Serialize)]
+struct Usage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<DateTime<Utc>>,
+    jobs: usize,
+    completed: usize,
+    failed: usize,
+    active: usize,
+    /// Finished jobs whose run time is known
+    timed: usize,
+    hours: f64,
+    /// Core-hours, taken as SUs
+    core_hours: f64,
+    /// Timed jobs without a recorded core count, counted as one core
+    assumed_one_core: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allocation: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remaining: Option<f64>,
+    tools: BTreeMap<String, ToolUsage>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ToolUsage {
+    jobs: usize,
+    failed: usize,
+    hours: f64,
+    core_hours: f64,
+}
+
+impl UsageCommand {
+    /// Summarize jobs run and compute used, from the local job database.
+    ///
+    /// NSG's REST API doesn't report usage or allocations, so SUs are
+    /// estimated as run time × nodes × cores, with run times taken from the
+    /// job messages of each job's last fetched status.
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        if self.refresh {
+            refresh(global)?;
+        }
+
+        let cache = JobCache::load()?;
+        let jobs: Vec<&CachedJob> = cache
+            .jobs
+            .values()
+            .filter(|job| {
+                self.tool.as_deref().is_none_or(|tool| {
+                    job.tool
+                        .as_deref()
+                        .is_some_and(|t| t.eq_ignore_ascii_case(tool))
+                })
+            })
+            .filter(|job| {
+                self.since
+                    .is_none_or(|since| job.submitted().is_some_and(|at| at >= since))
+            })
+            .collect();
+
+        let mut usage = Usage {
+            since: self.since,
+            allocation: self.allocation,
+            ..Default::default()
+        };
+        for job in &jobs {
+            usage.add(job);
+        }
+        usage.remaining = self
+            .allocation
+            .map(|allocation| allocation - usage.core_hours);
+
+        if global.json_output() {
+            return print_json(&usage);
+        }
+
+        print_usage(&usage);
+        Ok(())
+    }
+}
+
+impl Usage {
+    fn add(&mut self, job: &CachedJob) {
+        let tool = self
+            .tools
+            .entry(job.tool.clone().unwrap_or_else(|| "unknown".to_string()))
+            .or_default();
+
+        self.jobs += 1;
+        tool.jobs += 1;
+        if job.failed {
+            self.failed += 1;
+            tool.failed += 1;
+        } else if job.stage == "COMPLETED" {
+            self.completed += 1;
+        } else if !job.terminal && !job.stage.is_empty() {
+            self.active += 1;
+        }
+
+        let Some(run_time) = job.run_time() else {
+            return;
+        };
+        let hours = run_time.num_seconds() as f64 / 3600.0;
+        let cores = match cores(job) {
+            Some(cores) => cores,
+            None => {
+                self.assumed_one_core += 1;
+                1
+            }
+        };
+
+        self.timed += 1;
+        self.hours += hours;
+        self.core_hours += hours * cores as f64;
+        tool.hours += hours;
+        tool.core_hours += hours * cores as f64;
+    }
+}
+
+/// Nodes × cores per node the job was submitted with from this machine
+fn cores(job: &CachedJob) -> Option<u64> {
+    let value = |name: &str| job.params.get(name)?.parse::<u64>().ok();
+    match (value("number_nodes_"), value("number_cores_")) {
+        (None, None) => None,
+        (nodes, cores) => Some(nodes.unwrap_or(1) * cores.unwrap_or(1)),
+    }
+}
+
+/// Fetch and record the status of the jobs NSG lists whose run time the
+/// local database doesn't know yet
+fn refresh(global: &GlobalArgs) -> Result<()> {
+    let credentials = global.credentials()?;
+    let client = global.client(credentials)?;
+    let cache = JobCache::load()?;
+
+    let jobs: Vec<String> = client
+        .list_jobs()?
+        .into_iter()
+        .filter(|job| {
+            cache
+                .get(&job.job_id)
+                .is_none_or(|cached| cached.finished_at.is_none())
+        })
+        .map(|job| job.url)
+        .collect();
+    if jobs.is_empty() {
+        return Ok(());
+    }
+
+    say!(
+        "{} Fetching status of {} job(s)...",
+        icon("→").cyan(),
+        jobs.len()
+    );
+    let statuses: Vec<_> = client
+        .get_job_statuses(&jobs)
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+    cache::record_statuses(&statuses);
+    say!();
+
+    Ok(())
+}
+
+fn print_usage(usage: &Usage) {
+    let title = match usage.since {
+        Some(since) => format!("NSG Usage since {}", since.format("%Y-%m-%d")),
+        None => "NSG Usage".to_string(),
+    };
+    say!("{}", title.bold().cyan());
+    say!("{}", "=".repeat(80).cyan());
+    say!();
+
+    if usage.jobs == 0 {
+        say!("{}", "No matching jobs in the local job database".yellow());
+        say!();
+        say!(
+            "Jobs are recorded as they are looked at; {} fetches the ones NSG lists",
+            "nsg usage --refresh".cyan()
+        );
+        say!();
+        return;
+    }
+
+    show!(
+        "Jobs:        {} ({} completed, {} failed, {} active)",
+        usage.jobs.to_string().bold(),
+        usage.completed,
+        usage.failed,
+        usage.active
+    );
+    show!(
+        "Run time:    {:.1} h over {} job(s) with known run times",
+        usage.hours,
+        usage.timed
+    );
+    show!(
+        "Core-hours:  {} SU (estimated)",
+        format!("{:.1}", usage.core_hours).bold()
+    );
+    if let (Some(allocation), Some(remaining)) = (usage.allocation, usage.remaining) {
+        let left = format!("~{:.1} SU left", remaining);
+        show!(
+            "Allocation:  {:.1} SU, {}",
+            allocation,
+            if remaining < 0.0 {
+                left.red().bold()
+            } else {
+                left.green()
+            }
+        );
+    }
+
+    show!();
+    show!(
+        "{}",
+        format!(
+            "{:<24} {:>6} {:>7} {:>9} {:>10}",
+            "TOOL", "JOBS", "FAILED", "HOURS", "SUs"
+        )
+        .bold()
+    );
+    for (name, tool) in &usage.tools {
+        show!(
+            "{:<24} {:>6} {:>7} {:>9.1} {:>10.1}",
+            name,
+            tool.jobs,
+            tool.failed,
+            tool.hours,
+            tool.core_hours
+        );
+    }
+
+    say!();
+    say!(
+        "{}",
+        "Estimated from the local job database: NSG doesn't report usage. Run times span from"
+            .dimmed()
+    );
+    say!(
+        "{}",
+        "hand-off to the cluster until results are collected, so they include cluster queueing."
+            .dimmed()
+    );
+    if usage.timed < usage.jobs - usage.active {
+        say!(
+            "{}",
+            format!(
+                "{} finished job(s) have no recorded run time (nsg usage --refresh).",
+                usage.jobs - usage.active - usage.timed
+            )
+            .dimmed()
+        );
+    }
+    if usage.assumed_one_core > 0 {
+        say!(
+            "{}",
+            format!(
+                "{} job(s) without a recorded core count were counted as one core.",
+                usage.assumed_one_core
+            )
+            .dimmed()
+        );
+    }
+    say!();
+}
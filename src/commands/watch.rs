@@ -0,0 +1,146 @@
+use crate::api::NsgApi;
+use crate::cache;
+use crate::commands::output::icon;
+use crate::commands::GlobalArgs;
+use crate::error::JobFailed;
+use crate::models::JobStatus;
+use crate::notify::{self, JobEvent};
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+use std::time::Duration;
+
+#[derive(Debug, Args)]
+pub struct WatchCommand {
+    #[arg(help = "Job URL or Job ID")]
+    job: String,
+
+    #[arg(
+        short,
+        long,
+        default_value = "30",
+        value_name = "SECONDS",
+        help = "Seconds between status checks"
+    )]
+    interval: u64,
+
+    #[arg(long, help = "Show a desktop notification when the job finishes")]
+    notify: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "notify",
+        help = "Don't show a desktop notification, even if config.toml says to"
+    )]
+    no_notify: bool,
+}
+
+impl WatchCommand {
+    /// Poll until the job is terminal. Returns an error (exit code 1) if the
+    /// job failed, so `nsg watch JOB && nsg download JOB` does the right thing.
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let credentials = global.credentials()?;
+        let client = global.client(credentials)?;
+
+        let status = watch_job(&client, &self.job, self.interval)?;
+        job_finished(&status, self.notify && !self.no_notify);
+
+        say!();
+        if status.failed {
+            println!(
+                "{} Job {} failed",
+                icon("✗").red().bold(),
+                status.job_id.cyan()
+            );
+            return Err(JobFailed {
+                job_id: status.job_id,
+            }
+            .into());
+        }
+
+        say!(
+            "{} Job {} finished",
+            icon("✓").green().bold(),
+            status.job_id.cyan()
+        );
+        say!(
+            "   Download results: {}",
+            format!("nsg download {}", status.job_id).cyan()
+        );
+
+        Ok(())
+    }
+}
+
+/// Poll `job` every `interval` seconds until it is terminal, printing stage
+/// changes and new messages, and return its final status
+pub(crate) fn watch_job(client: &dyn NsgApi, job: &str, interval: u64) -> Result<JobStatus> {
+    hint!(
+        "{} Watching {} every {}s (Ctrl-C to stop)",
+        icon("→").cyan(),
+        job.bold(),
+        interval.max(1)
+    );
+    hint!();
+
+    let mut last_stage = None;
+    let mut seen_messages = 0;
+
+    loop {
+        let status = client.get_job_status(job)?;
+        cache::record_statuses([&status]);
+
+        if last_stage.as_ref() != Some(&status.job_stage) {
+            say!(
+                "{}  {} {}",
+                chrono::Local::now().format("%H:%M:%S").to_string().dimmed(),
+                icon(status.job_stage.icon()),
+                status.job_stage.as_str().bold()
+            );
+            last_stage = Some(status.job_stage.clone());
+        }
+
+        for message in status.messages.iter().skip(seen_messages) {
+            say!(
+                "          {} {}",
+                format!("[{}]", message.stage).dimmed(),
+                message.text
+            );
+        }
+        seen_messages = status.messages.len();
+
+        if status.terminal_stage || status.failed {
+            return Ok(status);
+        }
+
+        std::thread::sleep(Duration::from_secs(interval.max(1)));
+    }
+}
+
+/// Post that a watched job reached its final `status` to the configured
+/// notification channels, and to the desktop if `desktop`. Failing to is
+/// only worth a warning: the job itself is done.
+pub(crate) fn job_finished(status: &JobStatus, desktop: bool) {
+    let event = JobEvent::from_status(status);
+
+    match notify::deliver(&event) {
+        Ok(failed) => {
+            for (kind, label, e) in failed {
+                eprintln!(
+                    "{} Failed to notify {} {}: {:#}",
+                    icon("⚠").yellow(),
+                    kind,
+                    label,
+                    e
+                );
+            }
+        }
+        Err(e) => eprintln!("{} {:#}", icon("⚠").yellow(), e),
+    }
+
+    if desktop {
+        if let Err(e) = notify::desktop(&event) {
+            eprintln!("{} {:#}", icon("⚠").yellow(), e);
+        }
+    }
+}
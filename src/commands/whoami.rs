@@ -0,0 +1,150 @@
+use crate::agent;
+use crate::commands::output::icon;
+use crate::commands::{print_json, GlobalArgs};
+use crate::config::{CredentialSource, Credentials};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+use std::time::Instant;
+
+#[derive(Debug, Args)]
+pub struct WhoamiCommand {
+    #[arg(long, help = "Don't contact NSG to check the credentials")]
+    no_check: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Identity {
+    profile: Option<String>,
+    username: String,
+    /// Only the last characters, to tell keys apart
+    app_key: String,
+    credentials: CredentialSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agent_expires_at: Option<DateTime<Utc>>,
+    base_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    auth: Option<AuthCheck>,
+}
+
+#[derive(Debug, Serialize)]
+struct AuthCheck {
+    ok: bool,
+    millis: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl WhoamiCommand {
+    /// Show which account and endpoint the other commands would use, and
+    /// check with NSG that the credentials work. Fails (exit code 1 or the
+    /// error's own) if they don't.
+    pub fn execute(self, global: &GlobalArgs) -> Result<()> {
+        let (credentials, source) =
+            Credentials::load_profile_with_source(global.profile.as_deref())?;
+        let agent_expires_at = match source {
+            CredentialSource::Agent => agent::status().ok().map(|status| status.expires_at),
+            _ => None,
+        };
+
+        let mut identity = Identity {
+            profile: global.profile.clone(),
+            username: credentials.username.clone(),
+            app_key: mask(&credentials.app_key),
+            credentials: source,
+            agent_expires_at,
+            base_url: global.client_options().base_url(),
+            auth: None,
+        };
+
+        let mut failure = None;
+        if !self.no_check {
+            let client = global.client(credentials)?;
+            let started = Instant::now();
+            let result = client.test_connection();
+            let millis = started.elapsed().as_millis();
+            identity.auth = Some(AuthCheck {
+                ok: result.is_ok(),
+                millis,
+                error: result.as_ref().err().map(|e| format!("{:#}", e)),
+            });
+            failure = result.err();
+        }
+
+        if global.json_output() {
+            print_json(&identity)?;
+        } else {
+            print_identity(&identity);
+        }
+
+        match failure {
+            Some(e) => Err(anyhow::Error::new(e).context("Auth check failed")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// `app_key` with all but its last four characters hidden
+fn mask(app_key: &str) -> String {
+    let chars: Vec<char> = app_key.chars().collect();
+    if chars.len() <= 8 {
+        return "*".repeat(chars.len());
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}", "*".repeat(8), tail)
+}
+
+fn print_identity(identity: &Identity) {
+    say!("{}", "NSG Identity".bold().cyan());
+    say!("{}", "=".repeat(80).cyan());
+    say!();
+
+    show!(
+        "Profile:      {}",
+        identity.profile.as_deref().unwrap_or("default")
+    );
+    show!("Username:     {}", identity.username.bold());
+    show!("App key:      {}", identity.app_key);
+    let from = match &identity.credentials {
+        CredentialSource::Environment => {
+            "environment (NSG_USERNAME, NSG_PASSWORD, NSG_APP_KEY)".to_string()
+        }
+        CredentialSource::Agent => match identity.agent_expires_at {
+            Some(at) => format!(
+                "nsg agent (until {})",
+                at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+            ),
+            None => "nsg agent".to_string(),
+        },
+        CredentialSource::File {
+            path,
+            keyring,
+            encrypted,
+        } => format!(
+            "{} (password in {})",
+            path.display(),
+            if *keyring {
+                "the OS keyring"
+            } else if *encrypted {
+                "the file, encrypted"
+            } else {
+                "the file"
+            }
+        ),
+    };
+    show!("Credentials:  {}", from);
+    show!("Base URL:     {}", identity.base_url);
+
+    match &identity.auth {
+        None => show!("Auth check:   {}", "skipped".dimmed()),
+        Some(check) if check.ok => show!(
+            "Auth check:   {} accepted by NSG ({} ms)",
+            icon("✓").green().bold(),
+            check.millis
+        ),
+        Some(_) => show!("Auth check:   {} failed", icon("✗").red().bold()),
+    }
+    say!();
+}
@@ -0,0 +1,307 @@
+use crate::api::NsgApi;
+use crate::commands::output::icon;
+use crate::lint::lint_params;
+use crate::models::ToolParameter;
+use crate::tool_cache;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Parameter types the wizard asks about; files and output declarations
+/// are handled by NSG itself
+const PROMPTED_TYPES: [&str; 6] = ["Integer", "Float", "String", "Switch", "Excl", "List"];
+
+/// What the user picked, ready to hand to the normal submit path
+pub(crate) struct WizardChoices {
+    pub tool: String,
+    pub zip_file: PathBuf,
+    pub params: Vec<(String, String)>,
+}
+
+/// Walk the user through tool, parameters and input, then confirm.
+///
+/// Returns `None` if the user declines at the final summary.
+pub(crate) fn run(
+    client: &dyn NsgApi,
+    default_tool: &str,
+    default_input: Option<&Path>,
+) -> Result<Option<WizardChoices>> {
+    println!("{}", "NSG Submission Wizard".bold().cyan());
+    println!("{}", "=".repeat(80).cyan());
+    println!();
+    println!("Press Enter to accept the value in [brackets]. Ctrl-C cancels at any time.");
+    println!();
+
+    let tool = choose_tool(client, default_tool)?;
+
+    println!();
+    println!(
+        "{} Fetching parameters for {}...",
+        icon("→").cyan(),
+        tool.bold()
+    );
+    let definition = tool_cache::tool_parameters(client, &tool)
+        .with_context(|| format!("Failed to fetch the description of tool {}", tool))?;
+    let params = enter_params(&definition)?;
+
+    println!();
+    let zip_file = choose_input(default_input)?;
+
+    println!();
+    println!("{}", "Summary".bold());
+    println!("{}", "-".repeat(80).cyan());
+    println!("Tool:     {}", tool.bold());
+    println!("Input:    {}", zip_file.display().to_string().cyan());
+    if params.is_empty() {
+        println!("Params:   {}", "(tool defaults)".dimmed());
+    }
+    for (name, value) in &params {
+        println!("Param:    {} = {}", name, value.bold());
+    }
+    println!();
+
+    if !confirm("Submit this job?")? {
+        return Ok(None);
+    }
+    println!();
+
+    Ok(Some(WizardChoices {
+        tool,
+        zip_file,
+        params,
+    }))
+}
+
+fn choose_tool(client: &dyn NsgApi, default_tool: &str) -> Result<String> {
+    println!("{} Fetching available tools...", icon("→").cyan());
+
+    let mut tools = match client.list_tools() {
+        Ok(tools) => tools,
+        Err(e) => {
+            eprintln!(
+                "{} Could not fetch the tool list: {}",
+                icon("⚠").yellow(),
+                e
+            );
+            Vec::new()
+        }
+    };
+    tools.sort_by(|a, b| a.tool_id.cmp(&b.tool_id));
+
+    println!();
+    for (i, tool) in tools.iter().enumerate() {
+        println!(
+            "  {:>3}. {:<24} {}",
+            i + 1,
+            tool.tool_id.cyan(),
+            tool.name.dimmed()
+        );
+    }
+    println!();
+
+    loop {
+        let answer = prompt(&format!("Tool (number or ID) [{}]", default_tool))?;
+        let answer = answer.trim();
+
+        if answer.is_empty() {
+            return Ok(default_tool.to_string());
+        }
+
+        if let Ok(n) = answer.parse::<usize>() {
+            if let Some(tool) = n.checked_sub(1).and_then(|i| tools.get(i)) {
+                return Ok(tool.tool_id.clone());
+            }
+        }
+
+        if let Some(tool) = tools
+            .iter()
+            .find(|t| t.tool_id.eq_ignore_ascii_case(answer))
+        {
+            return Ok(tool.tool_id.clone());
+        }
+
+        // Without a tool list there is nothing to check against
+        if tools.is_empty() {
+            return Ok(answer.to_uppercase());
+        }
+
+        eprintln!("  {} Unknown tool '{}'", icon("✗").red(), answer);
+    }
+}
+
+fn enter_params(definition: &[ToolParameter]) -> Result<Vec<(String, String)>> {
+    let prompted: Vec<&ToolParameter> = definition
+        .iter()
+        .filter(|p| !p.hidden && PROMPTED_TYPES.contains(&p.param_type.as_str()))
+        .collect();
+
+    if prompted.is_empty() {
+        println!("This tool has no user-settable parameters.");
+        return Ok(Vec::new());
+    }
+
+    println!(
+        "{} parameter(s). Leave blank to use the tool's default.",
+        prompted.len()
+    );
+
+    let mut params = Vec::new();
+
+    for param in prompted {
+        println!();
+        println!(
+            "{} {}",
+            param.prompt.as_deref().unwrap_or(&param.name).bold(),
+            format!("({}, {})", param.name, param.param_type).dimmed()
+        );
+        if param.min.is_some() || param.max.is_some() {
+            println!(
+                "  Range: {} to {}",
+                param.min.map(|v| v.to_string()).unwrap_or("-∞".into()),
+                param.max.map(|v| v.to_string()).unwrap_or("∞".into())
+            );
+        }
+        if !param.allowed_values.is_empty() {
+            println!("  Allowed: {}", param.allowed_values.join(", "));
+        }
+        if param.param_type == "Switch" {
+            println!("  Enter 1 (on) or 0 (off)");
+        }
+
+        loop {
+            let label = match &param.default {
+                Some(default) => format!("  {} [{}]", param.name, default),
+                None if param.mandatory => format!("  {} (required)", param.name),
+                None => format!("  {}", param.name),
+            };
+            let value = prompt(&label)?.trim().to_string();
+
+            if value.is_empty() {
+                if param.mandatory && param.default.is_none() {
+                    eprintln!("  {} A value is required", icon("✗").red());
+                    continue;
+                }
+                break;
+            }
+
+            let candidate = [(param.name.clone(), value)];
+            let issues = lint_params(definition, &candidate);
+            if let Some(issue) = issues.first() {
+                eprintln!("  {} {}", icon("✗").red(), issue);
+                continue;
+            }
+
+            let [pair] = candidate;
+            params.push(pair);
+            break;
+        }
+    }
+
+    Ok(params)
+}
+
+fn choose_input(default_input: Option<&Path>) -> Result<PathBuf> {
+    loop {
+        let label = match default_input {
+            Some(path) => format!("Input ZIP file or directory [{}]", path.display()),
+            None => "Input ZIP file or directory".to_string(),
+        };
+        let answer = prompt(&label)?;
+        let answer = answer.trim();
+
+        let path = match (answer.is_empty(), default_input) {
+            (true, Some(path)) => path.to_path_buf(),
+            (true, None) => continue,
+            (false, _) => PathBuf::from(answer),
+        };
+
+        if path.is_file() {
+            return Ok(path);
+        }
+
+        if path.is_dir() {
+            // Canonicalize first so `.` or `dir/` still name a sibling file
+            let zip_path = path
+                .canonicalize()
+                .with_context(|| format!("Failed to read {}", path.display()))?
+                .with_extension("zip");
+            if zip_path.exists() && !confirm(&format!("Overwrite {}?", zip_path.display()))? {
+                continue;
+            }
+
+            println!("{} Zipping {}...", icon("→").cyan(), path.display());
+            zip_directory(&path, &zip_path)?;
+            println!("{} Created {}", icon("✓").green(), zip_path.display());
+            return Ok(zip_path);
+        }
+
+        eprintln!("  {} {} does not exist", icon("✗").red(), path.display());
+    }
+}
+
+/// Zip `dir` so it unpacks to a single top-level folder, as NSG expects
+fn zip_directory(dir: &Path, zip_path: &Path) -> Result<()> {
+    let root = dir
+        .canonicalize()
+        .with_context(|| format!("Failed to read {}", dir.display()))?;
+    let top = root
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("Input directory has no usable name")?
+        .to_string();
+
+    let file = File::create(zip_path)
+        .with_context(|| format!("Failed to create {}", zip_path.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut pending = vec![(root, top)];
+    while let Some((dir, prefix)) = pending.pop() {
+        zip.add_directory(prefix.as_str(), options)?;
+
+        let mut entries = std::fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|e| e.file_name());
+
+        for entry in entries {
+            let name = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+            let path = entry.path();
+
+            if path.is_dir() {
+                pending.push((path, name));
+            } else {
+                zip.start_file(name.as_str(), options)?;
+                let mut source = File::open(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let mut buffer = Vec::new();
+                source.read_to_end(&mut buffer)?;
+                zip.write_all(&buffer)?;
+            }
+        }
+    }
+
+    zip.finish()
+        .with_context(|| format!("Failed to finish {}", zip_path.display()))?;
+    Ok(())
+}
+
+fn prompt(label: &str) -> Result<String> {
+    print!("{}: ", label);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input)? == 0 {
+        anyhow::bail!("Wizard cancelled");
+    }
+    Ok(input)
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    let answer = prompt(&format!("{} [y/N]", question))?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
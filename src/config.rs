@@ -1,10 +1,57 @@
+use crate::encryption::{self, SealedPassword};
+use crate::error::NotLoggedIn;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 const CONFIG_DIR: &str = ".nsg";
 const CREDENTIALS_FILE: &str = "credentials.json";
+const PROFILES_DIR: &str = "profiles";
+/// Service name of the OS keyring entries holding passwords
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "nsg-cli";
+
+/// Credentials file chosen with `--credentials-file`, in place of the default one
+static CREDENTIALS_FILE_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `path` instead of `~/.nsg/credentials.json` (and `$NSG_CREDENTIALS_FILE`)
+/// for the default credentials from now on. Only the first call has an effect.
+pub fn set_credentials_file(path: PathBuf) {
+    let _ = CREDENTIALS_FILE_OVERRIDE.set(path);
+}
+
+/// The credentials file named with `--credentials-file` or `NSG_CREDENTIALS_FILE`, if any
+fn explicit_credentials_file() -> Option<PathBuf> {
+    match CREDENTIALS_FILE_OVERRIDE.get() {
+        Some(path) => Some(path.clone()),
+        None => std::env::var_os("NSG_CREDENTIALS_FILE")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from),
+    }
+}
+
+/// Asks for the passphrase of an encrypted credentials file
+pub type PassphrasePrompt = fn(&Path) -> Result<String>;
+
+/// How to ask for passphrases; unset in library use, where encrypted files
+/// can only be read through `nsg agent`
+static PASSPHRASE_PROMPT: OnceLock<PassphrasePrompt> = OnceLock::new();
+
+/// Ask with `prompt` for the passphrase of encrypted credentials files from
+/// now on. Only the first call has an effect.
+pub fn set_passphrase_prompt(prompt: PassphrasePrompt) {
+    let _ = PASSPHRASE_PROMPT.set(prompt);
+}
+
+/// Directory holding all nsg-cli state (`~/.nsg`)
+pub fn config_dir() -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Could not determine home directory")?;
+    Ok(home.join(CONFIG_DIR))
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Credentials {
@@ -13,6 +60,49 @@ pub struct Credentials {
     pub app_key: String,
 }
 
+/// Where [`Credentials::save_profile`] put the password
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordStore {
+    /// macOS Keychain, Secret Service or Windows Credential Manager
+    Keyring,
+    /// The credentials file itself, readable by the owner only
+    File,
+    /// The credentials file, encrypted with a passphrase
+    Encrypted,
+}
+
+/// Where [`Credentials::load_profile`] found the credentials
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "source", rename_all = "lowercase")]
+pub enum CredentialSource {
+    /// `NSG_USERNAME`, `NSG_PASSWORD` and `NSG_APP_KEY`
+    Environment,
+    /// A running `nsg agent`
+    Agent,
+    /// A credentials file, holding the password itself (possibly encrypted)
+    /// or naming its keyring entry
+    File {
+        path: PathBuf,
+        keyring: bool,
+        encrypted: bool,
+    },
+}
+
+/// Layout of a credentials file: the password is either inline (older
+/// files, or no keyring available), encrypted with a passphrase, or replaced
+/// by the name of a keyring entry
+#[derive(Serialize, Deserialize)]
+struct StoredCredentials {
+    username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+    app_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keyring_entry: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encrypted_password: Option<SealedPassword>,
+}
+
 impl Credentials {
     pub fn new(username: String, password: String, app_key: String) -> Self {
         Self {
@@ -22,64 +112,300 @@ impl Credentials {
         }
     }
 
+    /// Credentials from a running `nsg agent`, falling back to the credentials file
     pub fn load() -> Result<Self> {
-        let path = Self::credentials_path()?;
+        Self::load_profile(None)
+    }
+
+    /// Credentials of the named `profile`, or with `None` those from the
+    /// `NSG_USERNAME`/`NSG_PASSWORD`/`NSG_APP_KEY` environment variables.
+    /// Either way a running `nsg agent` holding that profile comes next,
+    /// falling back to the profile's credentials file. A credentials file
+    /// given with `--credentials-file` or `NSG_CREDENTIALS_FILE` is used
+    /// straight away.
+    pub fn load_profile(profile: Option<&str>) -> Result<Self> {
+        Ok(Self::load_profile_with_source(profile)?.0)
+    }
+
+    /// [`load_profile`](Self::load_profile), also telling where the
+    /// credentials came from
+    pub fn load_profile_with_source(profile: Option<&str>) -> Result<(Self, CredentialSource)> {
+        // A file asked for by name beats whatever else happens to be around
+        let explicit = profile.is_none() && explicit_credentials_file().is_some();
+
+        if !explicit {
+            if profile.is_none() {
+                if let Some(creds) = Self::from_env()? {
+                    return Ok((creds, CredentialSource::Environment));
+                }
+            }
+            if let Some(creds) = crate::agent::fetch_credentials(profile) {
+                return Ok((creds, CredentialSource::Agent));
+            }
+        }
+
+        let path = Self::credentials_path(profile)?;
+        let (creds, store) = Self::read_file(profile)?;
+        let source = CredentialSource::File {
+            path,
+            keyring: store == PasswordStore::Keyring,
+            encrypted: store == PasswordStore::Encrypted,
+        };
+        Ok((creds, source))
+    }
+
+    /// Credentials from the environment, for containers and CI. `None` if
+    /// none of the variables are set; an error if only some of them are.
+    pub fn from_env() -> Result<Option<Self>> {
+        let read = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        match (
+            read("NSG_USERNAME"),
+            read("NSG_PASSWORD"),
+            read("NSG_APP_KEY"),
+        ) {
+            (None, None, None) => Ok(None),
+            (Some(username), Some(password), Some(app_key)) => {
+                Ok(Some(Self::new(username, password, app_key)))
+            }
+            (username, password, app_key) => {
+                let missing: Vec<&str> = [
+                    ("NSG_USERNAME", username.is_none()),
+                    ("NSG_PASSWORD", password.is_none()),
+                    ("NSG_APP_KEY", app_key.is_none()),
+                ]
+                .into_iter()
+                .filter(|(_, missing)| *missing)
+                .map(|(name, _)| name)
+                .collect();
+
+                Err(NotLoggedIn(format!(
+                    "Incomplete credentials in the environment: {} not set",
+                    missing.join(", ")
+                ))
+                .into())
+            }
+        }
+    }
+
+    pub fn load_from_file() -> Result<Self> {
+        Self::load_profile_from_file(None)
+    }
+
+    pub fn load_profile_from_file(profile: Option<&str>) -> Result<Self> {
+        Ok(Self::read_file(profile)?.0)
+    }
+
+    /// The credentials in `profile`'s file, and where the password was kept.
+    /// An encrypted password is decrypted with a passphrase asked for through
+    /// the prompt from [`set_passphrase_prompt`].
+    fn read_file(profile: Option<&str>) -> Result<(Self, PasswordStore)> {
+        let path = Self::credentials_path(profile)?;
 
         if !path.exists() {
-            anyhow::bail!(
-                "No credentials found. Please run 'nsg login' first.\n\
+            let login = match profile {
+                Some(name) => format!("nsg login --profile {}", name),
+                None => "nsg login".to_string(),
+            };
+            return Err(NotLoggedIn(format!(
+                "No credentials found. Please run '{}' first.\n\
                  Expected credentials at: {}",
+                login,
                 path.display()
-            );
+            ))
+            .into());
         }
 
-        let content = fs::read_to_string(&path)
-            .with_context(|| format!("Failed to read credentials from {}", path.display()))?;
+        let stored = Self::read_stored(&path)?;
+        let (password, store) = match (
+            stored.password,
+            &stored.encrypted_password,
+            &stored.keyring_entry,
+        ) {
+            (Some(password), _, _) => (password, PasswordStore::File),
+            (None, Some(sealed), _) => {
+                let prompt = PASSPHRASE_PROMPT.get().with_context(|| {
+                    format!(
+                        "{} is encrypted; start 'nsg agent' to unlock it",
+                        path.display()
+                    )
+                })?;
+                let passphrase = prompt(&path)?;
+                let password = encryption::open(sealed, &passphrase)
+                    .with_context(|| format!("Failed to decrypt {}", path.display()))?;
+                (password, PasswordStore::Encrypted)
+            }
+            (None, None, Some(entry)) => {
+                let password = keyring_get(entry).with_context(|| {
+                    format!(
+                        "Failed to read the password for {} from the OS keyring; \
+                         run 'nsg login' again",
+                        stored.username
+                    )
+                })?;
+                (password, PasswordStore::Keyring)
+            }
+            (None, None, None) => anyhow::bail!("No password in {}", path.display()),
+        };
+
+        Ok((Self::new(stored.username, password, stored.app_key), store))
+    }
 
-        let creds: Credentials =
-            serde_json::from_str(&content).context("Failed to parse credentials file")?;
+    fn read_stored(path: &Path) -> Result<StoredCredentials> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read credentials from {}", path.display()))?;
 
-        Ok(creds)
+        serde_json::from_str(&content).context("Failed to parse credentials file")
     }
 
     pub fn save(&self) -> Result<()> {
-        let config_dir = Self::config_dir()?;
-
-        if !config_dir.exists() {
-            fs::create_dir_all(&config_dir).with_context(|| {
-                format!(
-                    "Failed to create config directory at {}",
-                    config_dir.display()
-                )
+        self.save_profile(None, true).map(|_| ())
+    }
+
+    /// Save as the named `profile`, or as the default credentials with `None`.
+    ///
+    /// With `use_keyring` the password goes into the OS keyring and the file
+    /// only names the entry; if no keyring is reachable (headless machines,
+    /// builds without the `keyring` feature) it is kept in the file instead.
+    pub fn save_profile(&self, profile: Option<&str>, use_keyring: bool) -> Result<PasswordStore> {
+        let entry = keyring_entry_name(profile, &self.username);
+        let store = if use_keyring && keyring_set(&entry, &self.password).is_ok() {
+            PasswordStore::Keyring
+        } else {
+            // Don't leave an older copy of the password behind in the keyring
+            let _ = keyring_delete(&entry);
+            PasswordStore::File
+        };
+
+        let stored = StoredCredentials {
+            username: self.username.clone(),
+            password: (store == PasswordStore::File).then(|| self.password.clone()),
+            app_key: self.app_key.clone(),
+            keyring_entry: (store == PasswordStore::Keyring).then_some(entry),
+            encrypted_password: None,
+        };
+        Self::write_stored(profile, &stored)?;
+
+        Ok(store)
+    }
+
+    /// Save as the named `profile` like [`save_profile`](Self::save_profile),
+    /// with the password encrypted under `passphrase` instead of in the keyring
+    pub fn save_profile_encrypted(&self, profile: Option<&str>, passphrase: &str) -> Result<()> {
+        let sealed = encryption::seal(&self.password, passphrase)?;
+        let _ = keyring_delete(&keyring_entry_name(profile, &self.username));
+
+        let stored = StoredCredentials {
+            username: self.username.clone(),
+            password: None,
+            app_key: self.app_key.clone(),
+            keyring_entry: None,
+            encrypted_password: Some(sealed),
+        };
+        Self::write_stored(profile, &stored)
+    }
+
+    fn write_stored(profile: Option<&str>, stored: &StoredCredentials) -> Result<()> {
+        let path = Self::credentials_path(profile)?;
+        let dir = path.parent().context("Invalid credentials path")?;
+
+        if !dir.exists() {
+            fs::create_dir_all(dir).with_context(|| {
+                format!("Failed to create config directory at {}", dir.display())
             })?;
         }
 
-        let path = Self::credentials_path()?;
         let content =
-            serde_json::to_string_pretty(self).context("Failed to serialize credentials")?;
+            serde_json::to_string_pretty(stored).context("Failed to serialize credentials")?;
 
         fs::write(&path, content)
             .with_context(|| format!("Failed to write credentials to {}", path.display()))?;
 
         // Set file permissions to owner-only read/write
-        Self::set_secure_permissions(&path)?;
-
-        Ok(())
+        Self::set_secure_permissions(&path)
     }
 
-    fn config_dir() -> Result<PathBuf> {
-        let home = dirs::home_dir().context("Could not determine home directory")?;
-        Ok(home.join(CONFIG_DIR))
+    /// Delete the saved credentials of `profile` (the default ones with
+    /// `None`), overwriting the file before unlinking it. Returns false if
+    /// there was nothing to delete.
+    pub fn remove_profile(profile: Option<&str>) -> Result<bool> {
+        let path = Self::credentials_path(profile)?;
+        if !path.exists() {
+            return Ok(false);
+        }
+
+        if let Some(entry) = Self::read_stored(&path)
+            .ok()
+            .and_then(|stored| stored.keyring_entry)
+        {
+            keyring_delete(&entry).context("Failed to remove the password from the OS keyring")?;
+        }
+
+        let len = fs::metadata(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?
+            .len();
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        file.write_all(&vec![0u8; len as usize])
+            .and_then(|_| file.sync_all())
+            .with_context(|| format!("Failed to overwrite {}", path.display()))?;
+        drop(file);
+
+        fs::remove_file(&path).with_context(|| format!("Failed to delete {}", path.display()))?;
+
+        Ok(true)
     }
 
-    fn credentials_path() -> Result<PathBuf> {
-        Ok(Self::config_dir()?.join(CREDENTIALS_FILE))
+    /// `~/.nsg/credentials.json` (unless moved with [`set_credentials_file`]
+    /// or `NSG_CREDENTIALS_FILE`), or `~/.nsg/profiles/<NAME>.json` for a
+    /// named profile
+    fn credentials_path(profile: Option<&str>) -> Result<PathBuf> {
+        match profile {
+            Some(name) => {
+                validate_profile_name(name)?;
+                Ok(config_dir()?
+                    .join(PROFILES_DIR)
+                    .join(format!("{}.json", name)))
+            }
+            None => match explicit_credentials_file() {
+                Some(path) => Ok(path),
+                None => Ok(config_dir()?.join(CREDENTIALS_FILE)),
+            },
+        }
     }
 
-    pub fn credentials_location() -> String {
-        Self::credentials_path()
+    pub fn credentials_location(profile: Option<&str>) -> String {
+        Self::credentials_path(profile)
             .map(|p| p.display().to_string())
-            .unwrap_or_else(|_| format!("~/{}/{}", CONFIG_DIR, CREDENTIALS_FILE))
+            .unwrap_or_else(|_| match profile {
+                Some(name) => format!("~/{}/{}/{}.json", CONFIG_DIR, PROFILES_DIR, name),
+                None => format!("~/{}/{}", CONFIG_DIR, CREDENTIALS_FILE),
+            })
+    }
+
+    /// Names of all saved profiles, sorted
+    pub fn list_profiles() -> Result<Vec<String>> {
+        let dir = config_dir()?.join(PROFILES_DIR);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_str()?.to_string())
+            })
+            .collect();
+        names.sort();
+
+        Ok(names)
     }
 
     /// Set file permissions to owner-only read/write (0600 on Unix, ACL on Windows)
@@ -147,3 +473,84 @@ impl Credentials {
         Ok(())
     }
 }
+
+/// Profile names become file names, so keep them to a safe character set
+fn validate_profile_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if !valid {
+        anyhow::bail!(
+            "Invalid profile name '{}': use letters, digits, '-' and '_' only",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+/// Keyring account for a profile's password, e.g. `default/jdoe` or
+/// `lab/jdoe`. A file named with `--credentials-file` or
+/// `NSG_CREDENTIALS_FILE` gets an account of its own, from a hash of its
+/// path (`file-3f2a9c0d1e4b5a67/jdoe`), so that two such files don't
+/// overwrite each other's password.
+fn keyring_entry_name(profile: Option<&str>, username: &str) -> String {
+    match (profile, explicit_credentials_file()) {
+        (Some(name), _) => format!("{}/{}", name, username),
+        (None, Some(path)) => format!("file-{}/{}", path_hash(&path), username),
+        (None, None) => format!("default/{}", username),
+    }
+}
+
+/// First 8 bytes of the SHA-256 of `path` made absolute, in hex. The file
+/// may not exist yet, so only its directory is resolved.
+fn path_hash(path: &Path) -> String {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let path = match (dir.canonicalize(), path.file_name()) {
+        (Ok(dir), Some(name)) => dir.join(name),
+        _ => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+    };
+
+    let digest = Sha256::digest(path.to_string_lossy().as_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_set(entry: &str, password: &str) -> Result<()> {
+    keyring::Entry::new(KEYRING_SERVICE, entry)?.set_password(password)?;
+    Ok(())
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_get(entry: &str) -> Result<String> {
+    Ok(keyring::Entry::new(KEYRING_SERVICE, entry)?.get_password()?)
+}
+
+/// Remove `entry` from the keyring; an entry that doesn't exist is not an error
+#[cfg(feature = "keyring")]
+fn keyring_delete(entry: &str) -> Result<()> {
+    match keyring::Entry::new(KEYRING_SERVICE, entry)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_set(_entry: &str, _password: &str) -> Result<()> {
+    anyhow::bail!("nsg was built without keyring support")
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_get(_entry: &str) -> Result<String> {
+    anyhow::bail!("nsg was built without keyring support")
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_delete(_entry: &str) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,104 @@
+//! Passphrase encryption of the password in a credentials file
+//! (`nsg login --encrypt`).
+//!
+//! The password is sealed with XChaCha20-Poly1305 under a key derived from
+//! the passphrase with Argon2id. Everything needed to open it again except
+//! the passphrase (salt, nonce and Argon2 costs) is stored alongside the
+//! ciphertext. `nsg agent` asks for the passphrase once and serves the
+//! opened credentials to later commands.
+
+use anyhow::{anyhow, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+
+const KDF: &str = "argon2id";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// A password sealed with a passphrase, as stored in a credentials file.
+/// Byte strings are hex-encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedPassword {
+    pub kdf: String,
+    /// Argon2 memory cost in KiB
+    pub m_cost: u32,
+    /// Argon2 iterations
+    pub t_cost: u32,
+    /// Argon2 lanes
+    pub p_cost: u32,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// Encrypt `password` under `passphrase`, with a fresh salt and nonce
+pub fn seal(password: &str, passphrase: &str) -> Result<SealedPassword> {
+    let params = Params::default();
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+
+    let key = derive_key(passphrase, &salt, params.clone())?;
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = XChaCha20Poly1305::new(&key)
+        .encrypt(&nonce, password.as_bytes())
+        .map_err(|_| anyhow!("Failed to encrypt the password"))?;
+
+    Ok(SealedPassword {
+        kdf: KDF.to_string(),
+        m_cost: params.m_cost(),
+        t_cost: params.t_cost(),
+        p_cost: params.p_cost(),
+        salt: to_hex(&salt),
+        nonce: to_hex(&nonce),
+        ciphertext: to_hex(&ciphertext),
+    })
+}
+
+/// Decrypt `sealed` with `passphrase`. A wrong passphrase and a tampered
+/// file look the same: the authentication tag doesn't match.
+pub fn open(sealed: &SealedPassword, passphrase: &str) -> Result<String> {
+    if sealed.kdf != KDF {
+        anyhow::bail!("Unsupported key derivation '{}'", sealed.kdf);
+    }
+    let params = Params::new(sealed.m_cost, sealed.t_cost, sealed.p_cost, None)
+        .map_err(|e| anyhow!("Invalid Argon2 parameters: {}", e))?;
+    let salt = from_hex(&sealed.salt)?;
+    let nonce = from_hex(&sealed.nonce)?;
+    if nonce.len() != NONCE_LEN {
+        anyhow::bail!("Invalid nonce length {}", nonce.len());
+    }
+    let ciphertext = from_hex(&sealed.ciphertext)?;
+
+    let key = derive_key(passphrase, &salt, params)?;
+    let password = XChaCha20Poly1305::new(&key)
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| anyhow!("Wrong passphrase"))?;
+
+    String::from_utf8(password).context("Decrypted password is not UTF-8")
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], params: Params) -> Result<Key> {
+    let mut key = Key::default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive a key from the passphrase: {}", e))?;
+    Ok(key)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        anyhow::bail!("Invalid hex string in credentials file");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .context("Invalid hex string in credentials file")
+}
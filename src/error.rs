@@ -0,0 +1,235 @@
+//! Error kinds that library users and scripts can tell apart, and the
+//! process exit code each one maps to
+
+use crate::models::{parse_error_response, ParamError};
+use reqwest::StatusCode;
+use std::fmt;
+
+/// Exit codes of the `nsg` binary. Anything not listed exits with
+/// [`exit::FAILURE`].
+pub mod exit {
+    /// The command did what was asked
+    pub const SUCCESS: i32 = 0;
+    /// Any error without a more specific code below
+    pub const FAILURE: i32 = 1;
+    /// No stored credentials, or NSG rejected them (HTTP 401/403)
+    pub const AUTH: i32 = 2;
+    /// NSG could not be reached: DNS, connection, TLS or timeout
+    pub const NETWORK: i32 = 3;
+    /// The job being waited on ended in a failed state
+    pub const JOB_FAILED: i32 = 4;
+    /// The job, tool or file does not exist on NSG (HTTP 404)
+    pub const NOT_FOUND: i32 = 5;
+    /// Invalid command line arguments
+    pub const USAGE: i32 = 64;
+}
+
+/// Everything [`NsgClient`](crate::NsgClient), its async counterpart and the
+/// XML parsers in [`models`](crate::models) can fail with
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum NsgError {
+    /// NSG rejected the credentials (HTTP 401/403)
+    #[error("{message}")]
+    Auth { status: StatusCode, message: String },
+    /// The job, tool or file does not exist (HTTP 404)
+    #[error("{message}")]
+    NotFound { message: String },
+    /// NSG is throttling this account (HTTP 429)
+    #[error("{message}")]
+    RateLimited { message: String },
+    /// NSG refused the submitted parameters or inputs; `errors` says which
+    /// and why
+    #[error("{message}")]
+    InvalidParams {
+        status: StatusCode,
+        message: String,
+        errors: Vec<ParamError>,
+    },
+    /// Any other non-success HTTP status
+    #[error("{message}")]
+    Http { status: StatusCode, message: String },
+    /// A response that isn't the XML NSG is documented to send
+    #[error("{0}")]
+    Parse(String),
+    /// No response at all: DNS, connection, TLS or timeout, or the
+    /// connection dropped while reading the body
+    #[error("{context}")]
+    Network {
+        context: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    /// A download ended at a different size or offset than NSG announced;
+    /// running it again resumes it
+    #[error("{0}")]
+    Transfer(String),
+    /// Reading or writing a local file failed
+    #[error("{context}")]
+    Io {
+        context: String,
+        #[source]
+        source: std::io::Error,
+    },
+    /// The request can't be made as asked, e.g. a job URL on another server
+    /// or a job that has no results yet
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// Result of library calls that talk to NSG
+pub type Result<T, E = NsgError> = std::result::Result<T, E>;
+
+impl NsgError {
+    /// The HTTP status NSG answered with, if it answered with an error
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Auth { status, .. }
+            | Self::InvalidParams { status, .. }
+            | Self::Http { status, .. } => Some(*status),
+            Self::NotFound { .. } => Some(StatusCode::NOT_FOUND),
+            Self::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn network(context: impl Into<String>, source: reqwest::Error) -> Self {
+        Self::Network {
+            context: context.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn io(context: impl Into<String>, source: std::io::Error) -> Self {
+        Self::Io {
+            context: context.into(),
+            source,
+        }
+    }
+}
+
+/// Body reads and other request failures without a more specific message
+impl From<reqwest::Error> for NsgError {
+    fn from(source: reqwest::Error) -> Self {
+        Self::network("Failed to read the response from NSG", source)
+    }
+}
+
+/// `anyhow::Context`-style messages for the failures the clients wrap:
+/// requests become [`NsgError::Network`], file access [`NsgError::Io`], and a
+/// missing value [`NsgError::Invalid`]
+pub(crate) trait ResultExt<T> {
+    fn with_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> Result<T>;
+
+    fn context(self, context: impl Into<String>) -> Result<T>
+    where
+        Self: Sized,
+    {
+        self.with_context(|| context)
+    }
+}
+
+impl<T> ResultExt<T> for std::result::Result<T, reqwest::Error> {
+    fn with_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> Result<T> {
+        self.map_err(|e| NsgError::network(context(), e))
+    }
+}
+
+impl<T> ResultExt<T> for std::result::Result<T, std::io::Error> {
+    fn with_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> Result<T> {
+        self.map_err(|e| NsgError::io(context(), e))
+    }
+}
+
+impl<T> ResultExt<T> for Option<T> {
+    fn with_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> Result<T> {
+        self.ok_or_else(|| NsgError::Invalid(context().into()))
+    }
+}
+
+/// The error for a failed `what` (e.g. "Failed to submit job"), explained by
+/// NSG's error document in `body` when there is one, or else by the raw body
+pub(crate) fn response_error(status: StatusCode, what: &str, body: &str) -> NsgError {
+    let Some(response) = parse_error_response(body) else {
+        return api_error(
+            status,
+            format!("{}: HTTP {}\nResponse: {}", what, status, body),
+        );
+    };
+
+    let message = format!("{}: {}", what, response);
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => {
+            api_error(status, message)
+        }
+        _ if !response.param_errors.is_empty() => NsgError::InvalidParams {
+            status,
+            message,
+            errors: response.param_errors,
+        },
+        _ => api_error(status, message),
+    }
+}
+
+/// The error for a non-success `status`, with `message` shown to the user
+pub(crate) fn api_error(status: StatusCode, message: String) -> NsgError {
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => NsgError::Auth { status, message },
+        StatusCode::NOT_FOUND => NsgError::NotFound { message },
+        StatusCode::TOO_MANY_REQUESTS => NsgError::RateLimited { message },
+        _ => NsgError::Http { status, message },
+    }
+}
+
+/// No credentials are stored and no agent is running
+#[derive(Debug)]
+pub struct NotLoggedIn(pub String);
+
+impl fmt::Display for NotLoggedIn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for NotLoggedIn {}
+
+/// A job that a command waited on finished unsuccessfully
+#[derive(Debug)]
+pub struct JobFailed {
+    pub job_id: String,
+}
+
+impl fmt::Display for JobFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Job {} failed", self.job_id)
+    }
+}
+
+impl std::error::Error for JobFailed {}
+
+/// The [`exit`] code for `err`, from the first recognised cause in its chain
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    for cause in err.chain() {
+        if let Some(error) = cause.downcast_ref::<NsgError>() {
+            return match error {
+                NsgError::Auth { .. } => exit::AUTH,
+                NsgError::NotFound { .. } => exit::NOT_FOUND,
+                NsgError::Network { .. } => exit::NETWORK,
+                _ => exit::FAILURE,
+            };
+        }
+        if cause.is::<NotLoggedIn>() {
+            return exit::AUTH;
+        }
+        if cause.is::<JobFailed>() {
+            return exit::JOB_FAILED;
+        }
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            if e.is_connect() || e.is_timeout() || e.is_request() {
+                return exit::NETWORK;
+            }
+        }
+    }
+
+    exit::FAILURE
+}
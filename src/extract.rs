@@ -0,0 +1,184 @@
+//! Unpacking the `output.tar.gz` most NSG tools wrap their results in
+
+use crate::filenames::{long_path, sanitize_filename};
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use tar::{Archive, EntryType};
+
+/// The archive NSG tools put their results in
+pub const RESULTS_ARCHIVE: &str = "output.tar.gz";
+
+/// What unpacking an archive did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Extracted {
+    /// Files written
+    pub files: usize,
+    /// Their total size
+    pub bytes: u64,
+    /// Entries left out: links, devices and paths leading outside the
+    /// destination
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+/// Unpack the gzipped tarball `archive` into `dest`.
+///
+/// Nothing is written outside `dest`: absolute paths and `..` are refused,
+/// and since links could point anywhere, symbolic and hard links are
+/// skipped rather than created. Each path component is made portable the
+/// same way downloaded filenames are (see
+/// [`sanitize_filename`](crate::filenames::sanitize_filename)).
+pub fn extract_tar_gz(archive: &Path, dest: &Path) -> Result<Extracted> {
+    let file = File::open(long_path(archive))
+        .with_context(|| format!("Failed to open {}", archive.display()))?;
+    extract_from(BufReader::new(file), dest, |_| true)
+        .with_context(|| format!("Failed to extract {}", archive.display()))
+}
+
+/// Unpack the entries of a gzipped tarball read from `reader` that `select`
+/// accepts (given each entry's path in the archive) into `dest`, as safely
+/// as [`extract_tar_gz`] does. The archive is read once, front to back, so
+/// it can come straight from a download.
+pub fn extract_from(
+    reader: impl Read,
+    dest: &Path,
+    select: impl Fn(&Path) -> bool,
+) -> Result<Extracted> {
+    let mut archive = Archive::new(GzDecoder::new(reader));
+    let mut extracted = Extracted::default();
+
+    for entry in archive.entries().context("Not a gzipped tar archive")? {
+        let mut entry = entry.context("Corrupt archive entry")?;
+        if !select(&entry.path()?) {
+            continue;
+        }
+        let name = entry.path()?.display().to_string();
+
+        let kind = entry.header().entry_type();
+        match kind {
+            EntryType::Regular | EntryType::Continuous | EntryType::Directory => {}
+            EntryType::Symlink
+            | EntryType::Link
+            | EntryType::Char
+            | EntryType::Block
+            | EntryType::Fifo => {
+                extracted.skipped.push(name);
+                continue;
+            }
+            // Metadata such as pax headers, not files
+            _ => continue,
+        }
+        let Some(relative) = safe_path(&entry.path()?) else {
+            extracted.skipped.push(name);
+            continue;
+        };
+
+        let target = dest.join(&relative);
+        if kind == EntryType::Directory {
+            fs::create_dir_all(long_path(&target))
+                .with_context(|| format!("Failed to create {}", target.display()))?;
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(long_path(parent))
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        entry
+            .unpack(long_path(&target))
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        extracted.files += 1;
+        extracted.bytes += entry.header().size().unwrap_or(0);
+    }
+
+    Ok(extracted)
+}
+
+/// An entry of an archive, as `nsg download --list-archive` shows it
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub size: u64,
+    /// `file`, `directory`, `symlink`, `hardlink` or `other`
+    pub kind: &'static str,
+}
+
+/// The entries of a gzipped tarball read from `reader`, in archive order
+pub fn list_entries(reader: impl Read) -> Result<Vec<ArchiveEntry>> {
+    let mut archive = Archive::new(GzDecoder::new(reader));
+    let mut entries = Vec::new();
+
+    for entry in archive.entries().context("Not a gzipped tar archive")? {
+        let entry = entry.context("Corrupt archive entry")?;
+        let kind = match entry.header().entry_type() {
+            EntryType::Regular | EntryType::Continuous => "file",
+            EntryType::Directory => "directory",
+            EntryType::Symlink => "symlink",
+            EntryType::Link => "hardlink",
+            EntryType::Char | EntryType::Block | EntryType::Fifo => "other",
+            _ => continue,
+        };
+        entries.push(ArchiveEntry {
+            path: entry.path()?.display().to_string(),
+            size: entry.header().size().unwrap_or(0),
+            kind,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Which archive entries to extract, by glob patterns on their paths.
+///
+/// NSG archives hold everything in one top-level folder, so a pattern
+/// matches either the whole path or the path below that folder:
+/// `results/*.json` picks `job_out/results/a.json`. `*` doesn't cross
+/// `/`; `**` does.
+#[derive(Debug, Clone, Default)]
+pub struct EntryFilter {
+    patterns: Vec<glob::Pattern>,
+}
+
+impl EntryFilter {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|p| {
+                glob::Pattern::new(p).with_context(|| format!("Invalid entry pattern '{}'", p))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { patterns })
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        let full = path.to_string_lossy();
+        let below_top = full.split_once('/').map(|(_, rest)| rest);
+
+        self.patterns.iter().any(|pattern| {
+            pattern.matches_with(&full, options)
+                || below_top.is_some_and(|rest| pattern.matches_with(rest, options))
+        })
+    }
+}
+
+/// `path` from an archive as a relative path that stays inside the
+/// directory it's unpacked into, or `None` if it can't be made one
+fn safe_path(path: &Path) -> Option<PathBuf> {
+    let mut safe = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => safe.push(sanitize_filename(&part.to_string_lossy())),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!safe.as_os_str().is_empty()).then_some(safe)
+}
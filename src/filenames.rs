@@ -0,0 +1,209 @@
+use crate::error::NsgError;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Written next to downloaded files whenever a remote name had to change
+pub const MAPPING_FILE: &str = ".nsg-filenames.json";
+
+/// Appended to a file while it is still downloading
+pub const PARTIAL_SUFFIX: &str = ".part";
+
+/// Longest name we produce, leaving room for a `_N` collision suffix
+const MAX_NAME_LEN: usize = 200;
+
+const RESERVED_WINDOWS_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Turn a remote filename into a single, portable path component.
+///
+/// Path separators and `..` can never escape the output directory, and the
+/// result is valid on NTFS as well as Unix filesystems:
+///
+/// - `/` and `\` collapse directories into `_`, dropping `.`/`..`/empty parts
+/// - characters Windows forbids (`<>:"|?*` and control characters) become `_`
+/// - trailing dots and spaces are trimmed
+/// - reserved device names such as `CON` or `aux.txt` get a `_` prefix
+pub fn sanitize_filename(name: &str) -> String {
+    let joined = name
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect::<Vec<_>>()
+        .join("_");
+
+    let mut clean: String = joined
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    while clean.ends_with('.') || clean.ends_with(' ') {
+        clean.pop();
+    }
+
+    if clean.len() > MAX_NAME_LEN {
+        let mut end = MAX_NAME_LEN;
+        while !clean.is_char_boundary(end) {
+            end -= 1;
+        }
+        clean.truncate(end);
+    }
+
+    let stem = clean.split('.').next().unwrap_or_default();
+    if RESERVED_WINDOWS_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        clean.insert(0, '_');
+    }
+
+    if clean.is_empty() {
+        clean.push('_');
+    }
+
+    clean
+}
+
+/// Assigns local names to a job's output files, keeping them unique even on
+/// case-insensitive filesystems, and remembers every name that changed.
+#[derive(Debug, Default)]
+pub struct FilenameMapper {
+    taken: HashSet<String>,
+    renamed: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MappingRecord<'a> {
+    /// local name -> original remote name
+    files: &'a BTreeMap<String, String>,
+}
+
+impl FilenameMapper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The local name to save `remote` under
+    pub fn local_name(&mut self, remote: &str) -> String {
+        let base = sanitize_filename(remote);
+        let mut candidate = base.clone();
+        let mut n = 2;
+
+        while self.taken.contains(&candidate.to_lowercase()) {
+            candidate = match base.rsplit_once('.') {
+                Some((stem, ext)) if !stem.is_empty() => format!("{}_{}.{}", stem, n, ext),
+                _ => format!("{}_{}", base, n),
+            };
+            n += 1;
+        }
+
+        self.taken.insert(candidate.to_lowercase());
+        if candidate != remote {
+            self.renamed.insert(candidate.clone(), remote.to_string());
+        }
+
+        candidate
+    }
+
+    pub fn renamed(&self) -> &BTreeMap<String, String> {
+        &self.renamed
+    }
+
+    /// Write [`MAPPING_FILE`] into `dir` if any name was changed
+    pub fn write_record(&self, dir: &Path) -> crate::error::Result<()> {
+        if self.renamed.is_empty() {
+            return Ok(());
+        }
+
+        let path = dir.join(MAPPING_FILE);
+        let content = serde_json::to_string_pretty(&MappingRecord {
+            files: &self.renamed,
+        })
+        .map_err(|e| NsgError::io("Failed to serialize filename mapping", e.into()))?;
+
+        std::fs::write(long_path(&path), content)
+            .map_err(|e| NsgError::io(format!("Failed to write {}", path.display()), e))
+    }
+}
+
+/// Which output files to download, by glob patterns on the remote filename.
+///
+/// No include patterns means every file; excludes always win.
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+}
+
+impl FileFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| {
+                    glob::Pattern::new(p).with_context(|| format!("Invalid file pattern '{}'", p))
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn matches(&self, filename: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches(filename)))
+            && !self.exclude.iter().any(|p| p.matches(filename))
+    }
+}
+
+/// Where `path` is kept until its download completes
+pub fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(PARTIAL_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// On Windows, turn paths that would exceed `MAX_PATH` into `\\?\` extended
+/// paths so deep output directories don't fail on NTFS. Elsewhere a no-op.
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> PathBuf {
+    const MAX_PATH: usize = 260;
+
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match std::env::current_dir() {
+            Ok(cwd) => cwd.join(path),
+            Err(_) => return path.to_path_buf(),
+        }
+    };
+
+    let as_str = absolute.to_string_lossy();
+    if as_str.len() < MAX_PATH || as_str.starts_with(r"\\?\") {
+        return path.to_path_buf();
+    }
+
+    // Extended paths are taken literally, so they must not contain `/` or `.`
+    let normalized: PathBuf = absolute.components().collect();
+    match normalized.to_string_lossy().strip_prefix(r"\\") {
+        Some(unc) => PathBuf::from(format!(r"\\?\UNC\{}", unc)),
+        None => PathBuf::from(format!(r"\\?\{}", normalized.display())),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
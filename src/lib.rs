@@ -1,7 +1,39 @@
+pub mod agent;
+pub mod api;
+pub mod archive;
+#[cfg(feature = "async")]
+pub mod async_client;
+pub mod cache;
 pub mod client;
+#[cfg(feature = "cli")]
 pub mod commands;
 pub mod config;
+pub mod encryption;
+pub mod error;
+pub mod extract;
+pub mod filenames;
+pub mod limiter;
+pub mod lint;
+pub mod manifest;
+pub mod metrics;
 pub mod models;
+pub mod notify;
+pub mod project;
+#[cfg(feature = "python")]
+mod python;
+pub mod retry;
+#[cfg(feature = "cli")]
+pub mod settings;
+pub mod templates;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod timeouts;
+pub mod tool_aliases;
+pub mod tool_cache;
 
+pub use api::NsgApi;
+#[cfg(feature = "async")]
+pub use async_client::NsgAsyncClient;
 pub use client::NsgClient;
 pub use config::Credentials;
+pub use error::NsgError;
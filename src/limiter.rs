@@ -0,0 +1,138 @@
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// A counting semaphore capping how many requests/transfers an `NsgClient`
+/// has in flight at once, no matter how many threads share it.
+#[derive(Debug)]
+pub struct ConcurrencyLimiter {
+    in_flight: Mutex<usize>,
+    released: Condvar,
+    limit: usize,
+}
+
+/// Held for the duration of one request; releases its slot when dropped
+pub struct Permit<'a> {
+    limiter: &'a ConcurrencyLimiter,
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            in_flight: Mutex::new(0),
+            released: Condvar::new(),
+            limit: limit.max(1),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Block until a slot is free
+    pub fn acquire(&self) -> Permit<'_> {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        while *in_flight >= self.limit {
+            in_flight = self
+                .released
+                .wait(in_flight)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *in_flight += 1;
+
+        Permit { limiter: self }
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self
+            .limiter
+            .in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *in_flight -= 1;
+        self.limiter.released.notify_one();
+    }
+}
+
+/// A token bucket spacing out the requests of an `NsgClient` to at most
+/// `per_second` on average, across all threads sharing it. Up to one
+/// second's worth of requests may go out at once after a quiet spell.
+#[derive(Debug)]
+pub struct RateLimiter {
+    bucket: Mutex<Bucket>,
+    per_second: Option<f64>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    /// Allow `per_second` requests per second; `None`, zero or less means
+    /// no limit
+    pub fn new(per_second: Option<f64>) -> Self {
+        let per_second = per_second.filter(|rate| rate.is_finite() && *rate > 0.0);
+        Self {
+            bucket: Mutex::new(Bucket {
+                tokens: per_second.map_or(0.0, burst),
+                refilled: Instant::now(),
+            }),
+            per_second,
+        }
+    }
+
+    pub fn per_second(&self) -> Option<f64> {
+        self.per_second
+    }
+
+    /// Take a token, returning how long to wait before using it. Tokens are
+    /// handed out in order, so waiting callers can't starve each other.
+    pub fn reserve(&self) -> Duration {
+        let Some(rate) = self.per_second else {
+            return Duration::ZERO;
+        };
+
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.refilled).as_secs_f64() * rate;
+        bucket.tokens = (bucket.tokens + refill).min(burst(rate));
+        bucket.refilled = now;
+        bucket.tokens -= 1.0;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            // A tiny rate can push the wait beyond what a Duration holds
+            Duration::try_from_secs_f64(-bucket.tokens / rate).unwrap_or(Duration::MAX)
+        }
+    }
+
+    /// Block until the next request may be sent; returns how long that took
+    pub fn acquire(&self) -> Duration {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+        wait
+    }
+}
+
+/// Requests allowed back to back at `rate` per second
+fn burst(rate: f64) -> f64 {
+    rate.max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiny_rate_saturates_the_wait() {
+        let limiter = RateLimiter::new(Some(1e-20));
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        assert_eq!(limiter.reserve(), Duration::MAX);
+    }
+}
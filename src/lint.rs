@@ -0,0 +1,180 @@
+use crate::models::ToolParameter;
+use std::fmt;
+
+/// A problem found by checking `--param` values against a tool's description
+#[derive(Debug, Clone)]
+pub enum LintIssue {
+    UnknownParameter {
+        name: String,
+        suggestion: Option<String>,
+    },
+    WrongType {
+        name: String,
+        value: String,
+        expected: &'static str,
+    },
+    OutOfRange {
+        name: String,
+        value: f64,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    NotAllowed {
+        name: String,
+        value: String,
+        allowed: Vec<String>,
+    },
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintIssue::UnknownParameter { name, suggestion } => {
+                write!(f, "Unknown parameter '{}'", name)?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, " (did you mean '{}'?)", suggestion)?;
+                }
+                Ok(())
+            }
+            LintIssue::WrongType {
+                name,
+                value,
+                expected,
+            } => write!(
+                f,
+                "Parameter '{}' must be {}, got '{}'",
+                name, expected, value
+            ),
+            LintIssue::OutOfRange {
+                name,
+                value,
+                min,
+                max,
+            } => {
+                write!(f, "Parameter '{}' = {} is out of range", name, value)?;
+                match (min, max) {
+                    (Some(min), Some(max)) => write!(f, " ({} to {})", min, max),
+                    (Some(min), None) => write!(f, " (must be >= {})", min),
+                    (None, Some(max)) => write!(f, " (must be <= {})", max),
+                    (None, None) => Ok(()),
+                }
+            }
+            LintIssue::NotAllowed {
+                name,
+                value,
+                allowed,
+            } => write!(
+                f,
+                "Parameter '{}' does not accept '{}' (allowed: {})",
+                name,
+                value,
+                allowed.join(", ")
+            ),
+        }
+    }
+}
+
+/// Check `(name, value)` pairs against a tool's declared parameters.
+///
+/// Names are vparam names as declared by the tool, e.g. `runtime_`.
+pub fn lint_params(definition: &[ToolParameter], params: &[(String, String)]) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+
+    for (name, value) in params {
+        let Some(param) = definition.iter().find(|p| &p.name == name) else {
+            issues.push(LintIssue::UnknownParameter {
+                name: name.clone(),
+                suggestion: closest_match(name, definition.iter().map(|p| p.name.as_str())),
+            });
+            continue;
+        };
+
+        if let Some(issue) = lint_value(param, value) {
+            issues.push(issue);
+        }
+    }
+
+    issues
+}
+
+fn lint_value(param: &ToolParameter, value: &str) -> Option<LintIssue> {
+    let wrong_type = |expected| LintIssue::WrongType {
+        name: param.name.clone(),
+        value: value.to_string(),
+        expected,
+    };
+
+    match param.param_type.as_str() {
+        "Integer" => {
+            let Ok(number) = value.trim().parse::<i64>() else {
+                return Some(wrong_type("an integer"));
+            };
+            check_range(param, number as f64)
+        }
+        "Float" => {
+            let Ok(number) = value.trim().parse::<f64>() else {
+                return Some(wrong_type("a number"));
+            };
+            check_range(param, number)
+        }
+        "Switch" => (!matches!(value, "0" | "1")).then(|| wrong_type("0 or 1")),
+        "Excl" | "List" if !param.allowed_values.is_empty() => {
+            let values: Vec<&str> = if param.param_type == "List" {
+                value.split(',').map(str::trim).collect()
+            } else {
+                vec![value]
+            };
+            values
+                .iter()
+                .find(|v| !param.allowed_values.iter().any(|a| a == *v))
+                .map(|v| LintIssue::NotAllowed {
+                    name: param.name.clone(),
+                    value: v.to_string(),
+                    allowed: param.allowed_values.clone(),
+                })
+        }
+        _ => None,
+    }
+}
+
+fn check_range(param: &ToolParameter, value: f64) -> Option<LintIssue> {
+    let below = param.min.is_some_and(|min| value < min);
+    let above = param.max.is_some_and(|max| value > max);
+
+    (below || above).then(|| LintIssue::OutOfRange {
+        name: param.name.clone(),
+        value,
+        min: param.min,
+        max: param.max,
+    })
+}
+
+/// The candidate closest to `name` by edit distance, if it is plausibly a typo
+pub(crate) fn closest_match<'a>(
+    name: &str,
+    candidates: impl Iterator<Item = &'a str>,
+) -> Option<String> {
+    let threshold = (name.len() / 3).max(2);
+
+    candidates
+        .map(|c| (edit_distance(&name.to_lowercase(), &c.to_lowercase()), c))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c.to_string())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        prev = current;
+    }
+
+    prev[b.len()]
+}
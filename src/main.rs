@@ -1,6 +1,9 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use colored::Colorize;
 use nsg_cli::commands::*;
+use nsg_cli::error::{exit, exit_code};
+use nsg_cli::project::ProjectConfig;
+use nsg_cli::settings::Settings;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -11,6 +14,9 @@ use nsg_cli::commands::*;
                   REST API. Submit jobs, check status, and download results from NSG HPC clusters."
 )]
 struct Cli {
+    #[command(flatten)]
+    global: GlobalArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -20,6 +26,14 @@ enum Commands {
     #[command(about = "Login and save NSG credentials")]
     Login(LoginCommand),
 
+    #[command(about = "Delete stored credentials")]
+    Logout(LogoutCommand),
+
+    #[command(
+        about = "Show the account, credentials and endpoint in use, and check them with NSG"
+    )]
+    Whoami(WhoamiCommand),
+
     #[command(about = "List all jobs for the authenticated user")]
     List(ListCommand),
 
@@ -29,21 +43,195 @@ enum Commands {
     #[command(about = "Submit a new job to NSG")]
     Submit(SubmitCommand),
 
+    #[command(about = "Save and manage named sets of submit options")]
+    Template(TemplateCommand),
+
+    #[command(about = "Submit a past job again, optionally with changed parameters")]
+    Resubmit(ResubmitCommand),
+
+    #[command(about = "Poll a job until it finishes, printing stage changes and messages")]
+    Watch(WatchCommand),
+
     #[command(about = "Download results from a completed job")]
     Download(DownloadCommand),
+
+    #[command(about = "Mirror a job's output files into a directory, fetching only what changed")]
+    Sync(SyncCommand),
+
+    #[command(about = "List the tools available on NSG")]
+    Tools(ToolsCommand),
+
+    #[command(about = "Cancel a queued or running job")]
+    Cancel(CancelCommand),
+
+    #[command(about = "Delete finished jobs and their results from the server")]
+    Delete(DeleteCommand),
+
+    #[command(about = "Run an in-memory credential agent for other nsg commands")]
+    Agent(AgentCommand),
+
+    #[command(about = "Follow stdout/stderr of a running job")]
+    Tail(TailCommand),
+
+    #[command(about = "Print a job's stdout/stderr without downloading its results")]
+    Logs(LogsCommand),
+
+    #[command(about = "Open a job's page in the default browser")]
+    Open(OpenCommand),
+
+    #[command(about = "Generate a self-contained HTML report for a job")]
+    Report(ReportCommand),
+
+    #[command(about = "Export a job record or a portable, verifiable job bundle")]
+    Export(ExportCommand),
+
+    #[command(about = "Show all jobs recorded locally, including ones gone from NSG")]
+    History(HistoryCommand),
+
+    #[command(about = "Estimate compute used (SUs) and what's left of an allocation")]
+    Usage(UsageCommand),
+
+    #[cfg(feature = "tui")]
+    #[command(about = "Live dashboard of your jobs, with keys to download or cancel")]
+    Ui(UiCommand),
+
+    #[command(about = "Manage local tags attached to jobs")]
+    Tag(TagCommand),
+
+    #[command(about = "Manage local notes attached to jobs")]
+    Note(NoteCommand),
+
+    #[command(about = "Print a terse job summary for shell prompts (cache only)")]
+    Prompt(PromptCommand),
+
+    #[command(about = "Inspect and test notification channels")]
+    Notify(NotifyCommand),
+
+    #[command(about = "Manage opt-in anonymous usage metrics")]
+    Metrics(MetricsCommand),
+
+    #[command(about = "Get and set defaults in ~/.nsg/config.toml")]
+    Config(ConfigCommand),
+
+    #[command(about = "Print a shell completion script")]
+    Completions(CompletionsCommand),
+
+    #[command(hide = true, about = "Generate roff man pages (for packagers)")]
+    Man(ManCommand),
+}
+
+impl Commands {
+    fn name(&self) -> &'static str {
+        match self {
+            Commands::Login(_) => "login",
+            Commands::Logout(_) => "logout",
+            Commands::Whoami(_) => "whoami",
+            Commands::List(_) => "list",
+            Commands::Status(_) => "status",
+            Commands::Submit(_) => "submit",
+            Commands::Resubmit(_) => "resubmit",
+            Commands::Template(_) => "template",
+            Commands::Watch(_) => "watch",
+            Commands::Download(_) => "download",
+            Commands::Sync(_) => "sync",
+            Commands::Tools(_) => "tools",
+            Commands::Cancel(_) => "cancel",
+            Commands::Delete(_) => "delete",
+            Commands::Agent(_) => "agent",
+            Commands::Tail(_) => "tail",
+            Commands::Logs(_) => "logs",
+            Commands::Open(_) => "open",
+            Commands::Report(_) => "report",
+            Commands::Export(_) => "export",
+            Commands::History(_) => "history",
+            Commands::Usage(_) => "usage",
+            #[cfg(feature = "tui")]
+            Commands::Ui(_) => "ui",
+            Commands::Tag(_) => "tag",
+            Commands::Note(_) => "note",
+            Commands::Prompt(_) => "prompt",
+            Commands::Notify(_) => "notify",
+            Commands::Metrics(_) => "metrics",
+            Commands::Config(_) => "config",
+            Commands::Completions(_) => "completions",
+            Commands::Man(_) => "man",
+        }
+    }
 }
 
 fn main() {
-    let cli = Cli::parse();
+    // config.toml and then .nsg.toml values become argument defaults, so
+    // flags still override them
+    let (settings, project) = Settings::load()
+        .and_then(|settings| Ok((settings, ProjectConfig::discover()?)))
+        .unwrap_or_else(|e| {
+            eprintln!("{} {:#}", "Error:".red().bold(), e);
+            std::process::exit(exit::FAILURE);
+        });
+    let mut command = settings.apply(Cli::command());
+    if let Some(project) = &project {
+        command = project.apply(command);
+    }
+    let mut cli = command
+        .try_get_matches()
+        .and_then(|matches| Cli::from_arg_matches(&matches))
+        .unwrap_or_else(|e| {
+            // clap's own code for bad arguments (2) is taken by auth failures
+            if e.use_stderr() {
+                let _ = e.print();
+                std::process::exit(exit::USAGE);
+            }
+            e.exit()
+        });
+    cli.global.project = project;
+    cli.global.tool_aliases = settings.tool_aliases;
+    if let Some(path) = &cli.global.credentials_file {
+        nsg_cli::config::set_credentials_file(path.clone());
+    }
+    nsg_cli::config::set_passphrase_prompt(login::prompt_passphrase);
+    if let Some(name) = &cli.global.profile {
+        nsg_cli::cache::set_profile(name.clone());
+    }
+    output::configure(&cli.global);
+    let command_name = cli.command.name();
 
     let result = match cli.command {
-        Commands::Login(cmd) => cmd.execute(),
-        Commands::List(cmd) => cmd.execute(),
-        Commands::Status(cmd) => cmd.execute(),
-        Commands::Submit(cmd) => cmd.execute(),
-        Commands::Download(cmd) => cmd.execute(),
+        Commands::Login(cmd) => cmd.execute(&cli.global),
+        Commands::Logout(cmd) => cmd.execute(&cli.global),
+        Commands::Whoami(cmd) => cmd.execute(&cli.global),
+        Commands::List(cmd) => cmd.execute(&cli.global),
+        Commands::Status(cmd) => cmd.execute(&cli.global),
+        Commands::Submit(cmd) => cmd.execute(&cli.global),
+        Commands::Resubmit(cmd) => cmd.execute(&cli.global),
+        Commands::Template(cmd) => cmd.execute(&cli.global),
+        Commands::Watch(cmd) => cmd.execute(&cli.global),
+        Commands::Download(cmd) => cmd.execute(&cli.global),
+        Commands::Sync(cmd) => cmd.execute(&cli.global),
+        Commands::Tools(cmd) => cmd.execute(&cli.global),
+        Commands::Cancel(cmd) => cmd.execute(&cli.global),
+        Commands::Delete(cmd) => cmd.execute(&cli.global),
+        Commands::Agent(cmd) => cmd.execute(&cli.global),
+        Commands::Tail(cmd) => cmd.execute(&cli.global),
+        Commands::Logs(cmd) => cmd.execute(&cli.global),
+        Commands::Open(cmd) => cmd.execute(&cli.global),
+        Commands::Report(cmd) => cmd.execute(&cli.global),
+        Commands::Export(cmd) => cmd.execute(&cli.global),
+        Commands::History(cmd) => cmd.execute(&cli.global),
+        Commands::Usage(cmd) => cmd.execute(&cli.global),
+        #[cfg(feature = "tui")]
+        Commands::Ui(cmd) => cmd.execute(&cli.global),
+        Commands::Tag(cmd) => cmd.execute(),
+        Commands::Note(cmd) => cmd.execute(),
+        Commands::Prompt(cmd) => cmd.execute(),
+        Commands::Notify(cmd) => cmd.execute(),
+        Commands::Metrics(cmd) => cmd.execute(),
+        Commands::Config(cmd) => cmd.execute(),
+        Commands::Completions(cmd) => cmd.execute(Cli::command()),
+        Commands::Man(cmd) => cmd.execute(Cli::command()),
     };
 
+    nsg_cli::metrics::record_command(command_name, result.as_ref().err());
+
     if let Err(e) = result {
         eprintln!();
         eprintln!("{} {}", "Error:".red().bold(), e);
@@ -54,6 +242,10 @@ fn main() {
         }
 
         eprintln!();
-        std::process::exit(1);
+        if command_name != "login" && login::is_rejected(&e) {
+            login::offer_relogin(&cli.global);
+            eprintln!();
+        }
+        std::process::exit(exit_code(&e));
     }
 }
@@ -0,0 +1,68 @@
+use crate::cache::JobCache;
+use crate::filenames::long_path;
+use crate::models::{tool_from_job_id, DownloadedFile, JobStatus};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Integrity record written into a download directory
+pub const MANIFEST_FILE: &str = "MANIFEST.sha256";
+
+/// Write [`MANIFEST_FILE`] into `dir` for the files of one download.
+///
+/// The file is in `sha256sum` format, so `sha256sum -c MANIFEST.sha256`
+/// checks it; job ID, tool and dates go in `#` comment lines at the top.
+pub fn write_manifest(dir: &Path, status: &JobStatus, files: &[DownloadedFile]) -> Result<PathBuf> {
+    let tool = JobCache::load()
+        .ok()
+        .and_then(|cache| cache.tool_for(&status.job_id).map(str::to_string))
+        .or_else(|| tool_from_job_id(&status.job_id).map(str::to_string));
+
+    let mut manifest = String::new();
+    let _ = writeln!(manifest, "# NSG job:    {}", status.job_id);
+    let _ = writeln!(manifest, "# Tool:       {}", tool.as_deref().unwrap_or("-"));
+    let _ = writeln!(
+        manifest,
+        "# Submitted:  {}",
+        status.date_submitted.as_deref().unwrap_or("-")
+    );
+    let _ = writeln!(manifest, "# Downloaded: {}", Utc::now().to_rfc3339());
+    let _ = writeln!(
+        manifest,
+        "# Written by: {}",
+        crate::client::default_user_agent()
+    );
+
+    for file in files {
+        let _ = writeln!(manifest, "{}  {}", sha256_file(&file.path)?, file.filename);
+    }
+
+    let path = dir.join(MANIFEST_FILE);
+    std::fs::write(long_path(&path), manifest)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(path)
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(long_path(path))
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let bytes_read = file
+            .read(&mut buffer)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
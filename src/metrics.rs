@@ -0,0 +1,174 @@
+use crate::config::config_dir;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const METRICS_FILE: &str = "metrics.json";
+const UPLOAD_INTERVAL_DAYS: i64 = 7;
+
+/// Opt-in, anonymous usage counters kept in `~/.nsg/metrics.json`.
+///
+/// Nothing is recorded until the user runs `nsg metrics enable`. Only
+/// subcommand names and coarse error categories are counted - never job IDs,
+/// usernames, file names or parameters.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UsageMetrics {
+    pub enabled: bool,
+    pub upload_url: Option<String>,
+    pub last_upload: Option<DateTime<Utc>>,
+    pub since: Option<DateTime<Utc>>,
+    pub commands: BTreeMap<String, u64>,
+    pub errors: BTreeMap<String, u64>,
+}
+
+/// The aggregate payload sent to `upload_url`
+#[derive(Debug, Serialize)]
+struct UploadPayload<'a> {
+    version: &'a str,
+    os: &'a str,
+    since: Option<DateTime<Utc>>,
+    commands: &'a BTreeMap<String, u64>,
+    errors: &'a BTreeMap<String, u64>,
+}
+
+impl UsageMetrics {
+    pub fn load() -> Result<Self> {
+        let path = Self::metrics_path()?;
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read metrics from {}", path.display()))?;
+
+        serde_json::from_str(&content).context("Failed to parse metrics file")
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let dir = config_dir()?;
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create config directory at {}", dir.display()))?;
+
+        let path = Self::metrics_path()?;
+        let content = serde_json::to_string_pretty(self).context("Failed to serialize metrics")?;
+
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to write metrics to {}", path.display()))
+    }
+
+    pub fn metrics_path() -> Result<PathBuf> {
+        Ok(config_dir()?.join(METRICS_FILE))
+    }
+
+    /// Count one invocation of `command`, and its error category if it failed
+    pub fn record(&mut self, command: &str, error_category: Option<&str>) {
+        if self.since.is_none() {
+            self.since = Some(Utc::now());
+        }
+
+        *self.commands.entry(command.to_string()).or_insert(0) += 1;
+
+        if let Some(category) = error_category {
+            *self.errors.entry(category.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.commands.clear();
+        self.errors.clear();
+        self.since = None;
+        self.last_upload = None;
+    }
+
+    pub fn upload_due(&self) -> bool {
+        self.upload_url.is_some()
+            && self
+                .last_upload
+                .is_none_or(|last| Utc::now() - last >= Duration::days(UPLOAD_INTERVAL_DAYS))
+    }
+
+    /// POST the aggregate counters to the configured upload URL
+    pub fn upload(&mut self) -> Result<()> {
+        let url = self
+            .upload_url
+            .clone()
+            .context("No metrics upload URL configured")?;
+
+        let payload = UploadPayload {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            since: self.since,
+            commands: &self.commands,
+            errors: &self.errors,
+        };
+
+        let response = reqwest::blocking::Client::builder()
+            .user_agent(crate::client::default_user_agent())
+            .timeout(std::time::Duration::from_secs(5))
+            .build()
+            .context("Failed to create HTTP client")?
+            .post(&url)
+            .json(&payload)
+            .send()
+            .context("Failed to upload usage metrics")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to upload usage metrics: HTTP {}", response.status());
+        }
+
+        self.last_upload = Some(Utc::now());
+        Ok(())
+    }
+}
+
+/// Record a finished command if (and only if) the user has opted in.
+///
+/// Failures here are swallowed: metrics must never break the actual command.
+pub fn record_command(command: &str, error: Option<&anyhow::Error>) {
+    let Ok(mut metrics) = UsageMetrics::load() else {
+        return;
+    };
+
+    if !metrics.enabled {
+        return;
+    }
+
+    metrics.record(command, error.map(error_category));
+
+    if metrics.upload_due() {
+        let _ = metrics.upload();
+    }
+
+    let _ = metrics.save();
+}
+
+/// Map an error onto a coarse, non-identifying category
+pub fn error_category(error: &anyhow::Error) -> &'static str {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return if e.is_timeout() { "timeout" } else { "network" };
+        }
+        if cause.downcast_ref::<std::io::Error>().is_some() {
+            return "io";
+        }
+    }
+
+    let message = error.to_string();
+    if message.contains("No credentials found") {
+        "credentials"
+    } else if message.contains("Authentication failed") || message.contains("HTTP 401") {
+        "auth"
+    } else if message.contains("HTTP 404") {
+        "not_found"
+    } else if message.contains("HTTP ") {
+        "http"
+    } else if message.contains("XML parse error") {
+        "parse"
+    } else {
+        "other"
+    }
+}
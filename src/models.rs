@@ -1,26 +1,128 @@
-use anyhow::Result;
+use crate::error::{NsgError, Result};
 use quick_xml::events::Event;
 use quick_xml::Reader;
+use serde::Serialize;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct JobSummary {
     pub job_id: String,
     pub url: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct JobStatus {
     pub job_id: String,
-    pub job_stage: String,
+    pub job_stage: JobStage,
     pub failed: bool,
     pub date_submitted: Option<String>,
     pub self_uri: String,
     pub results_uri: Option<String>,
+    pub working_dir_uri: Option<String>,
+    pub terminal_stage: bool,
     pub messages: Vec<JobMessage>,
+    pub metadata: Vec<(String, String)>,
 }
 
-#[derive(Debug, Clone)]
+/// Where a job is in NSG's pipeline. Variants are declared in the order a
+/// job passes through them, so stages can be compared with `<`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JobStage {
+    Queue,
+    CommandRendering,
+    InputStaging,
+    Submitted,
+    Running,
+    LoadResults,
+    Completed,
+    Failed,
+    /// A stage this version doesn't know, kept as NSG spelled it
+    Unknown(String),
+}
+
+impl JobStage {
+    /// The stage as NSG spells it, e.g. `INPUTSTAGING`
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Queue => "QUEUE",
+            Self::CommandRendering => "COMMANDRENDERING",
+            Self::InputStaging => "INPUTSTAGING",
+            Self::Submitted => "SUBMITTED",
+            Self::Running => "RUNNING",
+            Self::LoadResults => "LOAD_RESULTS",
+            Self::Completed => "COMPLETED",
+            Self::Failed => "FAILED",
+            Self::Unknown(stage) => stage,
+        }
+    }
+
+    /// The job won't change stage any more
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed)
+    }
+
+    /// Accepted by NSG but not yet running on the cluster
+    pub fn is_queued(&self) -> bool {
+        matches!(
+            self,
+            Self::Queue | Self::CommandRendering | Self::InputStaging | Self::Submitted
+        )
+    }
+
+    /// Icon for the stage: `✓`, `✗`, `⟳` (running), `⏳` (queued) or `?`
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Self::Completed => "✓",
+            Self::Failed => "✗",
+            Self::Running | Self::LoadResults => "⟳",
+            stage if stage.is_queued() => "⏳",
+            _ => "?",
+        }
+    }
+}
+
+impl std::str::FromStr for JobStage {
+    type Err = std::convert::Infallible;
+
+    fn from_str(stage: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match stage.trim().to_ascii_uppercase().as_str() {
+            "QUEUE" => Self::Queue,
+            "COMMANDRENDERING" => Self::CommandRendering,
+            "INPUTSTAGING" => Self::InputStaging,
+            "SUBMITTED" => Self::Submitted,
+            "RUN" | "RUNNING" => Self::Running,
+            "LOAD_RESULTS" => Self::LoadResults,
+            "COMPLETED" => Self::Completed,
+            "FAILED" => Self::Failed,
+            _ => Self::Unknown(stage.to_string()),
+        })
+    }
+}
+
+impl From<&str> for JobStage {
+    fn from(stage: &str) -> Self {
+        let Ok(stage) = stage.parse();
+        stage
+    }
+}
+
+impl std::fmt::Display for JobStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Serialized as NSG's spelling, so JSON output keeps plain strings
+impl Serialize for JobStage {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct JobMessage {
     pub stage: String,
     pub text: String,
@@ -31,69 +133,171 @@ pub struct JobMessage {
 pub struct OutputFile {
     pub filename: String,
     pub download_uri: String,
-    pub size: u64,
+    /// The length NSG advertised, if it gave one. Without it a download
+    /// can't be checked for completeness.
+    pub size: Option<u64>,
 }
 
+/// An entry from the tool list (`/tool`)
 #[derive(Debug, Clone)]
+pub struct ToolInfo {
+    pub tool_id: String,
+    pub name: String,
+}
+
+/// A single parameter from a tool's PISE XML description
+#[derive(Debug, Clone)]
+pub struct ToolParameter {
+    pub name: String,
+    pub param_type: String,
+    pub prompt: Option<String>,
+    pub default: Option<String>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub allowed_values: Vec<String>,
+    pub mandatory: bool,
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct DownloadedFile {
+    /// Name on disk, after sanitization
     pub filename: String,
+    /// Name as reported by NSG
+    pub remote_filename: String,
     pub path: PathBuf,
     pub size: u64,
+    /// Already present locally with the right size, so not fetched again
+    pub up_to_date: bool,
+}
+
+/// The error document NSG sends with a rejected request
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorResponse {
+    /// Summary meant for users, e.g. "Form validation error."
+    pub display_message: String,
+    /// Developer-facing detail
+    pub message: Option<String>,
+    /// CIPRES error code (e.g. 5 for form validation)
+    pub code: Option<i32>,
+    /// One entry per rejected parameter or input
+    pub param_errors: Vec<ParamError>,
+}
+
+/// Why NSG rejected one submitted field
+#[derive(Debug, Clone, Serialize)]
+pub struct ParamError {
+    /// Field name as given to `--param`, i.e. without the `vparam.` prefix
+    pub param: String,
+    pub error: String,
+}
+
+impl std::fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.display_message)?;
+        for param_error in &self.param_errors {
+            write!(
+                f,
+                "\n  Parameter {}: {}",
+                param_error.param, param_error.error
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Extract the tool ID from an NSG job handle (`NGBW-JOB-<TOOL>-<hash>`)
+pub fn tool_from_job_id(job_id: &str) -> Option<&str> {
+    job_id
+        .strip_prefix("NGBW-JOB-")
+        .and_then(|rest| rest.rsplit_once('-'))
+        .map(|(tool, _)| tool)
+        .filter(|tool| !tool.is_empty())
 }
 
 pub fn parse_job_list(xml: &str) -> Result<Vec<JobSummary>> {
+    Ok(parse_job_page(xml)?.jobs)
+}
+
+/// One response of the job list: its jobs, and where the list continues
+/// when NSG splits it into pages
+#[derive(Debug, Clone, Default)]
+pub struct JobPage {
+    pub jobs: Vec<JobSummary>,
+    /// URL of the next page, from a link whose `rel` is `next`
+    pub next: Option<String>,
+}
+
+/// Parse one page of the job list. Jobs are the `selfUri` links; a link
+/// with `<rel>next</rel>` anywhere in the document points to the next page.
+pub fn parse_job_page(xml: &str) -> Result<JobPage> {
     let mut reader = Reader::from_str(xml);
     reader.config_mut().trim_text(true);
 
-    let mut jobs = Vec::new();
+    let mut page = JobPage::default();
     let mut buf = Vec::new();
+    let mut path: Vec<String> = Vec::new();
     let mut current_url = None;
     let mut current_title = None;
-    let mut in_self_uri = false;
+    let mut current_rel: Option<String> = None;
 
     loop {
         match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(e)) if e.name().as_ref() == b"selfUri" => {
-                in_self_uri = true;
-            }
-            Ok(Event::End(e)) if e.name().as_ref() == b"selfUri" => {
-                in_self_uri = false;
-                if let (Some(url), Some(title)) = (current_url.take(), current_title.take()) {
-                    jobs.push(JobSummary { job_id: title, url });
+            Ok(Event::Start(e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if !is_link_field(&name) {
+                    current_url = None;
+                    current_title = None;
+                    current_rel = None;
                 }
+                path.push(name);
             }
-            Ok(Event::Start(e)) if in_self_uri && e.name().as_ref() == b"url" => {
-                if let Ok(Event::Text(t)) = reader.read_event_into(&mut buf) {
-                    current_url = reader
-                        .decoder()
-                        .decode(t.as_ref())
-                        .ok()
-                        .map(|s| s.to_string());
+            Ok(Event::End(e)) => {
+                let name = path.pop().unwrap_or_default();
+                if is_link_field(&name) {
+                    // Still collecting the fields of the enclosing link
+                } else if e.name().as_ref() == b"selfUri" {
+                    if let (Some(url), Some(title)) = (current_url.take(), current_title.take()) {
+                        page.jobs.push(JobSummary { job_id: title, url });
+                    }
+                } else if current_rel
+                    .take()
+                    .is_some_and(|rel| rel.eq_ignore_ascii_case("next"))
+                {
+                    page.next = current_url.take();
                 }
             }
-            Ok(Event::Start(e)) if in_self_uri && e.name().as_ref() == b"title" => {
-                if let Ok(Event::Text(t)) = reader.read_event_into(&mut buf) {
-                    current_title = reader
-                        .decoder()
-                        .decode(t.as_ref())
-                        .ok()
-                        .map(|s| s.to_string());
+            Ok(Event::Text(t)) => {
+                let text = reader
+                    .decoder()
+                    .decode(t.as_ref())
+                    .ok()
+                    .map(|s| s.to_string());
+                match path.last().map(String::as_str) {
+                    Some("url") => current_url = text,
+                    Some("title") => current_title = text,
+                    Some("rel") => current_rel = text,
+                    _ => {}
                 }
             }
             Ok(Event::Eof) => break,
             Err(e) => {
-                return Err(anyhow::anyhow!(
+                return Err(NsgError::Parse(format!(
                     "XML parse error at position {}: {}",
                     reader.buffer_position(),
                     e
-                ))
+                )))
             }
             _ => {}
         }
         buf.clear();
     }
 
-    Ok(jobs)
+    Ok(page)
+}
+
+fn is_link_field(name: &str) -> bool {
+    matches!(name, "url" | "title" | "rel")
 }
 
 pub fn parse_job_status(xml: &str) -> Result<JobStatus> {
@@ -102,19 +306,25 @@ pub fn parse_job_status(xml: &str) -> Result<JobStatus> {
 
     let mut buf = Vec::new();
     let mut job_id = String::new();
-    let mut job_stage = String::new();
+    let mut job_stage = JobStage::Unknown(String::new());
     let mut failed = false;
     let mut date_submitted = None;
     let mut self_uri = String::new();
     let mut results_uri = None;
+    let mut working_dir_uri = None;
+    let mut terminal_stage = false;
     let mut messages = Vec::new();
+    let mut metadata = Vec::new();
 
     let mut current_tag = String::new();
     let mut in_results_uri = false;
+    let mut in_working_dir_uri = false;
     let mut in_message = false;
     let mut current_message_stage = String::new();
     let mut current_message_text = String::new();
     let mut current_message_timestamp = None;
+    let mut in_metadata = false;
+    let mut current_metadata_key = None;
 
     loop {
         match reader.read_event_into(&mut buf) {
@@ -122,6 +332,8 @@ pub fn parse_job_status(xml: &str) -> Result<JobStatus> {
                 current_tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
                 match current_tag.as_str() {
                     "resultsUri" => in_results_uri = true,
+                    "workingDirUri" => in_working_dir_uri = true,
+                    "metadata" => in_metadata = true,
                     "message" => {
                         in_message = true;
                         current_message_stage.clear();
@@ -135,15 +347,15 @@ pub fn parse_job_status(xml: &str) -> Result<JobStatus> {
                 let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
                 match tag.as_str() {
                     "resultsUri" => in_results_uri = false,
-                    "message" => {
-                        if in_message {
-                            messages.push(JobMessage {
-                                stage: current_message_stage.clone(),
-                                text: current_message_text.clone(),
-                                timestamp: current_message_timestamp.clone(),
-                            });
-                            in_message = false;
-                        }
+                    "workingDirUri" => in_working_dir_uri = false,
+                    "metadata" => in_metadata = false,
+                    "message" if in_message => {
+                        messages.push(JobMessage {
+                            stage: current_message_stage.clone(),
+                            text: current_message_text.clone(),
+                            timestamp: current_message_timestamp.clone(),
+                        });
+                        in_message = false;
                     }
                     _ => {}
                 }
@@ -157,26 +369,36 @@ pub fn parse_job_status(xml: &str) -> Result<JobStatus> {
                     .unwrap_or_default();
                 match current_tag.as_str() {
                     "jobHandle" => job_id = text,
-                    "jobStage" => job_stage = text,
+                    "jobStage" => job_stage = JobStage::from(text.as_str()),
                     "failed" => failed = text == "true",
+                    "terminalStage" => terminal_stage = text == "true",
                     "dateSubmitted" => date_submitted = Some(text),
                     "url" if in_results_uri => results_uri = Some(text),
-                    "url" if !in_results_uri && self_uri.is_empty() => self_uri = text,
+                    "url" if in_working_dir_uri => working_dir_uri = Some(text),
+                    "url" if self_uri.is_empty() => self_uri = text,
                     "stage" if in_message => current_message_stage = text,
                     "text" if in_message => current_message_text = text,
                     "timestamp" if in_message => current_message_timestamp = Some(text),
+                    "key" if in_metadata => current_metadata_key = Some(text),
+                    "value" if in_metadata => {
+                        if let Some(key) = current_metadata_key.take() {
+                            metadata.push((key, text));
+                        }
+                    }
                     _ => {}
                 }
             }
             Ok(Event::Eof) => break,
-            Err(e) => return Err(anyhow::anyhow!("XML parse error: {}", e)),
+            Err(e) => return Err(NsgError::Parse(format!("XML parse error: {}", e))),
             _ => {}
         }
         buf.clear();
     }
 
     if job_id.is_empty() {
-        anyhow::bail!("Failed to parse job status: missing job ID");
+        return Err(NsgError::Parse(
+            "Failed to parse job status: missing job ID".to_string(),
+        ));
     }
 
     Ok(JobStatus {
@@ -186,7 +408,10 @@ pub fn parse_job_status(xml: &str) -> Result<JobStatus> {
         date_submitted,
         self_uri,
         results_uri,
+        working_dir_uri,
+        terminal_stage,
         messages,
+        metadata,
     })
 }
 
@@ -218,17 +443,16 @@ pub fn parse_output_files(xml: &str) -> Result<Vec<OutputFile>> {
                 let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
                 match tag.as_str() {
                     "jobfile" => {
-                        if let (Some(filename), Some(download_uri), Some(size)) = (
-                            current_filename.take(),
-                            current_download_uri.take(),
-                            current_size.take(),
-                        ) {
+                        if let (Some(filename), Some(download_uri)) =
+                            (current_filename.take(), current_download_uri.take())
+                        {
                             files.push(OutputFile {
                                 filename,
                                 download_uri,
-                                size,
+                                size: current_size.take(),
                             });
                         }
+                        current_size = None;
                         in_jobfile = false;
                     }
                     "downloadUri" => in_download_uri = false,
@@ -252,7 +476,7 @@ pub fn parse_output_files(xml: &str) -> Result<Vec<OutputFile>> {
                 }
             }
             Ok(Event::Eof) => break,
-            Err(e) => return Err(anyhow::anyhow!("XML parse error: {}", e)),
+            Err(e) => return Err(NsgError::Parse(format!("XML parse error: {}", e))),
             _ => {}
         }
         buf.clear();
@@ -260,3 +484,212 @@ pub fn parse_output_files(xml: &str) -> Result<Vec<OutputFile>> {
 
     Ok(files)
 }
+
+pub fn parse_tool_list(xml: &str) -> Result<Vec<ToolInfo>> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut tools = Vec::new();
+    let mut buf = Vec::new();
+    let mut current_tag = String::new();
+    let mut current_id = None;
+    let mut current_name = None;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                current_tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+            }
+            Ok(Event::End(e)) => {
+                if e.name().as_ref() == b"tool" {
+                    if let Some(tool_id) = current_id.take() {
+                        tools.push(ToolInfo {
+                            tool_id,
+                            name: current_name.take().unwrap_or_default(),
+                        });
+                    }
+                    current_name = None;
+                }
+                current_tag.clear();
+            }
+            Ok(Event::Text(e)) => {
+                let text = reader
+                    .decoder()
+                    .decode(e.as_ref())
+                    .map(|s| s.to_string())
+                    .unwrap_or_default();
+                match current_tag.as_str() {
+                    "toolId" => current_id = Some(text),
+                    "toolName" => current_name = Some(text),
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(NsgError::Parse(format!("XML parse error: {}", e))),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(tools)
+}
+
+/// Parse an NSG error document (`<error><displayMessage>...`), as sent with
+/// failed requests. Returns `None` for anything else, such as an HTML error
+/// page from a proxy.
+pub fn parse_error_response(xml: &str) -> Option<ErrorResponse> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut path: Vec<String> = Vec::new();
+    let mut buf = Vec::new();
+    let mut display_message = None;
+    let mut message = None;
+    let mut code = None;
+    let mut param_errors = Vec::new();
+    let mut current_param = None;
+    let mut current_error = None;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if path.is_empty() && name != "error" {
+                    return None;
+                }
+                path.push(name);
+            }
+            Ok(Event::End(_)) => {
+                if path.last().map(String::as_str) == Some("paramError") {
+                    if let Some(param) = current_param.take() {
+                        param_errors.push(ParamError {
+                            param,
+                            error: current_error.take().unwrap_or_default(),
+                        });
+                    }
+                    current_error = None;
+                }
+                path.pop();
+            }
+            Ok(Event::Text(e)) => {
+                let text = reader
+                    .decoder()
+                    .decode(e.as_ref())
+                    .map(|s| s.to_string())
+                    .unwrap_or_default();
+                let tags: Vec<&str> = path.iter().map(String::as_str).collect();
+                match tags.as_slice() {
+                    ["error", "displayMessage"] => display_message = Some(text),
+                    ["error", "message"] => message = Some(text),
+                    ["error", "code"] => code = text.trim().parse().ok(),
+                    ["error", "paramError", "param"] => {
+                        let param = text.strip_prefix("vparam.").unwrap_or(&text);
+                        current_param = Some(param.to_string());
+                    }
+                    ["error", "paramError", "error"] => current_error = Some(text),
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(_) => return None,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Some(ErrorResponse {
+        display_message: display_message.or_else(|| message.clone())?,
+        message,
+        code,
+        param_errors,
+    })
+}
+
+/// Parse a tool's PISE XML (`/tool/{id}/doc/pise`) into its parameter list.
+///
+/// Paragraphs (parameter groups) are flattened; only the parameters they
+/// contain are returned.
+pub fn parse_tool_parameters(xml: &str) -> Result<Vec<ToolParameter>> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut params = Vec::new();
+    let mut buf = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut stack: Vec<ToolParameter> = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if tag == "parameter" {
+                    let mut param = ToolParameter {
+                        name: String::new(),
+                        param_type: String::new(),
+                        prompt: None,
+                        default: None,
+                        min: None,
+                        max: None,
+                        allowed_values: Vec::new(),
+                        mandatory: false,
+                        hidden: false,
+                    };
+                    for attr in e.attributes().flatten() {
+                        let value = String::from_utf8_lossy(&attr.value).to_string();
+                        match attr.key.as_ref() {
+                            b"type" => param.param_type = value,
+                            b"ismandatory" => param.mandatory = value == "1",
+                            b"ishidden" => param.hidden = value == "1",
+                            _ => {}
+                        }
+                    }
+                    stack.push(param);
+                }
+                path.push(tag);
+            }
+            Ok(Event::End(e)) => {
+                path.pop();
+                if e.name().as_ref() == b"parameter" {
+                    if let Some(param) = stack.pop() {
+                        if param.param_type != "Paragraph" && !param.name.is_empty() {
+                            params.push(param);
+                        }
+                    }
+                }
+            }
+            Ok(Event::Text(e)) => {
+                let Some(param) = stack.last_mut() else {
+                    buf.clear();
+                    continue;
+                };
+                let text = reader
+                    .decoder()
+                    .decode(e.as_ref())
+                    .map(|s| s.to_string())
+                    .unwrap_or_default();
+                let tail: Vec<&str> = path.iter().rev().take(4).map(|s| s.as_str()).collect();
+                match tail.as_slice() {
+                    ["name", "parameter", ..] => param.name = text,
+                    ["prompt", "attributes", "parameter", ..] => param.prompt = Some(text),
+                    ["value", "vdef", "attributes", "parameter"] => param.default = Some(text),
+                    ["value", "scalemin", "attributes", "parameter"] => {
+                        param.min = text.parse().ok()
+                    }
+                    ["value", "scalemax", "attributes", "parameter"] => {
+                        param.max = text.parse().ok()
+                    }
+                    ["value", "vlist", "attributes", "parameter"] => {
+                        param.allowed_values.push(text)
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(NsgError::Parse(format!("XML parse error: {}", e))),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(params)
+}
@@ -0,0 +1,121 @@
+//! Per-project defaults from a `.nsg.toml`, found by walking up from the
+//! working directory the way git finds `.gitignore`. They override the
+//! user-level `config.toml` and are overridden by command line flags.
+
+use anyhow::{Context, Result};
+#[cfg(feature = "cli")]
+use clap::Command;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const PROJECT_FILE: &str = ".nsg.toml";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProjectFile {
+    tool: Option<String>,
+    input: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    #[serde(default)]
+    params: BTreeMap<String, toml::Value>,
+}
+
+/// A loaded `.nsg.toml`, with paths resolved against its directory
+#[derive(Debug, Clone, Default)]
+pub struct ProjectConfig {
+    /// Location of the `.nsg.toml` itself
+    pub path: PathBuf,
+    /// Tool for `nsg submit`
+    pub tool: Option<String>,
+    /// Input ZIP file, or a directory to zip, when `nsg submit` gets none
+    pub input: Option<PathBuf>,
+    /// Directory for `nsg download` and `nsg submit --download`
+    pub output_dir: Option<PathBuf>,
+    /// `--param` values, below any given on the command line
+    pub params: Vec<(String, String)>,
+}
+
+impl ProjectConfig {
+    /// The nearest `.nsg.toml` in the working directory or its parents
+    pub fn discover() -> Result<Option<Self>> {
+        let cwd = std::env::current_dir().context("Could not determine the working directory")?;
+        match find(&cwd) {
+            Some(path) => Self::load(&path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file: ProjectFile = toml::from_str(&content)
+            .with_context(|| format!("Invalid project config {}", path.display()))?;
+
+        let base = path.parent().unwrap_or(Path::new("."));
+        let params = param_values(file.params, path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            tool: file.tool,
+            input: file.input.map(|input| base.join(input)),
+            output_dir: file.output_dir.map(|dir| base.join(dir)),
+            params,
+        })
+    }
+
+    /// Make the project's tool and output directory the defaults of the
+    /// matching arguments of `cli`, the top-level `nsg` command
+    #[cfg(feature = "cli")]
+    pub fn apply(&self, mut cli: Command) -> Command {
+        if let Some(tool) = &self.tool {
+            cli = cli.mut_subcommand("submit", |cmd| {
+                cmd.mut_arg("tool", |arg| arg.default_value(tool.clone()))
+            });
+        }
+
+        if let Some(dir) = &self.output_dir {
+            let dir = dir.display().to_string();
+            cli = cli
+                .mut_subcommand("download", |cmd| {
+                    cmd.mut_arg("output", |arg| arg.default_value(dir.clone()))
+                })
+                .mut_subcommand("submit", |cmd| {
+                    cmd.mut_arg("download", |arg| arg.default_missing_value(dir.clone()))
+                });
+        }
+
+        cli
+    }
+}
+
+/// The `[params]` table of a TOML file read from `path` as `--param` pairs
+pub(crate) fn param_values(
+    params: BTreeMap<String, toml::Value>,
+    path: &Path,
+) -> Result<Vec<(String, String)>> {
+    params
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                toml::Value::String(text) => text,
+                toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                    value.to_string()
+                }
+                _ => anyhow::bail!(
+                    "Parameter '{}' in {} must be a string, number or boolean",
+                    name,
+                    path.display()
+                ),
+            };
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn find(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
@@ -0,0 +1,315 @@
+//! Python bindings (`python` feature), built into the `nsg_cli` extension
+//! module with maturin. They wrap [`NsgClient`] and share the CLI's
+//! credential store, so `nsg login` once serves both.
+//!
+//! ```python
+//! import nsg_cli
+//!
+//! client = nsg_cli.Client()
+//! job = client.submit("PY_EXPANSE", "model/", params={"runtime_": "1"})
+//! job = client.wait(job.job_id)
+//! client.download(job.job_id, "results")
+//! ```
+
+use crate::archive::zip_directory;
+use crate::client::{ClientOptions, DownloadOptions, NsgClient};
+use crate::config::Credentials;
+use crate::error::NotLoggedIn;
+use crate::filenames::FileFilter;
+use crate::models;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+pyo3::create_exception!(
+    nsg_cli,
+    NsgError,
+    PyException,
+    "A request to NSG failed, or local credentials or files were unusable."
+);
+pyo3::create_exception!(
+    nsg_cli,
+    AuthError,
+    NsgError,
+    "NSG rejected the credentials, or there are none (run `nsg login`)."
+);
+pyo3::create_exception!(
+    nsg_cli,
+    NotFoundError,
+    NsgError,
+    "The job or file doesn't exist on NSG."
+);
+
+fn nsg_error(error: crate::error::NsgError) -> PyErr {
+    let message = error.to_string();
+    match error {
+        crate::error::NsgError::Auth { .. } => AuthError::new_err(message),
+        crate::error::NsgError::NotFound { .. } => NotFoundError::new_err(message),
+        _ => NsgError::new_err(message),
+    }
+}
+
+fn other_error(error: anyhow::Error) -> PyErr {
+    if error.is::<NotLoggedIn>() {
+        return AuthError::new_err(format!("{:#}", error));
+    }
+    match error.downcast::<crate::error::NsgError>() {
+        Ok(error) => nsg_error(error),
+        Err(error) => NsgError::new_err(format!("{:#}", error)),
+    }
+}
+
+/// A job's status, as returned by `Client.status`, `submit` and `wait`
+#[pyclass(module = "nsg_cli", frozen, get_all)]
+#[derive(Debug, Clone)]
+struct JobStatus {
+    job_id: String,
+    /// NSG's spelling of the stage, e.g. `"RUNNING"` or `"COMPLETED"`
+    stage: String,
+    failed: bool,
+    /// The job won't change any more
+    terminal: bool,
+    date_submitted: Option<String>,
+    /// `(stage, text, timestamp)` of each of NSG's job messages
+    messages: Vec<(String, String, Option<String>)>,
+    metadata: HashMap<String, String>,
+}
+
+#[pymethods]
+impl JobStatus {
+    fn __repr__(&self) -> String {
+        format!(
+            "JobStatus(job_id='{}', stage='{}', failed={})",
+            self.job_id,
+            self.stage,
+            if self.failed { "True" } else { "False" }
+        )
+    }
+}
+
+impl From<models::JobStatus> for JobStatus {
+    fn from(status: models::JobStatus) -> Self {
+        Self {
+            job_id: status.job_id,
+            stage: status.job_stage.to_string(),
+            failed: status.failed,
+            terminal: status.terminal_stage || status.job_stage.is_terminal(),
+            date_submitted: status.date_submitted,
+            messages: status
+                .messages
+                .into_iter()
+                .map(|message| (message.stage, message.text, message.timestamp))
+                .collect(),
+            metadata: status.metadata.into_iter().collect(),
+        }
+    }
+}
+
+/// A connection to NSG. Without explicit credentials it uses the ones
+/// `nsg login` saved (for `profile`), or the `NSG_USERNAME`, `NSG_PASSWORD`
+/// and `NSG_APP_KEY` environment variables.
+#[pyclass(module = "nsg_cli", frozen)]
+struct Client {
+    client: NsgClient,
+}
+
+#[pymethods]
+impl Client {
+    #[new]
+    #[pyo3(signature = (username=None, password=None, app_key=None, *, profile=None, base_url=None))]
+    fn new(
+        username: Option<String>,
+        password: Option<String>,
+        app_key: Option<String>,
+        profile: Option<String>,
+        base_url: Option<String>,
+    ) -> PyResult<Self> {
+        let credentials = match (username, password, app_key) {
+            (Some(username), Some(password), Some(app_key)) => {
+                Credentials::new(username, password, app_key)
+            }
+            (None, None, None) => {
+                Credentials::load_profile(profile.as_deref()).map_err(other_error)?
+            }
+            _ => {
+                return Err(AuthError::new_err(
+                    "Give all of username, password and app_key, or none of them",
+                ))
+            }
+        };
+        client(credentials, base_url).map(|client| Self { client })
+    }
+
+    /// IDs of all of the user's jobs
+    fn list_jobs(&self, py: Python<'_>) -> PyResult<Vec<String>> {
+        let jobs = py.detach(|| self.client.list_jobs()).map_err(nsg_error)?;
+        Ok(jobs.into_iter().map(|job| job.job_id).collect())
+    }
+
+    /// Status of a job given its ID or URL
+    fn status(&self, py: Python<'_>, job: &str) -> PyResult<JobStatus> {
+        py.detach(|| self.client.get_job_status(job))
+            .map(JobStatus::from)
+            .map_err(nsg_error)
+    }
+
+    /// Submit `input` (a ZIP file, or a directory to zip) to `tool`.
+    /// `params` are tool parameters such as `{"runtime_": "1"}`; `name` is
+    /// shown as the job's name on NSG.
+    #[pyo3(signature = (tool, input, params=None, *, name=None))]
+    fn submit(
+        &self,
+        py: Python<'_>,
+        tool: &str,
+        input: PathBuf,
+        params: Option<HashMap<String, String>>,
+        name: Option<String>,
+    ) -> PyResult<JobStatus> {
+        let mut params: Vec<(String, String)> = params.unwrap_or_default().into_iter().collect();
+        params.sort();
+        if let Some(name) = name {
+            params.push(("metadata.clientJobName".to_string(), name));
+        }
+
+        py.detach(|| -> PyResult<JobStatus> {
+            if !input.is_dir() {
+                return self
+                    .client
+                    .submit_job_with_params(&input, tool, &params)
+                    .map(JobStatus::from)
+                    .map_err(nsg_error);
+            }
+
+            let staging = tempfile::Builder::new()
+                .prefix("nsg-python-")
+                .tempdir()
+                .map_err(|e| {
+                    NsgError::new_err(format!("Failed to create a staging directory: {}", e))
+                })?;
+            let zipped = staging.path().join("input.zip");
+            zip_directory(&input, &zipped)
+                .map_err(other_error)
+                .and_then(|()| {
+                    self.client
+                        .submit_job_with_params(&zipped, tool, &params)
+                        .map_err(nsg_error)
+                })
+                .map(JobStatus::from)
+        })
+    }
+
+    /// Poll a job every `interval` seconds until it finishes, and return
+    /// its final status. Raises `TimeoutError` after `timeout` seconds.
+    #[pyo3(signature = (job, interval=30.0, timeout=None))]
+    fn wait(
+        &self,
+        py: Python<'_>,
+        job: &str,
+        interval: f64,
+        timeout: Option<f64>,
+    ) -> PyResult<JobStatus> {
+        let started = Instant::now();
+        let interval = Duration::from_secs_f64(interval.max(1.0));
+        loop {
+            let status = self.status(py, job)?;
+            if status.terminal {
+                return Ok(status);
+            }
+            if timeout.is_some_and(|timeout| started.elapsed().as_secs_f64() >= timeout) {
+                return Err(pyo3::exceptions::PyTimeoutError::new_err(format!(
+                    "Job {} is still {}",
+                    status.job_id, status.stage
+                )));
+            }
+
+            // In steps, so Ctrl-C in a notebook interrupts promptly
+            let until = Instant::now() + interval;
+            while Instant::now() < until {
+                py.detach(|| std::thread::sleep(Duration::from_millis(200)));
+                py.check_signals()?;
+            }
+        }
+    }
+
+    /// Download a job's outputs into `output_dir` (default `nsg_results`,
+    /// like the CLI's), optionally only the files matching the glob patterns
+    /// in `include` and not in `exclude`. Returns the downloaded files' paths.
+    #[pyo3(signature = (job, output_dir=None, include=None, exclude=None))]
+    fn download(
+        &self,
+        py: Python<'_>,
+        job: &str,
+        output_dir: Option<PathBuf>,
+        include: Option<Vec<String>>,
+        exclude: Option<Vec<String>>,
+    ) -> PyResult<Vec<PathBuf>> {
+        let output_dir = output_dir.unwrap_or_else(|| PathBuf::from("nsg_results"));
+        let options = DownloadOptions {
+            filter: FileFilter::new(&include.unwrap_or_default(), &exclude.unwrap_or_default())
+                .map_err(other_error)?,
+            ..Default::default()
+        };
+        let files = py
+            .detach(|| {
+                self.client
+                    .download_results_with(job, &output_dir, &options, |_| {})
+            })
+            .map_err(nsg_error)?;
+        Ok(files.into_iter().map(|file| file.path).collect())
+    }
+
+    /// Cancel a job; NSG removes it from the job list as well
+    fn cancel(&self, py: Python<'_>, job: &str) -> PyResult<()> {
+        py.detach(|| self.client.cancel_job(job)).map_err(nsg_error)
+    }
+}
+
+fn client(credentials: Credentials, base_url: Option<String>) -> PyResult<NsgClient> {
+    let base_url = base_url
+        .map(|url| crate::client::parse_base_url(&url))
+        .transpose()
+        .map_err(NsgError::new_err)?;
+    NsgClient::with_options(
+        credentials,
+        ClientOptions {
+            base_url,
+            ..Default::default()
+        },
+    )
+    .map_err(nsg_error)
+}
+
+/// Check the credentials with NSG and save them where `nsg login` does
+/// (the password in the OS keyring when available). Returns a `Client`.
+#[pyfunction]
+#[pyo3(signature = (username, password, app_key, *, profile=None, base_url=None))]
+fn login(
+    py: Python<'_>,
+    username: String,
+    password: String,
+    app_key: String,
+    profile: Option<String>,
+    base_url: Option<String>,
+) -> PyResult<Client> {
+    let credentials = Credentials::new(username, password, app_key);
+    let client = client(credentials.clone(), base_url)?;
+    py.detach(|| client.test_connection()).map_err(nsg_error)?;
+    credentials
+        .save_profile(profile.as_deref(), cfg!(feature = "keyring"))
+        .map_err(other_error)?;
+    Ok(Client { client })
+}
+
+#[pymodule]
+fn nsg_cli(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Client>()?;
+    m.add_class::<JobStatus>()?;
+    m.add_function(wrap_pyfunction!(login, m)?)?;
+    m.add("NsgError", m.py().get_type::<NsgError>())?;
+    m.add("AuthError", m.py().get_type::<AuthError>())?;
+    m.add("NotFoundError", m.py().get_type::<NotFoundError>())?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
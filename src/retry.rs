@@ -0,0 +1,88 @@
+use reqwest::StatusCode;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Retries used when `--retries` is not given
+pub const DEFAULT_RETRIES: u32 = 3;
+
+/// First wait before retrying, doubled on every further attempt
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest wait between two attempts, however many have failed
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// When and how long to wait before re-sending a request that failed in a
+/// way that is likely to go away on its own (gateway hiccups, dropped
+/// connections, timeouts).
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Extra attempts after the first one; 0 disables retrying
+    pub retries: u32,
+    /// Wait before the first retry; each further retry waits twice as long,
+    /// up to [`MAX_RETRY_DELAY`]
+    pub backoff: Duration,
+    /// Wait a random 50-100% of the delay, so parallel downloads that failed
+    /// together don't all come back at the same moment
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: DEFAULT_RETRIES,
+            backoff: DEFAULT_RETRY_BACKOFF,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that sends every request exactly once
+    pub fn none() -> Self {
+        Self {
+            retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// How long to wait before retry number `retry` (starting at 1)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        let delay = self
+            .backoff
+            .checked_mul(factor)
+            .unwrap_or(MAX_RETRY_DELAY)
+            .min(MAX_RETRY_DELAY);
+
+        if self.jitter {
+            delay.mul_f64(0.5 + random_fraction() / 2.0)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Whether a response with `status` is worth sending again: any 5xx, plus
+/// 429 Too Many Requests
+pub fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Whether a request that got no response at all is worth sending again
+pub fn is_transient_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.is_request()
+}
+
+/// A number in `[0, 1)` that differs between calls and processes. Good
+/// enough to spread out retries; not for anything security related.
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
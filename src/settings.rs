@@ -0,0 +1,419 @@
+//! User defaults from `~/.nsg/config.toml`, kept apart from credentials.
+//!
+//! Each setting becomes the default of the matching command line option, so
+//! an explicit flag still wins and `--help` shows the effective default.
+
+use crate::client::parse_base_url;
+use crate::commands::global::{parse_backoff, parse_rate_limit};
+use crate::config::config_dir;
+use anyhow::{Context, Result};
+use clap::Command;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const SETTINGS_FILE: &str = "config.toml";
+
+/// Every key accepted in `config.toml`: name, what it does, built-in default
+pub const KEYS: &[(&str, &str, &str)] = &[
+    ("default_tool", "Tool for nsg submit", "PY_EXPANSE"),
+    (
+        "default_output_dir",
+        "Directory for nsg download / submit --download / ui",
+        "./nsg_results",
+    ),
+    (
+        "extract",
+        "Unpack output.tar.gz after nsg download (true/false)",
+        "false",
+    ),
+    (
+        "notify",
+        "Desktop notification when watch / submit --wait sees a job finish (true/false)",
+        "false",
+    ),
+    (
+        "allocation",
+        "Your allocation in SUs, for nsg usage",
+        "none",
+    ),
+    ("base_url", "NSG REST endpoint", crate::client::NSG_BASE_URL),
+    (
+        "poll_interval",
+        "Seconds between status checks (watch, tail, submit, ui)",
+        "30",
+    ),
+    ("color", "Colored output (true/false)", "true"),
+    (
+        "retries",
+        "Retries of timed out / dropped / 5xx requests",
+        "3",
+    ),
+    ("retry_backoff", "Seconds before the first retry", "1"),
+    ("rate_limit", "API requests per second (0 = no limit)", "5"),
+    (
+        "retry_jitter",
+        "Randomize retry delays (true/false)",
+        "true",
+    ),
+    (
+        "timeout",
+        "Seconds an API call may take (0 = forever)",
+        "30",
+    ),
+    ("connect_timeout", "Seconds to wait for a connection", "15"),
+    (
+        "read_timeout",
+        "Seconds a download may go without data (0 = forever)",
+        "60",
+    ),
+    (
+        "ca_cert",
+        "Extra trusted root certificates (PEM file)",
+        "none",
+    ),
+    (
+        "insecure",
+        "Skip TLS certificate verification (true/false)",
+        "false",
+    ),
+];
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    /// Tool for `nsg submit` without `--tool`
+    pub default_tool: Option<String>,
+    /// Directory for `nsg download` without `--output`, and for a bare
+    /// `nsg submit --download`
+    pub default_output_dir: Option<PathBuf>,
+    /// `true` behaves like passing `nsg download --extract` every time
+    pub extract: Option<bool>,
+    /// `true` behaves like passing `--notify` to `watch`, `submit` and
+    /// `resubmit` every time
+    pub notify: Option<bool>,
+    /// SUs available to the user, like `nsg usage --allocation`
+    pub allocation: Option<f64>,
+    /// NSG REST endpoint, e.g. a test server
+    pub base_url: Option<String>,
+    /// Seconds between status checks in `watch`, `tail`, `submit --wait` and `ui`
+    pub poll_interval: Option<u64>,
+    /// `false` behaves like passing `--no-color` every time
+    pub color: Option<bool>,
+    /// Retries of requests that fail transiently, like `--retries`
+    pub retries: Option<u32>,
+    /// Seconds before the first retry, like `--retry-backoff`
+    pub retry_backoff: Option<f64>,
+    /// Requests per second, like `--rate-limit`
+    pub rate_limit: Option<f64>,
+    /// `false` behaves like passing `--no-retry-jitter` every time
+    pub retry_jitter: Option<bool>,
+    /// Seconds an API call may take, like `--timeout`
+    pub timeout: Option<u64>,
+    /// Seconds to wait for a connection, like `--connect-timeout`
+    pub connect_timeout: Option<u64>,
+    /// Seconds a download may go without data, like `--read-timeout`
+    pub read_timeout: Option<u64>,
+    /// PEM bundle trusted on top of the system roots, like `--ca-cert`
+    pub ca_cert: Option<PathBuf>,
+    /// `true` behaves like passing `--insecure` every time
+    pub insecure: Option<bool>,
+    /// `[tool_aliases]`: extra names for `--tool`, e.g. `nrn = "NEURON_EXPANSE"`
+    #[serde(default)]
+    pub tool_aliases: BTreeMap<String, String>,
+}
+
+impl Settings {
+    /// Read the config file; a missing file gives the built-in defaults
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    pub fn path() -> Result<PathBuf> {
+        Ok(config_dir()?.join(SETTINGS_FILE))
+    }
+
+    /// Value of each key as written in the config file, `None` when unset
+    pub fn values() -> Result<Vec<(&'static str, Option<String>)>> {
+        let document = read_document()?;
+
+        Ok(KEYS
+            .iter()
+            .map(|(key, _, _)| {
+                let value = document.get(key).and_then(|item| match item.as_str() {
+                    Some(text) => Some(text.to_string()),
+                    None => item.as_value().map(|v| v.to_string().trim().to_string()),
+                });
+                (*key, value)
+            })
+            .collect())
+    }
+
+    /// Check `value` for `key` and write it to the config file, keeping the
+    /// rest of the file (comments included) as it was
+    pub fn set(key: &str, value: &str) -> Result<()> {
+        let item = parse_value(key, value)?;
+
+        let mut document = read_document()?;
+        document[key] = item;
+        write_document(&document)
+    }
+
+    /// Remove `key` from the config file. Returns false if it wasn't set.
+    pub fn unset(key: &str) -> Result<bool> {
+        check_key(key)?;
+
+        let mut document = read_document()?;
+        let removed = document.remove(key).is_some();
+        if removed {
+            write_document(&document)?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Make the configured values the defaults of the matching arguments of
+    /// `cli`, the top-level `nsg` command
+    pub fn apply(&self, mut cli: Command) -> Command {
+        if let Some(tool) = &self.default_tool {
+            cli = cli.mut_subcommand("submit", |cmd| {
+                cmd.mut_arg("tool", |arg| arg.default_value(tool.clone()))
+            });
+        }
+
+        if let Some(dir) = &self.default_output_dir {
+            let dir = expand_home(dir).display().to_string();
+            cli = cli
+                .mut_subcommand("download", |cmd| {
+                    cmd.mut_arg("output", |arg| arg.default_value(dir.clone()))
+                })
+                .mut_subcommand("submit", |cmd| {
+                    cmd.mut_arg("download", |arg| arg.default_missing_value(dir.clone()))
+                })
+                .mut_subcommand("resubmit", |cmd| {
+                    cmd.mut_arg("download", |arg| arg.default_missing_value(dir.clone()))
+                });
+            #[cfg(feature = "tui")]
+            {
+                cli = cli.mut_subcommand("ui", |cmd| {
+                    cmd.mut_arg("output", |arg| arg.default_value(dir.clone()))
+                });
+            }
+        }
+
+        if self.extract == Some(true) {
+            cli = cli.mut_subcommand("download", |cmd| {
+                cmd.mut_arg("extract", |arg| arg.default_value("true"))
+            });
+        }
+
+        if self.notify == Some(true) {
+            for name in ["watch", "submit", "resubmit"] {
+                cli = cli.mut_subcommand(name, |cmd| {
+                    cmd.mut_arg("notify", |arg| arg.default_value("true"))
+                });
+            }
+        }
+
+        if let Some(allocation) = self.allocation {
+            cli = cli.mut_subcommand("usage", |cmd| {
+                cmd.mut_arg("allocation", |arg| {
+                    arg.default_value(allocation.to_string())
+                })
+            });
+        }
+
+        if let Some(interval) = self.poll_interval {
+            for name in ["watch", "tail", "submit", "resubmit"] {
+                cli = cli.mut_subcommand(name, |cmd| {
+                    cmd.mut_arg("interval", |arg| arg.default_value(interval.to_string()))
+                });
+            }
+            #[cfg(feature = "tui")]
+            {
+                cli = cli.mut_subcommand("ui", |cmd| {
+                    cmd.mut_arg("interval", |arg| arg.default_value(interval.to_string()))
+                });
+            }
+        }
+
+        if let Some(base_url) = &self.base_url {
+            cli = cli.mut_arg("base_url", |arg| arg.default_value(base_url.clone()));
+        }
+
+        if self.color == Some(false) {
+            cli = cli.mut_arg("no_color", |arg| arg.default_value("true"));
+        }
+
+        if let Some(retries) = self.retries {
+            cli = cli.mut_arg("retries", |arg| arg.default_value(retries.to_string()));
+        }
+
+        if let Some(backoff) = self.retry_backoff {
+            cli = cli.mut_arg("retry_backoff", |arg| {
+                arg.default_value(backoff.to_string())
+            });
+        }
+
+        if let Some(rate) = self.rate_limit {
+            cli = cli.mut_arg("rate_limit", |arg| arg.default_value(rate.to_string()));
+        }
+
+        if self.retry_jitter == Some(false) {
+            cli = cli.mut_arg("no_retry_jitter", |arg| arg.default_value("true"));
+        }
+
+        if let Some(timeout) = self.timeout {
+            cli = cli.mut_arg("timeout", |arg| arg.default_value(timeout.to_string()));
+        }
+
+        if let Some(timeout) = self.connect_timeout {
+            cli = cli.mut_arg("connect_timeout", |arg| {
+                arg.default_value(timeout.to_string())
+            });
+        }
+
+        if let Some(timeout) = self.read_timeout {
+            cli = cli.mut_arg("read_timeout", |arg| arg.default_value(timeout.to_string()));
+        }
+
+        if let Some(path) = &self.ca_cert {
+            let path = expand_home(path).display().to_string();
+            cli = cli.mut_arg("ca_cert", |arg| arg.default_value(path));
+        }
+
+        if self.insecure == Some(true) {
+            cli = cli.mut_arg("insecure", |arg| arg.default_value("true"));
+        }
+
+        cli
+    }
+}
+
+/// Resolve a leading `~/`, which TOML strings don't get from a shell
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Fail with the list of known keys if `key` isn't one of them
+pub fn check_key(key: &str) -> Result<()> {
+    if !KEYS.iter().any(|(name, _, _)| *name == key) {
+        let names: Vec<&str> = KEYS.iter().map(|(name, _, _)| *name).collect();
+        anyhow::bail!(
+            "Unknown config key '{}' (expected one of: {})",
+            key,
+            names.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Turn the command line text for `key` into a typed TOML value
+fn parse_value(key: &str, value: &str) -> Result<toml_edit::Item> {
+    check_key(key)?;
+
+    let item = match key {
+        "poll_interval" | "connect_timeout" => {
+            let seconds: u64 = value
+                .parse()
+                .ok()
+                .filter(|seconds| *seconds >= 1)
+                .with_context(|| {
+                    format!(
+                        "{} must be a whole number of seconds >= 1, not '{}'",
+                        key, value
+                    )
+                })?;
+            toml_edit::value(seconds as i64)
+        }
+        "color" | "retry_jitter" | "insecure" | "extract" | "notify" => {
+            let flag: bool = value
+                .parse()
+                .with_context(|| format!("{} must be true or false, not '{}'", key, value))?;
+            toml_edit::value(flag)
+        }
+        "timeout" | "read_timeout" => {
+            let seconds: u64 = value.parse().with_context(|| {
+                format!(
+                    "{} must be a whole number of seconds >= 0, not '{}'",
+                    key, value
+                )
+            })?;
+            toml_edit::value(seconds as i64)
+        }
+        "retries" => {
+            let retries: u32 = value
+                .parse()
+                .with_context(|| format!("retries must be a whole number >= 0, not '{}'", value))?;
+            toml_edit::value(retries as i64)
+        }
+        "retry_backoff" => {
+            let secs = parse_backoff(value).map_err(|e| anyhow::anyhow!("retry_backoff: {}", e))?;
+            toml_edit::value(secs)
+        }
+        "allocation" => {
+            let su: f64 = value
+                .parse()
+                .ok()
+                .filter(|su: &f64| su.is_finite() && *su >= 0.0)
+                .with_context(|| {
+                    format!("allocation must be a number of SUs >= 0, not '{}'", value)
+                })?;
+            toml_edit::value(su)
+        }
+        "rate_limit" => {
+            let rate = parse_rate_limit(value).map_err(|e| anyhow::anyhow!("rate_limit: {}", e))?;
+            toml_edit::value(rate)
+        }
+        "base_url" => {
+            let url = parse_base_url(value).map_err(|e| anyhow::anyhow!("base_url: {}", e))?;
+            toml_edit::value(url)
+        }
+        _ => {
+            if value.trim().is_empty() {
+                anyhow::bail!("{} cannot be empty", key);
+            }
+            toml_edit::value(value)
+        }
+    };
+
+    Ok(item)
+}
+
+fn read_document() -> Result<toml_edit::DocumentMut> {
+    let path = Settings::path()?;
+    if !path.exists() {
+        return Ok(toml_edit::DocumentMut::new());
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    content
+        .parse()
+        .with_context(|| format!("Invalid config file {}", path.display()))
+}
+
+fn write_document(document: &toml_edit::DocumentMut) -> Result<()> {
+    let path = Settings::path()?;
+    let content = document.to_string();
+
+    // Refuse to write anything that `load` would then reject
+    toml::from_str::<Settings>(&content)
+        .with_context(|| format!("Refusing to write an invalid {}", path.display()))?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
@@ -0,0 +1,152 @@
+//! Named sets of submit options (`~/.nsg/templates/<name>.toml`), applied
+//! with `nsg submit --template <name>`. One file per template, so they can
+//! be edited by hand or copied between machines.
+
+use crate::config::config_dir;
+use crate::project::param_values;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const TEMPLATES_DIR: &str = "templates";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TemplateFile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    params: BTreeMap<String, toml::Value>,
+}
+
+/// A saved template
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Template {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Tool for `nsg submit`, unless `--tool` is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// `--param` values, below any given on the command line
+    pub params: BTreeMap<String, String>,
+}
+
+impl Template {
+    /// Read template `name`
+    pub fn load(name: &str) -> Result<Self> {
+        let path = Self::path(name)?;
+        if !path.exists() {
+            anyhow::bail!(
+                "No template named '{}' (`nsg template list` shows the saved ones)",
+                name
+            );
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file: TemplateFile = toml::from_str(&content)
+            .with_context(|| format!("Invalid template {}", path.display()))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            description: file.description,
+            tool: file.tool,
+            params: param_values(file.params, &path)?.into_iter().collect(),
+        })
+    }
+
+    /// Every saved template, by name
+    pub fn list() -> Result<Vec<Self>> {
+        let dir = config_dir()?.join(TEMPLATES_DIR);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut names = Vec::new();
+        for entry in
+            fs::read_dir(&dir).with_context(|| format!("Failed to read {}", dir.display()))?
+        {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "toml") {
+                if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+
+        names.iter().map(|name| Self::load(name)).collect()
+    }
+
+    /// Write the template, replacing one of the same name only if
+    /// `overwrite` is set. Returns where it was written.
+    pub fn save(&self, overwrite: bool) -> Result<PathBuf> {
+        let path = Self::path(&self.name)?;
+        if path.exists() && !overwrite {
+            anyhow::bail!(
+                "Template '{}' already exists (use --force to replace it)",
+                self.name
+            );
+        }
+
+        let file = TemplateFile {
+            description: self.description.clone(),
+            tool: self.tool.clone(),
+            params: self
+                .params
+                .iter()
+                .map(|(name, value)| (name.clone(), toml::Value::String(value.clone())))
+                .collect(),
+        };
+        let content = toml::to_string(&file).context("Failed to serialize template")?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+
+        Ok(path)
+    }
+
+    /// Remove template `name`. Returns false if there was none.
+    pub fn delete(name: &str) -> Result<bool> {
+        let path = Self::path(name)?;
+        if !path.exists() {
+            return Ok(false);
+        }
+
+        fs::remove_file(&path).with_context(|| format!("Failed to delete {}", path.display()))?;
+        Ok(true)
+    }
+
+    /// File holding template `name`, whether or not it exists
+    pub fn path(name: &str) -> Result<PathBuf> {
+        check_name(name)?;
+        Ok(config_dir()?
+            .join(TEMPLATES_DIR)
+            .join(format!("{}.toml", name)))
+    }
+}
+
+/// Template names become file names: letters, digits, `-`, `_` and `.`,
+/// not starting with a dot
+fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        anyhow::bail!(
+            "Invalid template name '{}' (use letters, digits, '-', '_' and '.')",
+            name
+        );
+    }
+    Ok(())
+}
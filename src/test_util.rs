@@ -0,0 +1,796 @@
+//! A stand-in NSG server for tests (`test-util` feature).
+//!
+//! [`MockNsg::start`] serves the CIPRES REST API on a local port from canned
+//! XML: a job list, job statuses, output and working directory listings and
+//! file downloads, tools, submission and deletion, with NSG's error
+//! documents for bad credentials, unknown jobs and rejected parameters.
+//! Point an [`NsgClient`] at it with [`MockNsg::client`], or the `nsg`
+//! binary with the variables from [`MockNsg::env`].
+//!
+//! ```no_run
+//! use nsg_cli::test_util::{MockJob, MockNsg};
+//! use nsg_cli::models::JobStage;
+//!
+//! let nsg = MockNsg::start()?;
+//! nsg.add_job(MockJob::new("NGBW-JOB-PY_EXPANSE-TEST", JobStage::Running));
+//! let status = nsg.client()?.get_job_status("NGBW-JOB-PY_EXPANSE-TEST")?;
+//! assert!(!status.terminal_stage);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::client::{ClientOptions, NsgClient};
+use crate::config::Credentials;
+use crate::error::Result;
+use crate::models::JobStage;
+use crate::retry::RetryPolicy;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+
+/// Path of the API below the server's address, as on nsgr.sdsc.edu
+const API_PATH: &str = "/cipresrest/v1";
+
+pub const MOCK_USERNAME: &str = "tester";
+pub const MOCK_PASSWORD: &str = "mock-password";
+pub const MOCK_APP_KEY: &str = "mock-app-key";
+
+/// Jobs a fresh [`MockNsg`] has: one completed with outputs, one running,
+/// one failed
+pub const COMPLETED_JOB: &str = "NGBW-JOB-PY_EXPANSE-0000000000000000000000000000000A";
+pub const RUNNING_JOB: &str = "NGBW-JOB-NEURON_EXPANSE-0000000000000000000000000000000B";
+pub const FAILED_JOB: &str = "NGBW-JOB-PY_EXPANSE-0000000000000000000000000000000C";
+
+/// Longest `runtime_` (hours) submissions may ask for, like on Expanse
+const MAX_RUNTIME_HOURS: f64 = 48.0;
+
+/// A job as the mock server reports it
+#[derive(Debug, Clone)]
+pub struct MockJob {
+    pub id: String,
+    pub stage: JobStage,
+    pub failed: bool,
+    /// `dateSubmitted`, e.g. `2024-05-01T10:00:00-07:00`
+    pub date_submitted: String,
+    pub metadata: Vec<(String, String)>,
+    /// Files listed under the job's results, with their contents
+    pub outputs: Vec<(String, Vec<u8>)>,
+    /// Files listed in the job's working directory
+    pub working_dir: Vec<(String, Vec<u8>)>,
+}
+
+impl MockJob {
+    pub fn new(id: &str, stage: JobStage) -> Self {
+        Self {
+            id: id.to_string(),
+            stage,
+            failed: false,
+            date_submitted: "2024-05-01T10:00:00-07:00".to_string(),
+            metadata: Vec::new(),
+            outputs: Vec::new(),
+            working_dir: Vec::new(),
+        }
+    }
+
+    /// Mark the job failed; NSG reports failed jobs as `COMPLETED`
+    pub fn failed(mut self) -> Self {
+        self.failed = true;
+        self
+    }
+
+    pub fn submitted(mut self, date: &str) -> Self {
+        self.date_submitted = date.to_string();
+        self
+    }
+
+    pub fn metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn output(mut self, name: &str, contents: impl Into<Vec<u8>>) -> Self {
+        self.outputs.push((name.to_string(), contents.into()));
+        self
+    }
+
+    pub fn working_file(mut self, name: &str, contents: impl Into<Vec<u8>>) -> Self {
+        self.working_dir.push((name.to_string(), contents.into()));
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    jobs: Vec<MockJob>,
+    /// Answered instead of the next requests, in order
+    failures: VecDeque<u16>,
+    /// `METHOD /path` of every request received
+    requests: Vec<String>,
+    submitted: usize,
+}
+
+/// An NSG server on `127.0.0.1`, running until dropped
+pub struct MockNsg {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Arc<AtomicBool>,
+    server: Option<JoinHandle<()>>,
+}
+
+impl MockNsg {
+    /// Start a server with the [`COMPLETED_JOB`], [`RUNNING_JOB`] and
+    /// [`FAILED_JOB`] fixtures, on a free port
+    pub fn start() -> std::io::Result<Self> {
+        let nsg = Self::empty()?;
+        nsg.add_job(
+            MockJob::new(COMPLETED_JOB, JobStage::Completed)
+                .metadata("clientJobName", "fixture-completed")
+                .output("STDOUT", "Hello from NSG\n")
+                .output("STDERR", "")
+                .output("output.tar.gz", gzip_fixture()),
+        );
+        nsg.add_job(
+            MockJob::new(RUNNING_JOB, JobStage::Running)
+                .submitted("2024-05-02T10:00:00-07:00")
+                .working_file("stdout.txt", "step 1 of 3\n"),
+        );
+        nsg.add_job(
+            MockJob::new(FAILED_JOB, JobStage::Completed)
+                .submitted("2024-05-03T10:00:00-07:00")
+                .failed()
+                .output("STDERR", "Traceback (most recent call last):\n"),
+        );
+        Ok(nsg)
+    }
+
+    /// Start a server without any jobs
+    pub fn empty() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let server = {
+            let state = Arc::clone(&state);
+            let shutdown = Arc::clone(&shutdown);
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    let state = Arc::clone(&state);
+                    std::thread::spawn(move || {
+                        let _ = serve(stream, addr, &state);
+                    });
+                }
+            })
+        };
+
+        Ok(Self {
+            addr,
+            state,
+            shutdown,
+            server: Some(server),
+        })
+    }
+
+    /// API root to use as `base_url`, e.g. `http://127.0.0.1:41234/cipresrest/v1`
+    pub fn base_url(&self) -> String {
+        format!("http://{}{}", self.addr, API_PATH)
+    }
+
+    /// The only credentials the server accepts
+    pub fn credentials(&self) -> Credentials {
+        Credentials {
+            username: MOCK_USERNAME.to_string(),
+            password: MOCK_PASSWORD.to_string(),
+            app_key: MOCK_APP_KEY.to_string(),
+        }
+    }
+
+    /// A client for this server, without rate limiting or retries, so
+    /// that injected failures reach the caller
+    pub fn client(&self) -> Result<NsgClient> {
+        NsgClient::with_options(
+            self.credentials(),
+            ClientOptions {
+                base_url: Some(self.base_url()),
+                rate_limit: Some(0.0),
+                retry: RetryPolicy::none(),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Environment variables that make the `nsg` binary use this server and
+    /// its credentials
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("NSG_BASE_URL", self.base_url()),
+            ("NSG_USERNAME", MOCK_USERNAME.to_string()),
+            ("NSG_PASSWORD", MOCK_PASSWORD.to_string()),
+            ("NSG_APP_KEY", MOCK_APP_KEY.to_string()),
+        ]
+    }
+
+    /// Add a job, or replace the one with the same ID
+    pub fn add_job(&self, job: MockJob) {
+        let mut state = self.state();
+        match state.jobs.iter_mut().find(|j| j.id == job.id) {
+            Some(existing) => *existing = job,
+            None => state.jobs.push(job),
+        }
+    }
+
+    /// The job with `id` as it is now, e.g. after a submission or deletion
+    pub fn job(&self, id: &str) -> Option<MockJob> {
+        self.state().jobs.iter().find(|job| job.id == id).cloned()
+    }
+
+    /// Move a job on to `stage`
+    pub fn set_stage(&self, id: &str, stage: JobStage) {
+        if let Some(job) = self.state().jobs.iter_mut().find(|job| job.id == id) {
+            job.stage = stage;
+        }
+    }
+
+    /// Answer the next request with HTTP `status` and an NSG error document
+    /// instead. Clients that retry (not [`client`](Self::client)'s) take
+    /// one of these per attempt at a 429 or 5xx.
+    pub fn fail_next(&self, status: u16) {
+        self.state().failures.push_back(status);
+    }
+
+    /// `METHOD /path` of every request so far, in order
+    pub fn requests(&self) -> Vec<String> {
+        self.state().requests.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockNsg {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn xml(status: u16, body: String) -> Self {
+        Self {
+            status,
+            content_type: "application/xml",
+            headers: Vec::new(),
+            body: body.into_bytes(),
+        }
+    }
+
+    /// NSG's error document
+    fn error(status: u16, message: &str) -> Self {
+        Self::xml(
+            status,
+            format!(
+                "<error><displayMessage>{}</displayMessage><message>{}</message><code>{}</code></error>",
+                escape(message),
+                escape(message),
+                status
+            ),
+        )
+    }
+}
+
+fn serve(stream: TcpStream, addr: SocketAddr, state: &Mutex<State>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let Some(request) = read_request(&mut reader)? else {
+        return Ok(());
+    };
+
+    let reply = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .requests
+            .push(format!("{} {}", request.method, request.path));
+        match state.failures.pop_front() {
+            Some(status) => Reply::error(status, "Injected failure"),
+            None => route(
+                &request,
+                &format!("http://{}{}", addr, API_PATH),
+                &mut state,
+            ),
+        }
+    };
+
+    write_reply(stream, &reply)
+}
+
+fn read_request(reader: &mut impl BufRead) -> std::io::Result<Option<Request>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let mut request = Request {
+        method,
+        path,
+        headers,
+        body: Vec::new(),
+    };
+    if let Some(length) = request.header("content-length") {
+        let length = length.parse().unwrap_or(0);
+        request.body.resize(length, 0);
+        reader.read_exact(&mut request.body)?;
+    } else if request
+        .header("transfer-encoding")
+        .is_some_and(|encoding| encoding.eq_ignore_ascii_case("chunked"))
+    {
+        request.body = read_chunked(reader)?;
+    }
+    Ok(Some(request))
+}
+
+fn read_chunked(reader: &mut impl BufRead) -> std::io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let size =
+            usize::from_str_radix(line.trim().split(';').next().unwrap_or("0"), 16).unwrap_or(0);
+        if size == 0 {
+            // Trailers, then the final blank line
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                    return Ok(body);
+                }
+            }
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        line.clear();
+        reader.read_line(&mut line)?;
+    }
+}
+
+fn write_reply(mut stream: TcpStream, reply: &Reply) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        reply.status,
+        reason(reply.status),
+        reply.content_type,
+        reply.body.len()
+    );
+    for (name, value) in &reply.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&reply.body)?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        206 => "Partial Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        416 => "Range Not Satisfiable",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+fn route(request: &Request, base: &str, state: &mut State) -> Reply {
+    let authorized = request.header("cipres-appkey") == Some(MOCK_APP_KEY)
+        && request.header("authorization")
+            == Some(&format!(
+                "Basic {}",
+                base64(format!("{}:{}", MOCK_USERNAME, MOCK_PASSWORD).as_bytes())
+            ));
+    if !authorized {
+        return Reply::error(401, "Authentication Error");
+    }
+
+    let path = request.path.split('?').next().unwrap_or_default();
+    let Some(path) = path.strip_prefix(API_PATH) else {
+        return Reply::error(404, "Not Found");
+    };
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let method = request.method.as_str();
+
+    match segments.as_slice() {
+        ["tool"] if method == "GET" => Reply::xml(200, tool_list(base)),
+        ["tool", tool, "doc", "pise"] if method == "GET" => match *tool {
+            "PY_EXPANSE" => Reply::xml(200, PY_EXPANSE_PISE.to_string()),
+            _ => Reply::error(404, &format!("Tool Not Found Error: {}", tool)),
+        },
+        ["job", user, ..] if *user != MOCK_USERNAME => {
+            Reply::error(401, "Authentication Error: not your jobs")
+        }
+        ["job", _] if method == "GET" => Reply::xml(200, job_list(base, &state.jobs)),
+        ["job", _] if method == "POST" => submit(request, base, state),
+        ["job", _, id, rest @ ..] => {
+            let Some(index) = state.jobs.iter().position(|job| job.id == *id) else {
+                return Reply::error(404, &format!("Job Not Found Error: {}", id));
+            };
+            let job = &state.jobs[index];
+            match (method, rest) {
+                ("GET", []) => Reply::xml(200, job_status(base, job)),
+                ("DELETE", []) => {
+                    state.jobs.remove(index);
+                    Reply {
+                        status: 204,
+                        content_type: "application/xml",
+                        headers: Vec::new(),
+                        body: Vec::new(),
+                    }
+                }
+                ("GET", [kind @ ("output" | "workingdir")]) => {
+                    Reply::xml(200, file_list(base, job, kind, files(job, kind)))
+                }
+                ("GET", [kind @ ("output" | "workingdir"), name]) => {
+                    let name = percent_decode(name);
+                    match files(job, kind).iter().find(|(n, _)| *n == name) {
+                        Some((_, contents)) => download(request, contents),
+                        None => Reply::error(404, &format!("File Not Found Error: {}", name)),
+                    }
+                }
+                _ => Reply::error(404, "Not Found"),
+            }
+        }
+        _ => Reply::error(404, "Not Found"),
+    }
+}
+
+fn files<'a>(job: &'a MockJob, kind: &str) -> &'a [(String, Vec<u8>)] {
+    if kind == "output" {
+        &job.outputs
+    } else {
+        &job.working_dir
+    }
+}
+
+/// A file's contents, honouring `Range: bytes=N-` the way tailing uses it
+fn download(request: &Request, contents: &[u8]) -> Reply {
+    let start = request
+        .header("range")
+        .and_then(|range| range.strip_prefix("bytes="))
+        .and_then(|range| range.split('-').next())
+        .and_then(|start| start.parse::<usize>().ok());
+
+    match start {
+        Some(start) if start >= contents.len() => Reply {
+            status: 416,
+            content_type: "application/octet-stream",
+            headers: vec![("Content-Range", format!("bytes */{}", contents.len()))],
+            body: Vec::new(),
+        },
+        Some(start) => Reply {
+            status: 206,
+            content_type: "application/octet-stream",
+            headers: vec![(
+                "Content-Range",
+                format!("bytes {}-{}/{}", start, contents.len() - 1, contents.len()),
+            )],
+            body: contents[start..].to_vec(),
+        },
+        None => Reply {
+            status: 200,
+            content_type: "application/octet-stream",
+            headers: Vec::new(),
+            body: contents.to_vec(),
+        },
+    }
+}
+
+/// Queue a new job for the submitted tool, or reject a `runtime_` over
+/// [`MAX_RUNTIME_HOURS`] with a `paramError` like NSG's form validation
+fn submit(request: &Request, base: &str, state: &mut State) -> Reply {
+    let Some(tool) = form_field(&request.body, "tool") else {
+        return Reply::error(400, "Form validation error: tool is required");
+    };
+    if let Some(hours) =
+        form_field(&request.body, "vparam.runtime_").and_then(|v| v.parse::<f64>().ok())
+    {
+        if hours > MAX_RUNTIME_HOURS {
+            return Reply::xml(
+                400,
+                format!(
+                    "<error><displayMessage>Form validation error.</displayMessage>\
+                     <message>Validation Error</message><code>5</code>\
+                     <paramError><param>runtime_</param>\
+                     <error>Maximum Hours to Run must be less than {}</error></paramError></error>",
+                    MAX_RUNTIME_HOURS
+                ),
+            );
+        }
+    }
+
+    state.submitted += 1;
+    let mut job = MockJob::new(
+        &format!("NGBW-JOB-{}-SUBMITTED{:04}", tool, state.submitted),
+        JobStage::Queue,
+    );
+    if let Some(name) = form_field(&request.body, "metadata.clientJobName") {
+        job = job.metadata("clientJobName", &name);
+    }
+    let status = job_status(base, &job);
+    state.jobs.push(job);
+    Reply::xml(200, status)
+}
+
+/// Value of the multipart form field `name`
+fn form_field(body: &[u8], name: &str) -> Option<String> {
+    let body = String::from_utf8_lossy(body);
+    let marker = format!("name=\"{}\"", name);
+    let start = body.find(&marker)? + marker.len();
+    let value = &body[start..];
+    let value = &value[value.find("\r\n\r\n")? + 4..];
+    Some(value[..value.find("\r\n")?].to_string())
+}
+
+fn link(url: &str, rel: &str, title: &str) -> String {
+    format!(
+        "<url>{}</url><rel>{}</rel><title>{}</title>",
+        escape(url),
+        rel,
+        escape(title)
+    )
+}
+
+fn job_list(base: &str, jobs: &[MockJob]) -> String {
+    let mut xml = String::from("<joblist><title>Submitted Jobs</title><jobs>");
+    for job in jobs {
+        xml.push_str(&format!(
+            "<jobstatus><selfUri>{}</selfUri></jobstatus>",
+            link(
+                &format!("{}/job/{}/{}", base, MOCK_USERNAME, job.id),
+                "jobstatus",
+                &job.id
+            )
+        ));
+    }
+    xml.push_str("</jobs></joblist>");
+    xml
+}
+
+fn job_status(base: &str, job: &MockJob) -> String {
+    let url = format!("{}/job/{}/{}", base, MOCK_USERNAME, job.id);
+    let terminal = job.stage.is_terminal();
+
+    let mut messages = format!(
+        "<message><timestamp>{}</timestamp><stage>QUEUE</stage><text>Added to cipres run queue.</text></message>",
+        job.date_submitted
+    );
+    if job.stage != JobStage::Queue {
+        messages.push_str(&format!(
+            "<message><timestamp>{}</timestamp><stage>{}</stage><text>{}</text></message>",
+            job.date_submitted,
+            job.stage,
+            if job.failed {
+                "Job failed"
+            } else {
+                "Job reached this stage"
+            }
+        ));
+    }
+
+    let metadata: String = job
+        .metadata
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "<entry><key>{}</key><value>{}</value></entry>",
+                escape(key),
+                escape(value)
+            )
+        })
+        .collect();
+
+    format!(
+        "<jobstatus><selfUri>{}</selfUri><jobHandle>{}</jobHandle><jobStage>{}</jobStage>\
+         <terminalStage>{}</terminalStage><failed>{}</failed>\
+         <metadata>{}</metadata><dateSubmitted>{}</dateSubmitted>\
+         <resultsUri>{}</resultsUri><workingDirUri>{}</workingDirUri>\
+         <messages>{}</messages><minPollIntervalSeconds>60</minPollIntervalSeconds></jobstatus>",
+        link(&url, "jobstatus", &job.id),
+        job.id,
+        job.stage,
+        terminal,
+        job.failed,
+        metadata,
+        job.date_submitted,
+        link(&format!("{}/output", url), "results", "Job Results"),
+        link(
+            &format!("{}/workingdir", url),
+            "workingdir",
+            "Job Working Directory"
+        ),
+        messages
+    )
+}
+
+fn file_list(base: &str, job: &MockJob, kind: &str, files: &[(String, Vec<u8>)]) -> String {
+    let mut xml = String::from("<results><jobfiles>");
+    for (name, contents) in files {
+        xml.push_str(&format!(
+            "<jobfile><downloadUri>{}</downloadUri><jobHandle>{}</jobHandle>\
+             <filename>{}</filename><length>{}</length>\
+             <parameterName>outputfile</parameterName><outputDocumentId>1</outputDocumentId></jobfile>",
+            link(
+                &format!(
+                    "{}/job/{}/{}/{}/{}",
+                    base,
+                    MOCK_USERNAME,
+                    job.id,
+                    kind,
+                    percent_encode(name)
+                ),
+                "fileDownload",
+                name
+            ),
+            job.id,
+            escape(name),
+            contents.len()
+        ));
+    }
+    xml.push_str("</jobfiles></results>");
+    xml
+}
+
+fn tool_list(base: &str) -> String {
+    let mut xml = String::from("<tools>");
+    for (id, name) in [
+        ("PY_EXPANSE", "Python on Expanse"),
+        ("NEURON_EXPANSE", "NEURON on Expanse"),
+    ] {
+        xml.push_str(&format!(
+            "<tool><toolId>{}</toolId><toolName>{}</toolName><selfUri>{}</selfUri></tool>",
+            id,
+            name,
+            link(&format!("{}/tool/{}", base, id), "tool", id)
+        ));
+    }
+    xml.push_str("</tools>");
+    xml
+}
+
+const PY_EXPANSE_PISE: &str = r#"<?xml version="1.0"?>
+<pise><head><title>PY_EXPANSE</title><description>Python on Expanse</description></head>
+<command>python</command><parameters>
+<parameter type="InFile" ismandatory="1"><name>infile_</name><attributes><prompt>Input zip</prompt></attributes></parameter>
+<parameter type="Float" ismandatory="1"><name>runtime_</name><attributes><prompt>Maximum Hours to Run</prompt><vdef><value>0.5</value></vdef><scalemin><value>0.1</value></scalemin><scalemax><value>48</value></scalemax></attributes></parameter>
+<parameter type="Integer"><name>number_nodes_</name><attributes><prompt>Number of nodes</prompt><vdef><value>1</value></vdef><scalemin><value>1</value></scalemin><scalemax><value>2</value></scalemax></attributes></parameter>
+<parameter type="Excl"><name>pythonoption_</name><attributes><prompt>Python version</prompt><vdef><value>3</value></vdef><vlist><value>2</value><label>Python 2</label><value>3</value><label>Python 3</label></vlist></attributes></parameter>
+</parameters></pise>"#;
+
+/// A small `output.tar.gz` with one file, `results/summary.txt`
+fn gzip_fixture() -> Vec<u8> {
+    let mut archive = tar::Builder::new(Vec::new());
+    let contents = b"all done\n";
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    let _ = archive.append_data(&mut header, "results/summary.txt", &contents[..]);
+    let tar = archive.into_inner().unwrap_or_default();
+
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let _ = gzip.write_all(&tar);
+    gzip.finish().unwrap_or_default()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn percent_encode(name: &str) -> String {
+    name.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(b) = text
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
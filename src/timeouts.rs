@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+/// Wait for a response used when `--timeout` is not given
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest wait for the connection to NSG to be set up
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Longest a download may go without receiving data
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Slowest upload speed, in bytes per second, that a submission is given
+/// time for before it's abandoned
+pub const MIN_UPLOAD_RATE: u64 = 128 * 1024;
+
+/// How long requests may take before they're given up on.
+///
+/// Connecting, API calls, uploads and downloads are limited separately, so
+/// a multi-gigabyte input archive isn't cut off by the limit meant for a
+/// status check. Downloads have no deadline as a whole, only one on going
+/// quiet, so a large result archive on a slow link isn't cut off either.
+#[derive(Debug, Clone, Copy)]
+pub struct Timeouts {
+    /// Setting up the TCP/TLS connection
+    pub connect: Duration,
+    /// An API call, from sending it to the end of NSG's answer; `None`
+    /// waits forever
+    pub request: Option<Duration>,
+    /// A download going without data, however long it takes in all;
+    /// `None` waits forever
+    pub read: Option<Duration>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: DEFAULT_CONNECT_TIMEOUT,
+            request: Some(DEFAULT_TIMEOUT),
+            read: Some(DEFAULT_READ_TIMEOUT),
+        }
+    }
+}
+
+impl Timeouts {
+    /// Timeouts taken from a number of seconds for API calls, where 0 means
+    /// no limit on them (connecting and downloads are still limited)
+    pub fn from_secs(secs: u64) -> Self {
+        Self {
+            request: limit(secs),
+            ..Self::default()
+        }
+    }
+
+    /// Time allowed for sending `bytes` of input and getting NSG's answer:
+    /// the request timeout plus the upload at [`MIN_UPLOAD_RATE`]
+    pub fn upload(&self, bytes: u64) -> Option<Duration> {
+        self.request
+            .map(|request| request + Duration::from_secs(bytes.div_ceil(MIN_UPLOAD_RATE)))
+    }
+}
+
+/// A limit of `secs` seconds, where 0 means waiting forever
+pub fn limit(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
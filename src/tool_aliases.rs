@@ -0,0 +1,24 @@
+//! Short names for NSG tools, so `--tool neuron` keeps working when NSG
+//! moves a tool to another machine and its ID changes
+
+use std::collections::BTreeMap;
+
+/// Aliases every `nsg` knows: the name and the tool ID it stands for.
+/// `[tool_aliases]` in `config.toml` adds to these and overrides them.
+pub const BUILTIN: &[(&str, &str)] = &[
+    ("neuron", "NEURON_EXPANSE"),
+    ("netpyne", "NETPYNE_EXPANSE"),
+    ("python", "PY_EXPANSE"),
+];
+
+/// The tool ID `tool` stands for, looking it up (ignoring case) in `custom`
+/// and then the built-in aliases. Anything else is taken as a tool ID.
+pub fn resolve(tool: &str, custom: &BTreeMap<String, String>) -> String {
+    custom
+        .iter()
+        .map(|(alias, id)| (alias.as_str(), id.as_str()))
+        .chain(BUILTIN.iter().copied())
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(tool))
+        .map_or(tool, |(_, id)| id)
+        .to_string()
+}
@@ -0,0 +1,118 @@
+use crate::api::NsgApi;
+use crate::config::config_dir;
+use crate::models::{parse_tool_parameters, ToolParameter};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+const TOOL_CACHE_DIR: &str = "cache/tools";
+const TOOL_LIST_FILE: &str = "cache/tools.txt";
+const TOOL_CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Parameters of `tool`, served from `~/.nsg/cache/tools/<TOOL>.xml` when the
+/// cached description is less than a day old.
+///
+/// If NSG can't be reached, a stale cached copy is used rather than failing.
+pub fn tool_parameters(client: &dyn NsgApi, tool: &str) -> Result<Vec<ToolParameter>> {
+    let path = cache_path(tool)?;
+
+    if is_fresh(&path) {
+        if let Ok(xml) = fs::read_to_string(&path) {
+            return Ok(parse_tool_parameters(&xml)?);
+        }
+    }
+
+    match client.get_tool_xml(tool) {
+        Ok(xml) => {
+            let params = parse_tool_parameters(&xml)?;
+            // Caching is best-effort; a read-only home directory shouldn't
+            // prevent submission.
+            let _ = store(&path, &xml);
+            Ok(params)
+        }
+        Err(e) => match fs::read_to_string(&path) {
+            Ok(xml) => Ok(parse_tool_parameters(&xml)?),
+            Err(_) => Err(e.into()),
+        },
+    }
+}
+
+/// Parameters of `tool` from the cached description only, however old,
+/// without contacting NSG
+pub fn cached_tool_parameters(tool: &str) -> Result<Vec<ToolParameter>> {
+    let path = cache_path(tool)?;
+    let xml = fs::read_to_string(&path).with_context(|| {
+        format!(
+            "No cached description of {} (`nsg tools info {}` fetches it)",
+            tool, tool
+        )
+    })?;
+    Ok(parse_tool_parameters(&xml)?)
+}
+
+/// IDs of the tools NSG offers, served from `~/.nsg/cache/tools.txt` when
+/// the cached list is less than a day old, unless `refresh`.
+///
+/// If NSG can't be reached, a stale cached list is used rather than failing.
+pub fn tool_ids(client: &dyn NsgApi, refresh: bool) -> Result<Vec<String>> {
+    let path = config_dir()?.join(TOOL_LIST_FILE);
+
+    if !refresh && is_fresh(&path) {
+        if let Ok(list) = fs::read_to_string(&path) {
+            return Ok(parse_ids(&list));
+        }
+    }
+
+    match client.list_tools() {
+        Ok(tools) => {
+            let ids: Vec<String> = tools.into_iter().map(|tool| tool.tool_id).collect();
+            let _ = store(&path, &format!("{}\n", ids.join("\n")));
+            Ok(ids)
+        }
+        Err(e) => match fs::read_to_string(&path) {
+            Ok(list) => Ok(parse_ids(&list)),
+            Err(_) => Err(e.into()),
+        },
+    }
+}
+
+/// IDs from the cached tool list only, however old, without contacting NSG
+pub fn cached_tool_ids() -> Result<Vec<String>> {
+    let path = config_dir()?.join(TOOL_LIST_FILE);
+    let list = fs::read_to_string(&path).context("No cached tool list")?;
+    Ok(parse_ids(&list))
+}
+
+fn parse_ids(list: &str) -> Vec<String> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn cache_path(tool: &str) -> Result<PathBuf> {
+    if tool.is_empty() || !tool.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        anyhow::bail!("Invalid tool name: {}", tool);
+    }
+    Ok(config_dir()?
+        .join(TOOL_CACHE_DIR)
+        .join(format!("{}.xml", tool)))
+}
+
+fn is_fresh(path: &PathBuf) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < TOOL_CACHE_MAX_AGE)
+}
+
+fn store(path: &PathBuf, xml: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+    }
+    fs::write(path, xml).with_context(|| format!("Failed to write {}", path.display()))
+}
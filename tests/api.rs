@@ -0,0 +1,155 @@
+//! The provided `NsgApi` methods, on an implementation that isn't `NsgClient`
+
+use nsg_cli::client::{DownloadEvent, DownloadOptions};
+use nsg_cli::error::Result;
+use nsg_cli::filenames::FileFilter;
+use nsg_cli::models::{JobStage, JobStatus, JobSummary, OutputFile, ToolInfo};
+use nsg_cli::{NsgApi, NsgError};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+type TestResult = std::result::Result<(), Box<dyn std::error::Error>>;
+
+const JOB: &str = "NGBW-JOB-PY_EXPANSE-FAKE";
+
+/// A finished job whose output files are served from memory
+struct FakeNsg {
+    outputs: Vec<(&'static str, &'static str)>,
+}
+
+impl FakeNsg {
+    fn new() -> Self {
+        Self {
+            outputs: vec![("stdout.txt", "fake output\n"), ("stderr.txt", "")],
+        }
+    }
+
+    fn unused<T>() -> Result<T> {
+        Err(NsgError::Invalid("not used by this test".into()))
+    }
+}
+
+impl NsgApi for FakeNsg {
+    fn test_connection(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn list_jobs(&self) -> Result<Vec<JobSummary>> {
+        Ok(Vec::new())
+    }
+
+    fn get_job_status(&self, job_url_or_id: &str) -> Result<JobStatus> {
+        if job_url_or_id != JOB {
+            return Err(NsgError::NotFound {
+                message: format!("Job Not Found Error: {}", job_url_or_id),
+            });
+        }
+        Ok(JobStatus {
+            job_id: JOB.to_string(),
+            job_stage: JobStage::Completed,
+            failed: false,
+            date_submitted: None,
+            self_uri: format!("fake://job/{}", JOB),
+            results_uri: Some(format!("fake://job/{}/output", JOB)),
+            working_dir_uri: None,
+            terminal_stage: true,
+            messages: Vec::new(),
+            metadata: Vec::new(),
+        })
+    }
+
+    fn delete_job(&self, _job_url_or_id: &str) -> Result<()> {
+        Self::unused()
+    }
+
+    fn submit_job_with_inputs(
+        &self,
+        _tool: &str,
+        _inputs: &[(String, PathBuf)],
+        _params: &[(String, String)],
+    ) -> Result<JobStatus> {
+        Self::unused()
+    }
+
+    fn list_tools(&self) -> Result<Vec<ToolInfo>> {
+        Ok(Vec::new())
+    }
+
+    fn get_tool_xml(&self, _tool: &str) -> Result<String> {
+        Self::unused()
+    }
+
+    fn list_working_dir(&self, _job_status: &JobStatus) -> Result<Vec<OutputFile>> {
+        Self::unused()
+    }
+
+    fn get_output_files(&self, _job_status: &JobStatus) -> Result<Vec<OutputFile>> {
+        Ok(self
+            .outputs
+            .iter()
+            .map(|(name, contents)| OutputFile {
+                filename: name.to_string(),
+                download_uri: format!("fake://job/{}/output/{}", JOB, name),
+                size: Some(contents.len() as u64),
+            })
+            .collect())
+    }
+
+    fn download_file(&self, _file: &OutputFile, _output_path: &Path) -> Result<u64> {
+        Self::unused()
+    }
+
+    fn open_output_file<'a>(&'a self, file: &OutputFile) -> Result<Box<dyn Read + Send + 'a>> {
+        let (_, contents) = self
+            .outputs
+            .iter()
+            .find(|(name, _)| *name == file.filename)
+            .ok_or_else(|| NsgError::NotFound {
+                message: format!("No such output file: {}", file.filename),
+            })?;
+        Ok(Box::new(contents.as_bytes()))
+    }
+}
+
+#[test]
+fn download_results_reads_each_output_file() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let mut events = Vec::new();
+
+    let files = FakeNsg::new().download_results(
+        JOB,
+        dir.path(),
+        &DownloadOptions::default(),
+        &mut |event| events.push(event),
+    )?;
+
+    assert_eq!(files.len(), 2);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("stdout.txt"))?,
+        "fake output\n"
+    );
+    assert_eq!(files[0].size, 12);
+    assert!(matches!(
+        events.last(),
+        Some(DownloadEvent::AllDone {
+            files: 2,
+            bytes: 12
+        })
+    ));
+    Ok(())
+}
+
+#[test]
+fn download_results_applies_the_filter() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let options = DownloadOptions {
+        filter: FileFilter::new(&["stdout*".to_string()], &[])?,
+        ..Default::default()
+    };
+
+    let files = FakeNsg::new().download_results(JOB, dir.path(), &options, &mut |_| {})?;
+
+    assert_eq!(files.len(), 1);
+    assert!(!dir.path().join("stderr.txt").exists());
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! The `nsg` binary against the mock NSG server, each run with its own
+//! `HOME` so the job database and credentials stay inside a temporary
+//! directory
+
+use nsg_cli::test_util::{MockNsg, COMPLETED_JOB};
+use std::path::Path;
+use std::process::{Command, Output};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+fn nsg(server: &MockNsg, home: &Path, args: &[&str]) -> std::io::Result<Output> {
+    Command::new(env!("CARGO_BIN_EXE_nsg"))
+        .args(args)
+        .envs(server.env())
+        .env("HOME", home)
+        .env_remove("NSG_CREDENTIALS_FILE")
+        .env_remove("NSG_AGENT_SOCK")
+        .output()
+}
+
+#[test]
+fn download_writes_files_and_manifest() -> TestResult {
+    let server = MockNsg::start()?;
+    let home = tempfile::tempdir()?;
+    let output = home.path().join("results");
+
+    let run = nsg(
+        &server,
+        home.path(),
+        &[
+            "--quiet",
+            "download",
+            COMPLETED_JOB,
+            "--output",
+            output.to_str().ok_or("non-UTF-8 temp path")?,
+        ],
+    )?;
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+
+    let dir = output.join(COMPLETED_JOB);
+    assert_eq!(
+        std::fs::read_to_string(dir.join("STDOUT"))?,
+        "Hello from NSG\n"
+    );
+    let manifest = std::fs::read_to_string(dir.join("MANIFEST.sha256"))?;
+    assert!(manifest.contains("STDOUT"));
+    assert!(home.path().join(".nsg").join("jobs.db").exists());
+    Ok(())
+}
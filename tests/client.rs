@@ -0,0 +1,163 @@
+//! `NsgClient` against the mock NSG server from `nsg_cli::test_util`
+
+use nsg_cli::client::{ClientOptions, DownloadOptions};
+use nsg_cli::models::JobStage;
+use nsg_cli::retry::RetryPolicy;
+use nsg_cli::test_util::{MockJob, MockNsg, COMPLETED_JOB, RUNNING_JOB};
+use nsg_cli::{NsgClient, NsgError};
+use std::path::PathBuf;
+use std::time::Duration;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+/// A stand-in input archive; the mock doesn't look inside it
+fn input_zip(dir: &tempfile::TempDir) -> std::io::Result<PathBuf> {
+    let path = dir.path().join("job.zip");
+    std::fs::write(&path, b"PK\x05\x06\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0")?;
+    Ok(path)
+}
+
+#[test]
+fn submit_queues_a_job() -> TestResult {
+    let nsg = MockNsg::start()?;
+    let dir = tempfile::tempdir()?;
+    let params = [("runtime_".to_string(), "0.5".to_string())];
+
+    let status = nsg
+        .client()?
+        .submit_job_with_params(&input_zip(&dir)?, "PY_EXPANSE", &params)?;
+
+    assert_eq!(status.job_stage, JobStage::Queue);
+    assert!(nsg.job(&status.job_id).is_some());
+    assert!(nsg.requests().iter().any(|r| r.starts_with("POST /")));
+    Ok(())
+}
+
+#[test]
+fn submit_reports_rejected_parameters() -> TestResult {
+    let nsg = MockNsg::start()?;
+    let dir = tempfile::tempdir()?;
+    let params = [("runtime_".to_string(), "72".to_string())];
+
+    let err = nsg
+        .client()?
+        .submit_job_with_params(&input_zip(&dir)?, "PY_EXPANSE", &params)
+        .unwrap_err();
+
+    match err {
+        NsgError::InvalidParams { errors, .. } => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].param, "runtime_");
+        }
+        other => panic!("expected InvalidParams, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn status_follows_the_job() -> TestResult {
+    let nsg = MockNsg::start()?;
+    let client = nsg.client()?;
+
+    let status = client.get_job_status(COMPLETED_JOB)?;
+    assert!(status.terminal_stage);
+    assert!(!status.failed);
+
+    assert!(!client.get_job_status(RUNNING_JOB)?.terminal_stage);
+    nsg.set_stage(RUNNING_JOB, JobStage::Completed);
+    assert!(client.get_job_status(RUNNING_JOB)?.terminal_stage);
+
+    let missing = client.get_job_status("NGBW-JOB-PY_EXPANSE-MISSING");
+    assert!(matches!(missing, Err(NsgError::NotFound { .. })));
+    Ok(())
+}
+
+#[test]
+fn download_fetches_every_output() -> TestResult {
+    let nsg = MockNsg::start()?;
+    let dir = tempfile::tempdir()?;
+
+    let files = nsg
+        .client()?
+        .download_results(COMPLETED_JOB, dir.path(), |_| {})?;
+
+    assert_eq!(files.len(), 3);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("STDOUT"))?,
+        "Hello from NSG\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn download_resumes_a_partial_file_with_range() -> TestResult {
+    let nsg = MockNsg::empty()?;
+    nsg.add_job(
+        MockJob::new("NGBW-JOB-PY_EXPANSE-RESUME", JobStage::Completed)
+            .output("result.txt", "0123456789"),
+    );
+    let dir = tempfile::tempdir()?;
+    // Different from the server's first bytes, so the result shows that
+    // only the rest was fetched
+    std::fs::write(dir.path().join("result.txt.part"), "abcd")?;
+
+    let options = DownloadOptions {
+        resume: true,
+        ..Default::default()
+    };
+    let files = nsg.client()?.download_results_with(
+        "NGBW-JOB-PY_EXPANSE-RESUME",
+        dir.path(),
+        &options,
+        |_| {},
+    )?;
+
+    assert_eq!(files[0].size, 10);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("result.txt"))?,
+        "abcd456789"
+    );
+    assert!(!dir.path().join("result.txt.part").exists());
+    Ok(())
+}
+
+#[test]
+fn transient_failures_are_retried() -> TestResult {
+    let nsg = MockNsg::start()?;
+    let client = NsgClient::with_options(
+        nsg.credentials(),
+        ClientOptions {
+            base_url: Some(nsg.base_url()),
+            rate_limit: Some(0.0),
+            retry: RetryPolicy {
+                retries: 2,
+                backoff: Duration::from_millis(10),
+                jitter: false,
+            },
+            ..Default::default()
+        },
+    )?;
+
+    nsg.fail_next(503);
+    nsg.fail_next(502);
+    assert!(client.get_job_status(COMPLETED_JOB)?.terminal_stage);
+    assert_eq!(nsg.requests().len(), 3);
+
+    for _ in 0..3 {
+        nsg.fail_next(503);
+    }
+    assert!(client.get_job_status(COMPLETED_JOB).is_err());
+    Ok(())
+}
+
+#[test]
+fn failures_reach_a_client_without_retries() -> TestResult {
+    let nsg = MockNsg::start()?;
+    let client = nsg.client()?;
+
+    nsg.fail_next(503);
+    assert!(client.get_job_status(COMPLETED_JOB).is_err());
+    assert!(client.get_job_status(COMPLETED_JOB).is_ok());
+    assert_eq!(nsg.requests().len(), 2);
+    Ok(())
+}